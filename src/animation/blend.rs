@@ -0,0 +1,238 @@
+use glam::*;
+
+use super::{BoneIndex, Skeleton, SkeletonPose};
+use crate::transform::Transform;
+
+/// Per bone weight used to restrict a blend to part of the skeleton
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoneMask {
+    pub weights: Vec<f32>,
+}
+
+impl BoneMask {
+    pub fn all(bone_count: usize) -> Self {
+        Self {
+            weights: vec![1.0; bone_count],
+        }
+    }
+
+    pub fn none(bone_count: usize) -> Self {
+        Self {
+            weights: vec![0.0; bone_count],
+        }
+    }
+
+    /// Mask containing `roots` and all their descendants, e.g. the upper body from the spine
+    pub fn from_hierarchy(skeleton: &Skeleton, roots: &[BoneIndex]) -> Self {
+        let weights = (0..skeleton.bone_count())
+            .map(|bone| {
+                if roots.iter().any(|root| skeleton.is_ancestor(*root, bone)) {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        Self { weights }
+    }
+
+    pub fn set(&mut self, bone: BoneIndex, weight: f32) {
+        self.weights[bone] = weight;
+    }
+
+    /// Bones outside the mask have a weight of zero
+    pub fn weight(&self, bone: BoneIndex) -> f32 {
+        self.weights.get(bone).copied().unwrap_or(0.0)
+    }
+}
+
+fn mask_weight(mask: Option<&BoneMask>, bone: BoneIndex) -> f32 {
+    match mask {
+        Some(mask) => mask.weight(bone),
+        None => 1.0,
+    }
+}
+
+impl SkeletonPose {
+    /// Moves every bone towards `other` by `weight`, scaled by the mask of the bone
+    pub fn blend(&mut self, other: &SkeletonPose, weight: f32, mask: Option<&BoneMask>) {
+        for (bone, (transform, target)) in self.bones.iter_mut().zip(other.bones.iter()).enumerate()
+        {
+            let weight = weight * mask_weight(mask, bone);
+            if weight <= 0.0 {
+                continue;
+            }
+            *transform = transform.lerp(target, weight.min(1.0));
+        }
+    }
+
+    /// Weighted average of several poses, the weights don't need to be normalized.\
+    /// Returns `false` when all the weights are zero and `self` was left untouched.
+    pub fn blend_weighted<'a>(
+        &mut self,
+        poses: impl IntoIterator<Item = (&'a SkeletonPose, f32)>,
+    ) -> bool {
+        let mut accumulated_weight = 0.0;
+        for (pose, weight) in poses {
+            if weight <= 0.0 {
+                continue;
+            }
+            if accumulated_weight == 0.0 {
+                self.bones.clone_from(&pose.bones);
+                accumulated_weight = weight;
+            } else {
+                accumulated_weight += weight;
+                self.blend(pose, weight / accumulated_weight, None);
+            }
+        }
+        accumulated_weight > 0.0
+    }
+
+    /// Difference between this pose and `reference`, applied on top of other poses with
+    /// `apply_additive`
+    pub fn additive_difference(&self, reference: &SkeletonPose) -> SkeletonPose {
+        let bones = self
+            .bones
+            .iter()
+            .zip(reference.bones.iter())
+            .map(|(transform, reference)| Transform {
+                translation: transform.translation - reference.translation,
+                rotation: (reference.rotation.inverse() * transform.rotation).normalize(),
+                scale: transform.scale / reference.scale,
+            })
+            .collect();
+        SkeletonPose { bones }
+    }
+
+    pub fn apply_additive(
+        &mut self,
+        additive: &SkeletonPose,
+        weight: f32,
+        mask: Option<&BoneMask>,
+    ) {
+        for (bone, (transform, delta)) in
+            self.bones.iter_mut().zip(additive.bones.iter()).enumerate()
+        {
+            let weight = weight * mask_weight(mask, bone);
+            if weight <= 0.0 {
+                continue;
+            }
+            transform.translation += delta.translation * weight;
+            transform.rotation =
+                (transform.rotation * Quat::IDENTITY.slerp(delta.rotation, weight)).normalize();
+            transform.scale *= Vec3::ONE.lerp(delta.scale, weight);
+        }
+    }
+}
+
+/// Index of a clip inside an `AnimationMixer`
+pub type ClipIndex = usize;
+
+/// Clips placed along one parameter, e.g. walk at speed 1.5 and run at speed 4
+#[derive(Debug, Clone, Default)]
+pub struct BlendSpace1D {
+    points: Vec<(f32, ClipIndex)>,
+}
+
+impl BlendSpace1D {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_point(mut self, position: f32, clip: ClipIndex) -> Self {
+        self.add_point(position, clip);
+        self
+    }
+
+    pub fn add_point(&mut self, position: f32, clip: ClipIndex) {
+        let index = self.points.partition_point(|(point, _)| *point <= position);
+        self.points.insert(index, (position, clip));
+    }
+
+    pub fn points(&self) -> &[(f32, ClipIndex)] {
+        &self.points
+    }
+
+    /// Weights of the two clips around `parameter`, the parameter is clamped to the range
+    pub fn weights(&self, parameter: f32) -> Vec<(ClipIndex, f32)> {
+        if self.points.is_empty() {
+            return Vec::new();
+        }
+        let first = self.points[0];
+        let last = self.points[self.points.len() - 1];
+        if parameter <= first.0 {
+            return vec![(first.1, 1.0)];
+        }
+        if parameter >= last.0 {
+            return vec![(last.1, 1.0)];
+        }
+        let next = self
+            .points
+            .partition_point(|(point, _)| *point <= parameter);
+        let (start, start_clip) = self.points[next - 1];
+        let (end, end_clip) = self.points[next];
+        let t = (parameter - start) / (end - start);
+        vec![(start_clip, 1.0 - t), (end_clip, t)]
+    }
+}
+
+/// Clips placed on a plane, e.g. strafing directions.\
+/// Weights use gradient band interpolation, so any point layout is supported.
+#[derive(Debug, Clone, Default)]
+pub struct BlendSpace2D {
+    points: Vec<(Vec2, ClipIndex)>,
+}
+
+impl BlendSpace2D {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_point(mut self, position: Vec2, clip: ClipIndex) -> Self {
+        self.add_point(position, clip);
+        self
+    }
+
+    pub fn add_point(&mut self, position: Vec2, clip: ClipIndex) {
+        self.points.push((position, clip));
+    }
+
+    pub fn points(&self) -> &[(Vec2, ClipIndex)] {
+        &self.points
+    }
+
+    /// Normalized weights, clips without influence are not returned
+    pub fn weights(&self, parameter: Vec2) -> Vec<(ClipIndex, f32)> {
+        if self.points.len() == 1 {
+            return vec![(self.points[0].1, 1.0)];
+        }
+        let mut weights = Vec::with_capacity(self.points.len());
+        let mut total_weight = 0.0;
+        for (i, (point_i, clip)) in self.points.iter().enumerate() {
+            let to_parameter = parameter - *point_i;
+            let mut weight = 1.0_f32;
+            for (j, (point_j, _)) in self.points.iter().enumerate() {
+                if i == j {
+                    continue;
+                }
+                let to_point = *point_j - *point_i;
+                let length_squared = to_point.length_squared();
+                if length_squared <= f32::EPSILON {
+                    continue;
+                }
+                let band = 1.0 - to_parameter.dot(to_point) / length_squared;
+                weight = weight.min(band.clamp(0.0, 1.0));
+            }
+            if weight > 0.0 {
+                weights.push((*clip, weight));
+                total_weight += weight;
+            }
+        }
+        if total_weight > 0.0 {
+            for (_, weight) in weights.iter_mut() {
+                *weight /= total_weight;
+            }
+        }
+        weights
+    }
+}
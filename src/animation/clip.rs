@@ -0,0 +1,341 @@
+use glam::*;
+use serde::{Deserialize, Serialize};
+
+use super::{AnimationEvent, BoneIndex, SkeletonPose};
+use crate::time::Time;
+use crate::transform::Transform;
+
+/// Keyframe interpolation, following the glTF sampler semantics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    /// Hermite spline, every keyframe stores `[in_tangent, value, out_tangent]`
+    CubicSpline,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PlaybackMode {
+    Loop,
+    Clamp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationTarget {
+    Bone(BoneIndex),
+    /// Scene node, the index meaning is defined by the owner of the clip
+    Node(usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChannelValues {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+    /// Morph target weights, `target_count` values per keyframe
+    Weights {
+        target_count: usize,
+        values: Vec<f32>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationChannel {
+    pub target: AnimationTarget,
+    pub interpolation: Interpolation,
+    /// Keyframe times in seconds, in ascending order
+    pub times: Vec<f32>,
+    pub values: ChannelValues,
+}
+
+trait Keyframe: Copy {
+    fn interpolate_linear(a: Self, b: Self, t: f32) -> Self;
+    fn add(self, other: Self) -> Self;
+    fn scale(self, value: f32) -> Self;
+    fn finish(self) -> Self {
+        self
+    }
+}
+
+impl Keyframe for Vec3 {
+    fn interpolate_linear(a: Self, b: Self, t: f32) -> Self {
+        a.lerp(b, t)
+    }
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+    fn scale(self, value: f32) -> Self {
+        self * value
+    }
+}
+
+impl Keyframe for f32 {
+    fn interpolate_linear(a: Self, b: Self, t: f32) -> Self {
+        a + (b - a) * t
+    }
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+    fn scale(self, value: f32) -> Self {
+        self * value
+    }
+}
+
+impl Keyframe for Quat {
+    fn interpolate_linear(a: Self, b: Self, t: f32) -> Self {
+        a.slerp(b, t)
+    }
+    fn add(self, other: Self) -> Self {
+        self + other
+    }
+    fn scale(self, value: f32) -> Self {
+        self * value
+    }
+    fn finish(self) -> Self {
+        self.normalize()
+    }
+}
+
+/// Index of the keyframe before `time` and the normalized position between it and the next one
+fn find_keyframe(times: &[f32], time: f32) -> (usize, f32) {
+    let last = times.len() - 1;
+    if time <= times[0] {
+        return (0, 0.0);
+    }
+    if time >= times[last] {
+        return (last, 0.0);
+    }
+    let next = times.partition_point(|key_time| *key_time <= time);
+    let previous = next - 1;
+    let key_duration = times[next] - times[previous];
+    let t = if key_duration > 0.0 {
+        (time - times[previous]) / key_duration
+    } else {
+        0.0
+    };
+    (previous, t)
+}
+
+fn sample_keyframes<T: Keyframe>(
+    times: &[f32],
+    values: &[T],
+    interpolation: Interpolation,
+    time: f32,
+) -> Option<T> {
+    if times.is_empty() {
+        return None;
+    }
+    let (key, t) = find_keyframe(times, time);
+    let next_key = (key + 1).min(times.len() - 1);
+    match interpolation {
+        Interpolation::Step => values.get(key).copied(),
+        Interpolation::Linear => {
+            let a = *values.get(key)?;
+            let b = *values.get(next_key)?;
+            Some(T::interpolate_linear(a, b, t).finish())
+        }
+        Interpolation::CubicSpline => {
+            let value = |key: usize| values.get(key * 3 + 1).copied();
+            if key == next_key {
+                return value(key).map(|value| value.finish());
+            }
+            let key_duration = times[next_key] - times[key];
+            let v0 = value(key)?;
+            let out_tangent = values.get(key * 3 + 2)?.scale(key_duration);
+            let v1 = value(next_key)?;
+            let in_tangent = values.get(next_key * 3)?.scale(key_duration);
+
+            let t2 = t * t;
+            let t3 = t2 * t;
+            let result = v0
+                .scale(2.0 * t3 - 3.0 * t2 + 1.0)
+                .add(out_tangent.scale(t3 - 2.0 * t2 + t))
+                .add(v1.scale(-2.0 * t3 + 3.0 * t2))
+                .add(in_tangent.scale(t3 - t2));
+            Some(result.finish())
+        }
+    }
+}
+
+impl AnimationChannel {
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or(0.0)
+    }
+
+    /// Writes the animated property of the channel into `transform`
+    pub fn sample_into(&self, time: f32, transform: &mut Transform) {
+        match &self.values {
+            ChannelValues::Translation(values) => {
+                if let Some(value) = sample_keyframes(&self.times, values, self.interpolation, time) {
+                    transform.translation = value;
+                }
+            }
+            ChannelValues::Rotation(values) => {
+                if let Some(value) = sample_keyframes(&self.times, values, self.interpolation, time) {
+                    transform.rotation = value;
+                }
+            }
+            ChannelValues::Scale(values) => {
+                if let Some(value) = sample_keyframes(&self.times, values, self.interpolation, time) {
+                    transform.scale = value;
+                }
+            }
+            ChannelValues::Weights { .. } => {}
+        }
+    }
+
+    /// Writes the animated morph target weights, does nothing for transform channels
+    pub fn sample_weights(&self, time: f32, weights: &mut Vec<f32>) {
+        if let ChannelValues::Weights {
+            target_count,
+            values,
+        } = &self.values
+        {
+            weights.resize(weights.len().max(*target_count), 0.0);
+            for (target, weight) in weights.iter_mut().enumerate().take(*target_count) {
+                // Both layouts, one value or three cubic spline values per key, are strided
+                let target_values: Vec<f32> =
+                    values.iter().skip(target).step_by(*target_count).copied().collect();
+                if let Some(value) =
+                    sample_keyframes(&self.times, &target_values, self.interpolation, time)
+                {
+                    *weight = value;
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationClip {
+    pub name: Option<String>,
+    pub duration: f32,
+    pub channels: Vec<AnimationChannel>,
+    /// Sorted by time, added with `add_event`
+    pub events: Vec<AnimationEvent>,
+}
+
+impl AnimationClip {
+    /// The duration is the time of the last keyframe of the longest channel
+    pub fn new(name: Option<String>, channels: Vec<AnimationChannel>) -> Self {
+        let duration = channels
+            .iter()
+            .map(|channel| channel.duration())
+            .fold(0.0, f32::max);
+        Self {
+            name,
+            duration,
+            channels,
+            events: Vec::new(),
+        }
+    }
+
+    /// Maps a playback time to a time inside the clip
+    pub fn local_time(&self, time: f32, mode: PlaybackMode) -> f32 {
+        if self.duration <= 0.0 {
+            return 0.0;
+        }
+        match mode {
+            PlaybackMode::Loop => time.rem_euclid(self.duration),
+            PlaybackMode::Clamp => time.clamp(0.0, self.duration),
+        }
+    }
+
+    /// Overwrites the animated properties of the bones in `pose`, properties without a channel
+    /// keep their current value.
+    pub fn sample_pose(&self, time: f32, mode: PlaybackMode, pose: &mut SkeletonPose) {
+        let time = self.local_time(time, mode);
+        for channel in self.channels.iter() {
+            if let AnimationTarget::Bone(bone) = channel.target {
+                if let Some(transform) = pose.get_mut(bone) {
+                    channel.sample_into(time, transform);
+                }
+            }
+        }
+    }
+
+    /// Morph target weights of a node, `weights` grows to the number of animated targets
+    pub fn sample_weights(
+        &self,
+        target: AnimationTarget,
+        time: f32,
+        mode: PlaybackMode,
+        weights: &mut Vec<f32>,
+    ) {
+        let time = self.local_time(time, mode);
+        for channel in self.channels.iter().filter(|channel| channel.target == target) {
+            channel.sample_weights(time, weights);
+        }
+    }
+
+    pub fn sample_transform(
+        &self,
+        target: AnimationTarget,
+        time: f32,
+        mode: PlaybackMode,
+        transform: &mut Transform,
+    ) {
+        let time = self.local_time(time, mode);
+        for channel in self.channels.iter().filter(|channel| channel.target == target) {
+            channel.sample_into(time, transform);
+        }
+    }
+}
+
+/// Playback state of a clip: current time, speed and wrap mode
+#[derive(Debug, Clone, Copy)]
+pub struct ClipPlayback {
+    pub time: f32,
+    pub speed: f32,
+    pub mode: PlaybackMode,
+}
+
+impl ClipPlayback {
+    pub fn new(mode: PlaybackMode) -> Self {
+        Self {
+            time: 0.0,
+            speed: 1.0,
+            mode,
+        }
+    }
+
+    /// Returns the events of the clip crossed during the update
+    pub fn advance<'a>(&mut self, clip: &'a AnimationClip, delta: f32) -> Vec<&'a AnimationEvent> {
+        let previous = self.time;
+        self.time += delta * self.speed;
+        if self.mode == PlaybackMode::Clamp {
+            self.time = self.time.clamp(0.0, clip.duration);
+        }
+        clip.events_between(previous, self.time, self.mode)
+    }
+
+    pub fn update<'a>(&mut self, clip: &'a AnimationClip, time: &Time) -> Vec<&'a AnimationEvent> {
+        self.advance(clip, time.delta_time_seconds().0 as f32)
+    }
+
+    pub fn local_time(&self, clip: &AnimationClip) -> f32 {
+        clip.local_time(self.time, self.mode)
+    }
+
+    /// Position in the clip between 0 and 1
+    pub fn normalized_time(&self, clip: &AnimationClip) -> f32 {
+        if clip.duration <= 0.0 {
+            return 0.0;
+        }
+        self.local_time(clip) / clip.duration
+    }
+
+    pub fn is_finished(&self, clip: &AnimationClip) -> bool {
+        match self.mode {
+            PlaybackMode::Loop => false,
+            PlaybackMode::Clamp => {
+                (self.speed >= 0.0 && self.time >= clip.duration)
+                    || (self.speed < 0.0 && self.time <= 0.0)
+            }
+        }
+    }
+
+    pub fn sample_pose(&self, clip: &AnimationClip, pose: &mut SkeletonPose) {
+        clip.sample_pose(self.time, self.mode, pose);
+    }
+}
@@ -0,0 +1,206 @@
+use glam::*;
+
+use super::{
+    AnimationClip, AnimationTarget, BoneIndex, ClipIndex, PlaybackMode, Skeleton, SkeletonPose,
+};
+use crate::transform::Transform;
+
+/// Named marker on a clip, e.g. a footstep or the frame a hitbox becomes active
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationEvent {
+    pub name: String,
+    /// Time in seconds inside the clip
+    pub time: f32,
+}
+
+/// Event reached by a layer of an `AnimationMixer` during an update
+#[derive(Debug, Clone, PartialEq)]
+pub struct FiredEvent {
+    pub layer: usize,
+    pub clip: ClipIndex,
+    pub name: String,
+    pub time: f32,
+    /// Influence of the clip on the layer, events of blended or fading out clips have a
+    /// weight lower than 1
+    pub weight: f32,
+}
+
+/// Output of `AnimationMixer::advance`
+#[derive(Debug, Clone, Default)]
+pub struct AnimationUpdate {
+    pub events: Vec<FiredEvent>,
+    /// Root bone movement since the previous update, see `RootMotion::delta`
+    pub root_motion: Transform,
+}
+
+impl AnimationClip {
+    /// Adds an event keeping the events sorted by time
+    pub fn add_event(&mut self, name: impl Into<String>, time: f32) {
+        let index = self.events.partition_point(|event| event.time <= time);
+        self.events.insert(
+            index,
+            AnimationEvent {
+                name: name.into(),
+                time,
+            },
+        );
+    }
+
+    pub fn with_event(mut self, name: impl Into<String>, time: f32) -> Self {
+        self.add_event(name, time);
+        self
+    }
+
+    /// Events crossed when playback moves from `from` to `to`, in the order they are reached.\
+    /// Times are playback times, so a looping clip fires its events once per cycle crossed.
+    /// Playing forward the range is `(from, to]`, playing backwards it is `[to, from)`.
+    pub fn events_between(&self, from: f32, to: f32, mode: PlaybackMode) -> Vec<&AnimationEvent> {
+        let mut events = Vec::new();
+        if self.events.is_empty() || from == to {
+            return events;
+        }
+        let (from, to, cycles) = match mode {
+            PlaybackMode::Loop if self.duration > 0.0 => {
+                let first = (from.min(to) / self.duration).floor() as i64;
+                let last = (from.max(to) / self.duration).floor() as i64;
+                (from, to, first..=last)
+            }
+            _ => (
+                from.clamp(0.0, self.duration),
+                to.clamp(0.0, self.duration),
+                0..=0,
+            ),
+        };
+        if from < to {
+            for cycle in cycles {
+                let offset = cycle as f32 * self.duration;
+                events.extend(self.events.iter().filter(|event| {
+                    let time = offset + event.time;
+                    time > from && time <= to
+                }));
+            }
+        } else {
+            for cycle in cycles.rev() {
+                let offset = cycle as f32 * self.duration;
+                events.extend(self.events.iter().rev().filter(|event| {
+                    let time = offset + event.time;
+                    time >= to && time < from
+                }));
+            }
+        }
+        events
+    }
+}
+
+fn delta_inverse(delta: &Transform) -> Transform {
+    let rotation = delta.rotation.inverse();
+    Transform {
+        translation: -(rotation * delta.translation),
+        rotation,
+        scale: Vec3::ONE,
+    }
+}
+
+/// Moves the root bone animation to the character: the movement of the root is returned as
+/// a delta per update and removed from the pose.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RootMotion {
+    pub bone: BoneIndex,
+    /// Rest transform of the root, used for the properties without an animation channel
+    pub bind_transform: Transform,
+    /// Translation axes in the space of the root parent that are extracted, e.g. `(1, 0, 1)`
+    /// keeps the vertical bounce of a walk in the pose
+    pub axes: Vec3,
+    /// Also extracts the rotation of the root relative to its bind rotation
+    pub extract_rotation: bool,
+}
+
+impl RootMotion {
+    pub fn new(skeleton: &Skeleton, bone: BoneIndex) -> Self {
+        let bind_transform = skeleton
+            .bone(bone)
+            .map(|bone| bone.bind_transform)
+            .unwrap_or_default();
+        Self {
+            bone,
+            bind_transform,
+            axes: Vec3::ONE,
+            extract_rotation: false,
+        }
+    }
+
+    pub fn with_axes(mut self, axes: Vec3) -> Self {
+        self.axes = axes;
+        self
+    }
+
+    pub fn with_rotation(mut self, extract_rotation: bool) -> Self {
+        self.extract_rotation = extract_rotation;
+        self
+    }
+
+    /// Frame of the character at a time inside the clip
+    fn motion_frame(&self, clip: &AnimationClip, time: f32) -> Transform {
+        let mut transform = self.bind_transform;
+        clip.sample_transform(
+            AnimationTarget::Bone(self.bone),
+            time,
+            PlaybackMode::Clamp,
+            &mut transform,
+        );
+        let rotation = if self.extract_rotation {
+            (transform.rotation * self.bind_transform.rotation.inverse()).normalize()
+        } else {
+            Quat::IDENTITY
+        };
+        Transform {
+            translation: transform.translation * self.axes,
+            rotation,
+            scale: Vec3::ONE,
+        }
+    }
+
+    fn segment_delta(&self, clip: &AnimationClip, from: f32, to: f32) -> Transform {
+        let start = self.motion_frame(clip, from);
+        let end = self.motion_frame(clip, to);
+        delta_inverse(&start).mul_transform(&end)
+    }
+
+    /// Movement of the root between two playback times, relative to the character at `from`.\
+    /// Looping clips accumulate the motion of every cycle crossed. Apply it with
+    /// `character.mul_transform(&delta)`.
+    pub fn delta(&self, clip: &AnimationClip, from: f32, to: f32, mode: PlaybackMode) -> Transform {
+        if to < from {
+            return delta_inverse(&self.delta(clip, to, from, mode));
+        }
+        if mode == PlaybackMode::Clamp || clip.duration <= 0.0 {
+            let from = from.clamp(0.0, clip.duration);
+            let to = to.clamp(0.0, clip.duration);
+            return self.segment_delta(clip, from, to);
+        }
+        let first = (from / clip.duration).floor() as i64;
+        let last = (to / clip.duration).ceil() as i64;
+        let mut delta = Transform::IDENTITY;
+        for cycle in first..last {
+            let offset = cycle as f32 * clip.duration;
+            let start = (from - offset).max(0.0);
+            let end = (to - offset).min(clip.duration);
+            if end > start {
+                delta = delta.mul_transform(&self.segment_delta(clip, start, end));
+            }
+        }
+        delta
+    }
+
+    /// Resets the extracted properties of the root to the bind pose so the motion is not
+    /// applied twice
+    pub fn remove_from_pose(&self, pose: &mut SkeletonPose) {
+        if let Some(root) = pose.get_mut(self.bone) {
+            root.translation = root.translation * (Vec3::ONE - self.axes)
+                + self.bind_transform.translation * self.axes;
+            if self.extract_rotation {
+                root.rotation = self.bind_transform.rotation;
+            }
+        }
+    }
+}
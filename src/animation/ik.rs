@@ -0,0 +1,323 @@
+use glam::*;
+
+use super::{BoneIndex, Skeleton, SkeletonError, SkeletonPose};
+
+fn bone_position(world_matrices: &[Mat4], bone: BoneIndex) -> Vec3 {
+    world_matrices[bone].w_axis.truncate()
+}
+
+fn bone_rotation(world_matrices: &[Mat4], bone: BoneIndex) -> Quat {
+    let (_, rotation, _) = world_matrices[bone].to_scale_rotation_translation();
+    rotation
+}
+
+/// Applies `rotation` to the bone in world space, keeping the rotation of its parent
+fn rotate_bone_world(
+    skeleton: &Skeleton,
+    pose: &mut SkeletonPose,
+    world_matrices: &[Mat4],
+    bone: BoneIndex,
+    rotation: Quat,
+) {
+    let parent_rotation = match skeleton.parent(bone) {
+        Some(parent) => bone_rotation(world_matrices, parent),
+        None => {
+            let (_, rotation, _) = skeleton.transform.to_scale_rotation_translation();
+            rotation
+        }
+    };
+    let local = &mut pose.bones[bone].rotation;
+    *local = (parent_rotation.inverse() * rotation * parent_rotation * *local).normalize();
+}
+
+/// Rotation that takes the direction `from` to `to`, `None` if any of them is degenerate
+fn rotation_between(from: Vec3, to: Vec3) -> Option<Quat> {
+    let from = from.try_normalize()?;
+    let to = to.try_normalize()?;
+    Some(Quat::from_rotation_arc(from, to))
+}
+
+/// Limits `direction` to a cone of `max_angle` radians around `reference`
+fn limit_direction(direction: Vec3, reference: Vec3, max_angle: f32) -> Vec3 {
+    let (direction, reference) = match (direction.try_normalize(), reference.try_normalize()) {
+        (Some(direction), Some(reference)) => (direction, reference),
+        _ => return direction,
+    };
+    let angle = direction.angle_between(reference);
+    if angle <= max_angle {
+        return direction;
+    }
+    let axis = reference
+        .cross(direction)
+        .try_normalize()
+        .unwrap_or_else(|| reference.any_orthonormal_vector());
+    Quat::from_axis_angle(axis, max_angle) * reference
+}
+
+/// Analytic solver for a chain of three bones, like shoulder, elbow and hand or hip, knee and foot.\
+/// Targets and poles of all the solvers are in the space of `Skeleton::world_matrices`.
+#[derive(Debug, Clone, Copy)]
+pub struct TwoBoneIk {
+    pub root: BoneIndex,
+    pub mid: BoneIndex,
+    pub end: BoneIndex,
+    /// Position the middle joint bends towards
+    pub pole: Option<Vec3>,
+}
+
+impl TwoBoneIk {
+    pub fn new(root: BoneIndex, mid: BoneIndex, end: BoneIndex) -> Self {
+        Self {
+            root,
+            mid,
+            end,
+            pole: None,
+        }
+    }
+
+    pub fn with_pole(mut self, pole: Vec3) -> Self {
+        self.pole = Some(pole);
+        self
+    }
+
+    /// Rotates the root and middle bones so the end bone reaches `target`.\
+    /// Returns `false` when the target is out of reach, the chain is stretched towards it.
+    pub fn solve(
+        &self,
+        skeleton: &Skeleton,
+        pose: &mut SkeletonPose,
+        target: Vec3,
+    ) -> Result<bool, SkeletonError> {
+        if !skeleton.is_ancestor(self.root, self.mid) || !skeleton.is_ancestor(self.mid, self.end) {
+            return Err(SkeletonError::InvalidBone);
+        }
+        let mut world_matrices = skeleton.world_matrices(pose)?;
+        let a = bone_position(&world_matrices, self.root);
+        let b = bone_position(&world_matrices, self.mid);
+        let c = bone_position(&world_matrices, self.end);
+
+        let length_ab = (b - a).length();
+        let length_bc = (c - b).length();
+        let target_distance = (target - a).length();
+        let reachable = target_distance <= length_ab + length_bc
+            && target_distance >= (length_ab - length_bc).abs();
+        let epsilon = 1e-4;
+        let length_at = target_distance.clamp(
+            (length_ab - length_bc).abs() + epsilon,
+            length_ab + length_bc - epsilon,
+        );
+
+        // Bend the middle joint so the chain has the length to the target
+        let current_angle = (a - b).angle_between(c - b);
+        let desired_angle = ((length_ab * length_ab + length_bc * length_bc
+            - length_at * length_at)
+            / (2.0 * length_ab * length_bc))
+            .clamp(-1.0, 1.0)
+            .acos();
+        let bend_axis = (a - b)
+            .cross(c - b)
+            .try_normalize()
+            .or_else(|| {
+                let pole = self.pole.unwrap_or(b + (b - a).any_orthonormal_vector());
+                (c - a).cross(pole - a).try_normalize()
+            })
+            .unwrap_or_else(|| (c - a).any_orthonormal_vector());
+        let bend = Quat::from_axis_angle(bend_axis, desired_angle - current_angle);
+        rotate_bone_world(skeleton, pose, &world_matrices, self.mid, bend);
+
+        // Point the chain at the target
+        skeleton.compute_world_matrices(pose, &mut world_matrices)?;
+        let c = bone_position(&world_matrices, self.end);
+        if let Some(rotation) = rotation_between(c - a, target - a) {
+            rotate_bone_world(skeleton, pose, &world_matrices, self.root, rotation);
+        }
+
+        // Twist around the chain axis so the middle joint faces the pole
+        if let Some(pole) = self.pole {
+            skeleton.compute_world_matrices(pose, &mut world_matrices)?;
+            let b = bone_position(&world_matrices, self.mid);
+            let c = bone_position(&world_matrices, self.end);
+            if let Some(axis) = (c - a).try_normalize() {
+                let to_mid = (b - a).reject_from_normalized(axis);
+                let to_pole = (pole - a).reject_from_normalized(axis);
+                if let Some(rotation) = rotation_between(to_mid, to_pole) {
+                    rotate_bone_world(skeleton, pose, &world_matrices, self.root, rotation);
+                }
+            }
+        }
+        Ok(reachable)
+    }
+}
+
+/// Rotates a bone so its `forward` axis points at the target, e.g. a head or a turret
+#[derive(Debug, Clone, Copy)]
+pub struct LookAtConstraint {
+    pub bone: BoneIndex,
+    /// Axis of the bone, in its local space, that points at the target
+    pub forward: Vec3,
+    /// Maximum rotation from the current pose in radians
+    pub max_angle: Option<f32>,
+    /// Blend between the current pose and the constrained pose
+    pub weight: f32,
+}
+
+impl LookAtConstraint {
+    pub fn new(bone: BoneIndex, forward: Vec3) -> Self {
+        Self {
+            bone,
+            forward,
+            max_angle: None,
+            weight: 1.0,
+        }
+    }
+
+    pub fn with_max_angle(mut self, max_angle: f32) -> Self {
+        self.max_angle = Some(max_angle);
+        self
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn solve(
+        &self,
+        skeleton: &Skeleton,
+        pose: &mut SkeletonPose,
+        target: Vec3,
+    ) -> Result<(), SkeletonError> {
+        if self.bone >= skeleton.bone_count() {
+            return Err(SkeletonError::InvalidBone);
+        }
+        let world_matrices = skeleton.world_matrices(pose)?;
+        let forward = bone_rotation(&world_matrices, self.bone) * self.forward;
+        let mut direction = target - bone_position(&world_matrices, self.bone);
+        if let Some(max_angle) = self.max_angle {
+            direction = limit_direction(direction, forward, max_angle);
+        }
+        if let Some(rotation) = rotation_between(forward, direction) {
+            let rotation = Quat::IDENTITY.slerp(rotation, self.weight.clamp(0.0, 1.0));
+            rotate_bone_world(skeleton, pose, &world_matrices, self.bone, rotation);
+        }
+        Ok(())
+    }
+}
+
+/// Iterative solver for chains of any length
+#[derive(Debug, Clone)]
+pub struct FabrikChain {
+    /// Bones from the root of the chain to the end effector, each one a descendant of the previous
+    pub bones: Vec<BoneIndex>,
+    /// Maximum bend in radians of every joint relative to the previous segment, the root
+    /// and the end effector are not limited
+    pub limits: Vec<Option<f32>>,
+    pub tolerance: f32,
+    pub max_iterations: u32,
+}
+
+impl FabrikChain {
+    pub fn new(bones: Vec<BoneIndex>) -> Self {
+        let limits = vec![None; bones.len()];
+        Self {
+            bones,
+            limits,
+            tolerance: 1e-3,
+            max_iterations: 16,
+        }
+    }
+
+    /// Limits the bend of `joint`, an index in `bones`.\
+    /// Ignored for the root, the end effector and joints outside the chain, they have no bend
+    /// to limit.
+    pub fn with_limit(mut self, joint: usize, max_angle: f32) -> Self {
+        if joint > 0 && joint + 1 < self.bones.len() {
+            self.limits[joint] = Some(max_angle);
+        }
+        self
+    }
+
+    pub fn with_tolerance(mut self, tolerance: f32, max_iterations: u32) -> Self {
+        self.tolerance = tolerance;
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    fn constrain_backward(&self, positions: &mut [Vec3], lengths: &[f32]) {
+        for i in 0..lengths.len() {
+            let mut direction = (positions[i + 1] - positions[i])
+                .try_normalize()
+                .unwrap_or(Vec3::X);
+            if let (true, Some(max_angle)) = (i > 0, self.limits[i]) {
+                let reference = positions[i] - positions[i - 1];
+                direction = limit_direction(direction, reference, max_angle);
+            }
+            positions[i + 1] = positions[i] + direction * lengths[i];
+        }
+    }
+
+    /// Returns `false` when the target is out of reach or the solver did not converge
+    pub fn solve(
+        &self,
+        skeleton: &Skeleton,
+        pose: &mut SkeletonPose,
+        target: Vec3,
+    ) -> Result<bool, SkeletonError> {
+        if self.bones.len() < 2 {
+            return Err(SkeletonError::InvalidBone);
+        }
+        for pair in self.bones.windows(2) {
+            if pair[1] >= skeleton.bone_count() || !skeleton.is_ancestor(pair[0], pair[1]) {
+                return Err(SkeletonError::InvalidBone);
+            }
+        }
+        let mut world_matrices = skeleton.world_matrices(pose)?;
+        let mut positions: Vec<Vec3> = self
+            .bones
+            .iter()
+            .map(|bone| bone_position(&world_matrices, *bone))
+            .collect();
+        let lengths: Vec<f32> = positions
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).length())
+            .collect();
+        let root = positions[0];
+        let end = positions.len() - 1;
+
+        let reachable = (target - root).length() <= lengths.iter().sum::<f32>();
+        if !reachable {
+            let direction = (target - root).try_normalize().unwrap_or(Vec3::X);
+            for i in 0..lengths.len() {
+                positions[i + 1] = positions[i] + direction * lengths[i];
+            }
+            self.constrain_backward(&mut positions, &lengths);
+        } else {
+            for _ in 0..self.max_iterations {
+                if (positions[end] - target).length() <= self.tolerance {
+                    break;
+                }
+                // Forward pass, from the end effector to the root
+                positions[end] = target;
+                for i in (0..lengths.len()).rev() {
+                    let direction = (positions[i] - positions[i + 1])
+                        .try_normalize()
+                        .unwrap_or(Vec3::X);
+                    positions[i] = positions[i + 1] + direction * lengths[i];
+                }
+                // Backward pass, from the root to the end effector
+                positions[0] = root;
+                self.constrain_backward(&mut positions, &lengths);
+            }
+        }
+
+        for i in 0..lengths.len() {
+            skeleton.compute_world_matrices(pose, &mut world_matrices)?;
+            let current = bone_position(&world_matrices, self.bones[i + 1])
+                - bone_position(&world_matrices, self.bones[i]);
+            if let Some(rotation) = rotation_between(current, positions[i + 1] - positions[i]) {
+                rotate_bone_world(skeleton, pose, &world_matrices, self.bones[i], rotation);
+            }
+        }
+        Ok(reachable && (positions[end] - target).length() <= self.tolerance)
+    }
+}
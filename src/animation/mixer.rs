@@ -0,0 +1,504 @@
+use glam::*;
+
+use super::{
+    AnimationClip, AnimationEvent, AnimationUpdate, BlendSpace1D, BlendSpace2D, BoneMask,
+    ClipIndex, FiredEvent, PlaybackMode, RootMotion, Skeleton, SkeletonPose,
+};
+use crate::time::Time;
+use crate::transform::Transform;
+
+/// What a layer plays: a single clip or a blend of clips driven by parameters
+#[derive(Debug, Clone)]
+pub enum Motion {
+    Clip(ClipIndex),
+    BlendSpace1D {
+        space: BlendSpace1D,
+        parameter: f32,
+    },
+    BlendSpace2D {
+        space: BlendSpace2D,
+        parameter: Vec2,
+    },
+}
+
+impl Motion {
+    pub fn clip_weights(&self) -> Vec<(ClipIndex, f32)> {
+        match self {
+            Motion::Clip(clip) => vec![(*clip, 1.0)],
+            Motion::BlendSpace1D { space, parameter } => space.weights(*parameter),
+            Motion::BlendSpace2D { space, parameter } => space.weights(*parameter),
+        }
+    }
+
+    /// Weighted average of the clip durations, the clips of a blend space are played with a
+    /// shared normalized time so their cycles stay in sync.
+    pub fn duration(&self, clips: &[AnimationClip]) -> f32 {
+        self.clip_weights()
+            .iter()
+            .map(|(clip, weight)| clips[*clip].duration * weight)
+            .sum()
+    }
+
+    pub fn set_parameter(&mut self, value: f32) {
+        if let Motion::BlendSpace1D { parameter, .. } = self {
+            *parameter = value;
+        }
+    }
+
+    pub fn set_parameter_2d(&mut self, value: Vec2) {
+        if let Motion::BlendSpace2D { parameter, .. } = self {
+            *parameter = value;
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MotionState {
+    pub motion: Motion,
+    /// Position in the motion between 0 and 1
+    pub normalized_time: f32,
+    pub speed: f32,
+    pub mode: PlaybackMode,
+}
+
+impl MotionState {
+    pub fn new(motion: Motion, mode: PlaybackMode) -> Self {
+        Self {
+            motion,
+            normalized_time: 0.0,
+            speed: 1.0,
+            mode,
+        }
+    }
+
+    pub fn clip(clip: ClipIndex, mode: PlaybackMode) -> Self {
+        Self::new(Motion::Clip(clip), mode)
+    }
+
+    fn advance_normalized(&mut self, normalized_delta: f32) -> (f32, f32) {
+        let from = self.normalized_time;
+        let to = from + normalized_delta * self.speed;
+        match self.mode {
+            PlaybackMode::Loop => {
+                self.normalized_time = to.rem_euclid(1.0);
+                (from, to)
+            }
+            PlaybackMode::Clamp => {
+                self.normalized_time = to.clamp(0.0, 1.0);
+                (from, self.normalized_time)
+            }
+        }
+    }
+
+    /// Returns the normalized times covered by the update, without wrapping, to be used with
+    /// `events_between` and `root_motion_between`
+    pub fn advance(&mut self, clips: &[AnimationClip], delta: f32) -> (f32, f32) {
+        let duration = self.motion.duration(clips);
+        if duration > 0.0 {
+            self.advance_normalized(delta / duration)
+        } else {
+            (self.normalized_time, self.normalized_time)
+        }
+    }
+
+    /// Events of every clip of the motion between two normalized times, with the clip weight
+    pub fn events_between<'a>(
+        &self,
+        clips: &'a [AnimationClip],
+        from: f32,
+        to: f32,
+    ) -> Vec<(ClipIndex, &'a AnimationEvent, f32)> {
+        let mut events = Vec::new();
+        for (clip_index, weight) in self.motion.clip_weights() {
+            let clip = &clips[clip_index];
+            events.extend(
+                clip.events_between(from * clip.duration, to * clip.duration, self.mode)
+                    .into_iter()
+                    .map(|event| (clip_index, event, weight)),
+            );
+        }
+        events
+    }
+
+    /// Root motion of the clips of the motion between two normalized times, blended by the
+    /// clip weights
+    pub fn root_motion_between(
+        &self,
+        clips: &[AnimationClip],
+        root_motion: &RootMotion,
+        from: f32,
+        to: f32,
+    ) -> Transform {
+        let mut delta = Transform::IDENTITY;
+        let mut accumulated_weight = 0.0;
+        for (clip_index, weight) in self.motion.clip_weights() {
+            if weight <= 0.0 {
+                continue;
+            }
+            let clip = &clips[clip_index];
+            let clip_delta =
+                root_motion.delta(clip, from * clip.duration, to * clip.duration, self.mode);
+            accumulated_weight += weight;
+            delta = delta.lerp(&clip_delta, weight / accumulated_weight);
+        }
+        delta
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self.mode {
+            PlaybackMode::Loop => false,
+            PlaybackMode::Clamp => {
+                (self.speed >= 0.0 && self.normalized_time >= 1.0)
+                    || (self.speed < 0.0 && self.normalized_time <= 0.0)
+            }
+        }
+    }
+
+    fn sample_at(&self, clips: &[AnimationClip], normalized_time: f32, pose: &mut SkeletonPose) {
+        let clip_weights = self.motion.clip_weights();
+        let sample_clip = |clip: &AnimationClip, pose: &mut SkeletonPose| {
+            clip.sample_pose(normalized_time * clip.duration, PlaybackMode::Clamp, pose)
+        };
+        if let [(clip, _)] = clip_weights.as_slice() {
+            sample_clip(&clips[*clip], pose);
+            return;
+        }
+        let clip_poses: Vec<(SkeletonPose, f32)> = clip_weights
+            .iter()
+            .map(|(clip, weight)| {
+                let mut clip_pose = pose.clone();
+                sample_clip(&clips[*clip], &mut clip_pose);
+                (clip_pose, *weight)
+            })
+            .collect();
+        pose.blend_weighted(
+            clip_poses
+                .iter()
+                .map(|(clip_pose, weight)| (clip_pose, *weight)),
+        );
+    }
+
+    /// Overwrites the animated bones of `pose`
+    pub fn sample_pose(&self, clips: &[AnimationClip], pose: &mut SkeletonPose) {
+        self.sample_at(clips, self.normalized_time, pose);
+    }
+
+    /// Difference between the current pose and the first frame of the motion
+    pub fn sample_additive_pose(
+        &self,
+        clips: &[AnimationClip],
+        base: &SkeletonPose,
+    ) -> SkeletonPose {
+        let mut pose = base.clone();
+        self.sample_at(clips, self.normalized_time, &mut pose);
+        let mut reference = base.clone();
+        self.sample_at(clips, 0.0, &mut reference);
+        pose.additive_difference(&reference)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LayerBlendMode {
+    /// Replaces the pose of the layers below, scaled by the layer weight
+    Override,
+    /// Adds the motion relative to its first frame on top of the layers below
+    Additive,
+}
+
+#[derive(Debug, Clone)]
+struct CrossFade {
+    from: MotionState,
+    elapsed: f32,
+    duration: f32,
+    sync: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct AnimationLayer {
+    pub weight: f32,
+    pub blend_mode: LayerBlendMode,
+    pub mask: Option<BoneMask>,
+    pub state: Option<MotionState>,
+    fade: Option<CrossFade>,
+}
+
+impl AnimationLayer {
+    pub fn new(blend_mode: LayerBlendMode) -> Self {
+        Self {
+            weight: 1.0,
+            blend_mode,
+            mask: None,
+            state: None,
+            fade: None,
+        }
+    }
+
+    pub fn with_weight(mut self, weight: f32) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    pub fn with_mask(mut self, mask: BoneMask) -> Self {
+        self.mask = Some(mask);
+        self
+    }
+
+    /// Switches to the state immediately, cancelling any cross-fade
+    pub fn play(&mut self, state: MotionState) {
+        self.state = Some(state);
+        self.fade = None;
+    }
+
+    /// Blends from the current state to `state` over `duration` seconds.\
+    /// With `sync` the new state starts at the normalized time of the current one and both
+    /// advance at the same rate during the fade, used to switch between walk and run cycles.
+    pub fn cross_fade(&mut self, mut state: MotionState, duration: f32, sync: bool) {
+        let from = match self.state.take() {
+            Some(from) => from,
+            None => {
+                self.play(state);
+                return;
+            }
+        };
+        if duration <= 0.0 {
+            self.play(state);
+            return;
+        }
+        if sync {
+            state.normalized_time = from.normalized_time;
+        }
+        self.state = Some(state);
+        self.fade = Some(CrossFade {
+            from,
+            elapsed: 0.0,
+            duration,
+            sync,
+        });
+    }
+
+    pub fn is_fading(&self) -> bool {
+        self.fade.is_some()
+    }
+
+    /// Weight of the new state during a cross-fade, between 0 and 1
+    pub fn fade_progress(&self) -> Option<f32> {
+        self.fade
+            .as_ref()
+            .map(|fade| (fade.elapsed / fade.duration).clamp(0.0, 1.0))
+    }
+
+    /// Advances the playing states, the events of the update are weighted by the cross-fade.\
+    /// The layer index of the events is set by the mixer.
+    pub fn advance(
+        &mut self,
+        clips: &[AnimationClip],
+        delta: f32,
+        root_motion: Option<&RootMotion>,
+    ) -> AnimationUpdate {
+        let mut update = AnimationUpdate::default();
+        let state = match self.state.as_mut() {
+            Some(state) => state,
+            None => return update,
+        };
+        match self.fade.as_mut() {
+            Some(fade) => {
+                let t = (fade.elapsed / fade.duration).clamp(0.0, 1.0);
+                let (from_range, to_range) = if fade.sync {
+                    let from_duration = fade.from.motion.duration(clips);
+                    let to_duration = state.motion.duration(clips);
+                    let duration = from_duration + (to_duration - from_duration) * t;
+                    if duration > 0.0 {
+                        (
+                            fade.from.advance_normalized(delta / duration),
+                            state.advance_normalized(delta / duration),
+                        )
+                    } else {
+                        (
+                            (fade.from.normalized_time, fade.from.normalized_time),
+                            (state.normalized_time, state.normalized_time),
+                        )
+                    }
+                } else {
+                    (fade.from.advance(clips, delta), state.advance(clips, delta))
+                };
+                collect_events(&fade.from, clips, from_range, 1.0 - t, &mut update.events);
+                collect_events(state, clips, to_range, t, &mut update.events);
+                if let Some(root_motion) = root_motion {
+                    let from_delta = fade.from.root_motion_between(
+                        clips,
+                        root_motion,
+                        from_range.0,
+                        from_range.1,
+                    );
+                    let to_delta =
+                        state.root_motion_between(clips, root_motion, to_range.0, to_range.1);
+                    update.root_motion = from_delta.lerp(&to_delta, t);
+                }
+                fade.elapsed += delta;
+                if fade.elapsed >= fade.duration {
+                    self.fade = None;
+                }
+            }
+            None => {
+                let (from, to) = state.advance(clips, delta);
+                collect_events(state, clips, (from, to), 1.0, &mut update.events);
+                if let Some(root_motion) = root_motion {
+                    update.root_motion = state.root_motion_between(clips, root_motion, from, to);
+                }
+            }
+        }
+        update
+    }
+
+    fn sample_state(
+        &self,
+        state: &MotionState,
+        clips: &[AnimationClip],
+        base: &SkeletonPose,
+    ) -> SkeletonPose {
+        match self.blend_mode {
+            LayerBlendMode::Override => {
+                let mut pose = base.clone();
+                state.sample_pose(clips, &mut pose);
+                pose
+            }
+            LayerBlendMode::Additive => state.sample_additive_pose(clips, base),
+        }
+    }
+
+    /// Pose of the layer including the cross-fade, `None` when nothing is playing
+    pub fn sample(&self, clips: &[AnimationClip], base: &SkeletonPose) -> Option<SkeletonPose> {
+        let state = self.state.as_ref()?;
+        let mut pose = match self.fade.as_ref() {
+            Some(fade) => self.sample_state(&fade.from, clips, base),
+            None => return Some(self.sample_state(state, clips, base)),
+        };
+        let target = self.sample_state(state, clips, base);
+        pose.blend(&target, self.fade_progress().unwrap_or(1.0), None);
+        Some(pose)
+    }
+}
+
+fn collect_events(
+    state: &MotionState,
+    clips: &[AnimationClip],
+    (from, to): (f32, f32),
+    weight: f32,
+    events: &mut Vec<FiredEvent>,
+) {
+    for (clip, event, clip_weight) in state.events_between(clips, from, to) {
+        events.push(FiredEvent {
+            layer: 0,
+            clip,
+            name: event.name.clone(),
+            time: event.time,
+            weight: weight * clip_weight,
+        });
+    }
+}
+
+/// Plays layers of motions over a skeleton. Layers are applied in order on top of the bind pose.
+pub struct AnimationMixer {
+    pub clips: Vec<AnimationClip>,
+    pub layers: Vec<AnimationLayer>,
+    /// When set, the movement of the root bone is returned by `advance` instead of being
+    /// part of the evaluated pose. Only override layers contribute to it.
+    pub root_motion: Option<RootMotion>,
+}
+
+impl AnimationMixer {
+    pub fn new(clips: Vec<AnimationClip>) -> Self {
+        Self {
+            clips,
+            layers: Vec::new(),
+            root_motion: None,
+        }
+    }
+
+    pub fn add_clip(&mut self, clip: AnimationClip) -> ClipIndex {
+        self.clips.push(clip);
+        self.clips.len() - 1
+    }
+
+    pub fn find_clip(&self, name: &str) -> Option<ClipIndex> {
+        self.clips
+            .iter()
+            .position(|clip| clip.name.as_deref() == Some(name))
+    }
+
+    pub fn add_layer(&mut self, layer: AnimationLayer) -> usize {
+        self.layers.push(layer);
+        self.layers.len() - 1
+    }
+
+    pub fn layer(&self, layer: usize) -> Option<&AnimationLayer> {
+        self.layers.get(layer)
+    }
+
+    pub fn layer_mut(&mut self, layer: usize) -> Option<&mut AnimationLayer> {
+        self.layers.get_mut(layer)
+    }
+
+    pub fn with_root_motion(mut self, root_motion: RootMotion) -> Self {
+        self.root_motion = Some(root_motion);
+        self
+    }
+
+    /// Returns the events fired by every layer, weighted by the layer weight, and the root
+    /// motion of the update
+    pub fn advance(&mut self, delta: f32) -> AnimationUpdate {
+        let mut update = AnimationUpdate::default();
+        for (index, layer) in self.layers.iter_mut().enumerate() {
+            let layer_update = layer.advance(&self.clips, delta, self.root_motion.as_ref());
+            update
+                .events
+                .extend(layer_update.events.into_iter().map(|mut event| {
+                    event.layer = index;
+                    event.weight *= layer.weight;
+                    event
+                }));
+            if let (Some(root_motion), LayerBlendMode::Override) =
+                (self.root_motion.as_ref(), layer.blend_mode)
+            {
+                let mask_weight = match layer.mask.as_ref() {
+                    Some(mask) => mask.weight(root_motion.bone),
+                    None => 1.0,
+                };
+                let weight = layer.weight * mask_weight;
+                if weight > 0.0 && layer.state.is_some() {
+                    update.root_motion = update
+                        .root_motion
+                        .lerp(&layer_update.root_motion, weight.min(1.0));
+                }
+            }
+        }
+        update
+    }
+
+    pub fn update(&mut self, time: &Time) -> AnimationUpdate {
+        self.advance(time.delta_time_seconds().0 as f32)
+    }
+
+    pub fn evaluate(&self, skeleton: &Skeleton, pose: &mut SkeletonPose) {
+        let bind_pose = skeleton.bind_pose();
+        pose.bones.clone_from(&bind_pose.bones);
+        for layer in self.layers.iter() {
+            if layer.weight <= 0.0 {
+                continue;
+            }
+            if let Some(layer_pose) = layer.sample(&self.clips, &bind_pose) {
+                match layer.blend_mode {
+                    LayerBlendMode::Override => {
+                        pose.blend(&layer_pose, layer.weight, layer.mask.as_ref())
+                    }
+                    LayerBlendMode::Additive => {
+                        pose.apply_additive(&layer_pose, layer.weight, layer.mask.as_ref())
+                    }
+                }
+            }
+        }
+        if let Some(root_motion) = self.root_motion.as_ref() {
+            root_motion.remove_from_pose(pose);
+        }
+    }
+}
@@ -1,17 +1,15 @@
-use glam::*;
-
-use crate::slotmap::prelude::*;
-crate::slotmap::prelude::create_custom_key!(BoneKey);
-
-pub struct Skeleton{
-	transform: Mat4,
-	bones: Slotmap<Bone>,
-	bone_tree: BoneNode,
-}
-pub struct BoneNode{
-	node: BoneKey,
-	children: Vec<BoneKey>
-}
-pub struct Bone{
-	transform: Mat4
-}
+mod skeleton;
+pub use skeleton::*;
+mod clip;
+pub use clip::*;
+mod blend;
+pub use blend::*;
+mod mixer;
+pub use mixer::*;
+mod state_machine;
+pub use state_machine::*;
+mod ik;
+pub use ik::*;
+mod events;
+pub use events::*;
+mod test;
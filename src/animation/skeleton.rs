@@ -0,0 +1,225 @@
+use glam::*;
+
+use crate::transform::Transform;
+
+/// Index of a bone inside its skeleton, also used as the joint index of skinned vertices
+pub type BoneIndex = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkeletonError {
+    /// The parent has to be added to the skeleton before its children
+    InvalidParent,
+    InvalidBone,
+    PoseSizeMismatch,
+}
+
+#[derive(Clone)]
+pub struct Bone {
+    pub name: Option<String>,
+    pub parent: Option<BoneIndex>,
+    pub children: Vec<BoneIndex>,
+    /// Local transform of the bone in the rest pose, relative to its parent
+    pub bind_transform: Transform,
+    /// Transforms from mesh space to the space of the bone in the rest pose
+    pub inverse_bind_matrix: Mat4,
+}
+
+/// Hierarchy of bones. Parents are always stored before their children, so a pose can be
+/// evaluated with a single pass over the bones.
+#[derive(Clone)]
+pub struct Skeleton {
+    /// Transform applied on top of the root bones
+    pub transform: Mat4,
+    bones: Vec<Bone>,
+    roots: Vec<BoneIndex>,
+}
+
+/// Local transform of every bone of a skeleton, indexed by `BoneIndex`
+#[derive(Clone, Debug, PartialEq)]
+pub struct SkeletonPose {
+    pub bones: Vec<Transform>,
+}
+
+impl SkeletonPose {
+    pub fn new(bone_count: usize) -> Self {
+        Self {
+            bones: vec![Transform::IDENTITY; bone_count],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.bones.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bones.is_empty()
+    }
+
+    pub fn get(&self, bone: BoneIndex) -> Option<&Transform> {
+        self.bones.get(bone)
+    }
+
+    pub fn get_mut(&mut self, bone: BoneIndex) -> Option<&mut Transform> {
+        self.bones.get_mut(bone)
+    }
+
+    pub fn set(&mut self, bone: BoneIndex, transform: Transform) {
+        self.bones[bone] = transform;
+    }
+}
+
+impl Default for Skeleton {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Skeleton {
+    pub fn new() -> Self {
+        Self {
+            transform: Mat4::IDENTITY,
+            bones: Vec::new(),
+            roots: Vec::new(),
+        }
+    }
+
+    /// Adds a bone with its rest pose, the inverse bind matrix is computed from the rest pose
+    /// of the bone and its parents.
+    pub fn add_bone(
+        &mut self,
+        name: Option<String>,
+        parent: Option<BoneIndex>,
+        bind_transform: Transform,
+    ) -> Result<BoneIndex, SkeletonError> {
+        let parent_bind_matrix = match parent {
+            Some(parent) => match self.bones.get(parent) {
+                Some(parent_bone) => parent_bone.inverse_bind_matrix.inverse(),
+                None => return Err(SkeletonError::InvalidParent),
+            },
+            None => Mat4::IDENTITY,
+        };
+        let bind_matrix = parent_bind_matrix * bind_transform.to_mat4();
+
+        let index = self.bones.len();
+        self.bones.push(Bone {
+            name,
+            parent,
+            children: Vec::new(),
+            bind_transform,
+            inverse_bind_matrix: bind_matrix.inverse(),
+        });
+        match parent {
+            Some(parent) => self.bones[parent].children.push(index),
+            None => self.roots.push(index),
+        }
+        Ok(index)
+    }
+
+    /// Overrides the computed inverse bind matrix, used when the asset provides its own
+    pub fn set_inverse_bind_matrix(
+        &mut self,
+        bone: BoneIndex,
+        inverse_bind_matrix: Mat4,
+    ) -> Result<(), SkeletonError> {
+        match self.bones.get_mut(bone) {
+            Some(bone) => {
+                bone.inverse_bind_matrix = inverse_bind_matrix;
+                Ok(())
+            }
+            None => Err(SkeletonError::InvalidBone),
+        }
+    }
+
+    pub fn bone_count(&self) -> usize {
+        self.bones.len()
+    }
+
+    pub fn bone(&self, bone: BoneIndex) -> Option<&Bone> {
+        self.bones.get(bone)
+    }
+
+    pub fn bones(&self) -> &[Bone] {
+        &self.bones
+    }
+
+    pub fn roots(&self) -> &[BoneIndex] {
+        &self.roots
+    }
+
+    pub fn parent(&self, bone: BoneIndex) -> Option<BoneIndex> {
+        self.bones.get(bone).and_then(|bone| bone.parent)
+    }
+
+    pub fn find_bone(&self, name: &str) -> Option<BoneIndex> {
+        self.bones
+            .iter()
+            .position(|bone| bone.name.as_deref() == Some(name))
+    }
+
+    /// `true` if `ancestor` is `bone` or one of its parents
+    pub fn is_ancestor(&self, ancestor: BoneIndex, bone: BoneIndex) -> bool {
+        let mut current = Some(bone);
+        while let Some(index) = current {
+            if index == ancestor {
+                return true;
+            }
+            current = self.parent(index);
+        }
+        false
+    }
+
+    pub fn bind_pose(&self) -> SkeletonPose {
+        SkeletonPose {
+            bones: self.bones.iter().map(|bone| bone.bind_transform).collect(),
+        }
+    }
+
+    /// Computes the model space matrix of every bone of the pose, including `self.transform`
+    pub fn compute_world_matrices(
+        &self,
+        pose: &SkeletonPose,
+        world_matrices: &mut Vec<Mat4>,
+    ) -> Result<(), SkeletonError> {
+        if pose.len() != self.bones.len() {
+            return Err(SkeletonError::PoseSizeMismatch);
+        }
+        world_matrices.clear();
+        for (index, bone) in self.bones.iter().enumerate() {
+            let parent_matrix = match bone.parent {
+                Some(parent) => world_matrices[parent],
+                None => self.transform,
+            };
+            world_matrices.push(parent_matrix * pose.bones[index].to_mat4());
+        }
+        Ok(())
+    }
+
+    pub fn world_matrices(&self, pose: &SkeletonPose) -> Result<Vec<Mat4>, SkeletonError> {
+        let mut world_matrices = Vec::with_capacity(self.bones.len());
+        self.compute_world_matrices(pose, &mut world_matrices)?;
+        Ok(world_matrices)
+    }
+
+    /// Skinning matrices from already computed world matrices, in `BoneIndex` order
+    pub fn compute_skinning_palette(
+        &self,
+        world_matrices: &[Mat4],
+        palette: &mut Vec<Mat4>,
+    ) -> Result<(), SkeletonError> {
+        if world_matrices.len() != self.bones.len() {
+            return Err(SkeletonError::PoseSizeMismatch);
+        }
+        palette.clear();
+        for (bone, world_matrix) in self.bones.iter().zip(world_matrices.iter()) {
+            palette.push(*world_matrix * bone.inverse_bind_matrix);
+        }
+        Ok(())
+    }
+
+    pub fn skinning_palette(&self, pose: &SkeletonPose) -> Result<Vec<Mat4>, SkeletonError> {
+        let world_matrices = self.world_matrices(pose)?;
+        let mut palette = Vec::with_capacity(self.bones.len());
+        self.compute_skinning_palette(&world_matrices, &mut palette)?;
+        Ok(palette)
+    }
+}
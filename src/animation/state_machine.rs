@@ -0,0 +1,388 @@
+use std::collections::BTreeMap;
+
+use glam::*;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    AnimationMixer, BlendSpace1D, BlendSpace2D, ClipIndex, Motion, MotionState, PlaybackMode,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Parameter {
+    Float(f32),
+    Bool(bool),
+    /// Bool that is reset when a transition consumes it
+    Trigger(bool),
+}
+
+/// Motion of a state, clips are referenced by their name in the mixer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MotionDefinition {
+    Clip(String),
+    BlendSpace1D {
+        parameter: String,
+        points: Vec<(f32, String)>,
+    },
+    BlendSpace2D {
+        parameter_x: String,
+        parameter_y: String,
+        points: Vec<([f32; 2], String)>,
+    },
+}
+
+fn default_speed() -> f32 {
+    1.0
+}
+
+fn default_mode() -> PlaybackMode {
+    PlaybackMode::Loop
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StateDefinition {
+    pub name: String,
+    pub motion: MotionDefinition,
+    #[serde(default = "default_speed")]
+    pub speed: f32,
+    #[serde(default = "default_mode")]
+    pub mode: PlaybackMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Condition {
+    Greater { parameter: String, value: f32 },
+    Less { parameter: String, value: f32 },
+    IsTrue(String),
+    IsFalse(String),
+    Trigger(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransitionDefinition {
+    /// Source state, `None` allows the transition from any other state
+    #[serde(default)]
+    pub from: Option<String>,
+    pub to: String,
+    /// All the conditions have to be met
+    #[serde(default)]
+    pub conditions: Vec<Condition>,
+    /// Normalized time of the source state before the transition can start
+    #[serde(default)]
+    pub exit_time: Option<f32>,
+    /// Cross-fade duration in seconds
+    #[serde(default)]
+    pub duration: f32,
+    /// Starts the target state at the normalized time of the source state
+    #[serde(default)]
+    pub sync: bool,
+}
+
+/// Data authored by designers, usually loaded from JSON
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnimationStateMachineDefinition {
+    #[serde(default)]
+    pub parameters: BTreeMap<String, Parameter>,
+    pub states: Vec<StateDefinition>,
+    pub default_state: String,
+    #[serde(default)]
+    pub transitions: Vec<TransitionDefinition>,
+}
+
+impl AnimationStateMachineDefinition {
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|error| error.to_string())
+    }
+
+    pub fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|error| error.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateMachineError {
+    UnknownClip(String),
+    UnknownState(String),
+    UnknownParameter(String),
+    ParameterTypeMismatch(String),
+    InvalidLayer,
+}
+
+struct Transition {
+    from: Option<usize>,
+    to: usize,
+    conditions: Vec<Condition>,
+    exit_time: Option<f32>,
+    duration: f32,
+    sync: bool,
+}
+
+/// Drives one layer of an `AnimationMixer`. Call `update` before advancing the mixer.
+pub struct AnimationStateMachine {
+    pub layer: usize,
+    states: Vec<StateDefinition>,
+    motions: Vec<MotionState>,
+    transitions: Vec<Transition>,
+    parameters: BTreeMap<String, Parameter>,
+    current: usize,
+}
+
+fn find_clip(mixer: &AnimationMixer, name: &str) -> Result<ClipIndex, StateMachineError> {
+    mixer
+        .find_clip(name)
+        .ok_or_else(|| StateMachineError::UnknownClip(name.into()))
+}
+
+fn find_state(states: &[StateDefinition], name: &str) -> Result<usize, StateMachineError> {
+    states
+        .iter()
+        .position(|state| state.name == name)
+        .ok_or_else(|| StateMachineError::UnknownState(name.into()))
+}
+
+impl AnimationStateMachine {
+    /// Resolves clip and state names and starts playing the default state on `layer`.\
+    /// Blend spaces and conditions have to reference parameters of the kind they read, e.g. a
+    /// `Condition::Trigger` a `Parameter::Trigger`.
+    pub fn new(
+        definition: &AnimationStateMachineDefinition,
+        mixer: &mut AnimationMixer,
+        layer: usize,
+    ) -> Result<Self, StateMachineError> {
+        let parameter_of_kind = |name: &String, kind: Parameter| {
+            let parameter = definition
+                .parameters
+                .get(name)
+                .ok_or_else(|| StateMachineError::UnknownParameter(name.clone()))?;
+            if std::mem::discriminant(parameter) == std::mem::discriminant(&kind) {
+                Ok(())
+            } else {
+                Err(StateMachineError::ParameterTypeMismatch(name.clone()))
+            }
+        };
+        let float = Parameter::Float(0.0);
+
+        let mut motions = Vec::with_capacity(definition.states.len());
+        for state in definition.states.iter() {
+            let motion = match &state.motion {
+                MotionDefinition::Clip(clip) => Motion::Clip(find_clip(mixer, clip)?),
+                MotionDefinition::BlendSpace1D { parameter, points } => {
+                    parameter_of_kind(parameter, float)?;
+                    let mut space = BlendSpace1D::new();
+                    for (position, clip) in points.iter() {
+                        space.add_point(*position, find_clip(mixer, clip)?);
+                    }
+                    Motion::BlendSpace1D {
+                        space,
+                        parameter: 0.0,
+                    }
+                }
+                MotionDefinition::BlendSpace2D {
+                    parameter_x,
+                    parameter_y,
+                    points,
+                } => {
+                    parameter_of_kind(parameter_x, float)?;
+                    parameter_of_kind(parameter_y, float)?;
+                    let mut space = BlendSpace2D::new();
+                    for (position, clip) in points.iter() {
+                        space.add_point(Vec2::from_array(*position), find_clip(mixer, clip)?);
+                    }
+                    Motion::BlendSpace2D {
+                        space,
+                        parameter: Vec2::ZERO,
+                    }
+                }
+            };
+            let mut motion_state = MotionState::new(motion, state.mode);
+            motion_state.speed = state.speed;
+            motions.push(motion_state);
+        }
+
+        let mut transitions = Vec::with_capacity(definition.transitions.len());
+        for transition in definition.transitions.iter() {
+            for condition in transition.conditions.iter() {
+                match condition {
+                    Condition::Greater { parameter, .. } | Condition::Less { parameter, .. } => {
+                        parameter_of_kind(parameter, float)?
+                    }
+                    Condition::IsTrue(parameter) | Condition::IsFalse(parameter) => {
+                        parameter_of_kind(parameter, Parameter::Bool(false))?
+                    }
+                    Condition::Trigger(parameter) => {
+                        parameter_of_kind(parameter, Parameter::Trigger(false))?
+                    }
+                }
+            }
+            let from = match &transition.from {
+                Some(from) => Some(find_state(&definition.states, from)?),
+                None => None,
+            };
+            transitions.push(Transition {
+                from,
+                to: find_state(&definition.states, &transition.to)?,
+                conditions: transition.conditions.clone(),
+                exit_time: transition.exit_time,
+                duration: transition.duration,
+                sync: transition.sync,
+            });
+        }
+
+        let current = find_state(&definition.states, &definition.default_state)?;
+        let state_machine = Self {
+            layer,
+            states: definition.states.clone(),
+            motions,
+            transitions,
+            parameters: definition.parameters.clone(),
+            current,
+        };
+        let motion_state = state_machine.motion_state(current);
+        match mixer.layer_mut(layer) {
+            Some(mixer_layer) => mixer_layer.play(motion_state),
+            None => return Err(StateMachineError::InvalidLayer),
+        }
+        Ok(state_machine)
+    }
+
+    pub fn current_state(&self) -> &str {
+        &self.states[self.current].name
+    }
+
+    pub fn parameter(&self, name: &str) -> Option<Parameter> {
+        self.parameters.get(name).copied()
+    }
+
+    fn set_parameter(&mut self, name: &str, value: Parameter) -> Result<(), StateMachineError> {
+        match self.parameters.get_mut(name) {
+            Some(parameter) => {
+                if std::mem::discriminant(parameter) != std::mem::discriminant(&value) {
+                    return Err(StateMachineError::ParameterTypeMismatch(name.into()));
+                }
+                *parameter = value;
+                Ok(())
+            }
+            None => Err(StateMachineError::UnknownParameter(name.into())),
+        }
+    }
+
+    pub fn set_float(&mut self, name: &str, value: f32) -> Result<(), StateMachineError> {
+        self.set_parameter(name, Parameter::Float(value))
+    }
+
+    pub fn set_bool(&mut self, name: &str, value: bool) -> Result<(), StateMachineError> {
+        self.set_parameter(name, Parameter::Bool(value))
+    }
+
+    pub fn set_trigger(&mut self, name: &str) -> Result<(), StateMachineError> {
+        self.set_parameter(name, Parameter::Trigger(true))
+    }
+
+    pub fn reset_trigger(&mut self, name: &str) -> Result<(), StateMachineError> {
+        self.set_parameter(name, Parameter::Trigger(false))
+    }
+
+    fn float(&self, name: &str) -> f32 {
+        match self.parameters.get(name) {
+            Some(Parameter::Float(value)) => *value,
+            _ => 0.0,
+        }
+    }
+
+    fn is_set(&self, name: &str) -> bool {
+        matches!(
+            self.parameters.get(name),
+            Some(Parameter::Bool(true)) | Some(Parameter::Trigger(true))
+        )
+    }
+
+    fn condition_met(&self, condition: &Condition) -> bool {
+        match condition {
+            Condition::Greater { parameter, value } => self.float(parameter) > *value,
+            Condition::Less { parameter, value } => self.float(parameter) < *value,
+            Condition::IsTrue(parameter) | Condition::Trigger(parameter) => self.is_set(parameter),
+            Condition::IsFalse(parameter) => !self.is_set(parameter),
+        }
+    }
+
+    /// Motion of the state with the blend space parameters taken from the current parameters
+    fn motion_state(&self, state: usize) -> MotionState {
+        let mut motion_state = self.motions[state].clone();
+        self.apply_parameters(state, &mut motion_state.motion);
+        motion_state
+    }
+
+    fn apply_parameters(&self, state: usize, motion: &mut Motion) {
+        match &self.states[state].motion {
+            MotionDefinition::Clip(_) => {}
+            MotionDefinition::BlendSpace1D { parameter, .. } => {
+                motion.set_parameter(self.float(parameter))
+            }
+            MotionDefinition::BlendSpace2D {
+                parameter_x,
+                parameter_y,
+                ..
+            } => motion.set_parameter_2d(vec2(self.float(parameter_x), self.float(parameter_y))),
+        }
+    }
+
+    /// Updates the blend space parameters of the layer and starts the first transition whose
+    /// conditions are met. Transitions are not evaluated during a cross-fade.\
+    /// Returns `true` when a transition started.
+    pub fn update(&mut self, mixer: &mut AnimationMixer) -> Result<bool, StateMachineError> {
+        let current = self.current;
+        let layer = match mixer.layers.get_mut(self.layer) {
+            Some(layer) => layer,
+            None => return Err(StateMachineError::InvalidLayer),
+        };
+        let normalized_time = match layer.state.as_mut() {
+            Some(state) => {
+                self.apply_parameters(current, &mut state.motion);
+                state.normalized_time
+            }
+            None => 0.0,
+        };
+        if layer.is_fading() {
+            return Ok(false);
+        }
+
+        let transition = self.transitions.iter().position(|transition| {
+            let from_current = match transition.from {
+                Some(from) => from == current,
+                None => transition.to != current,
+            };
+            let exit_reached = match transition.exit_time {
+                Some(exit_time) => normalized_time >= exit_time,
+                None => true,
+            };
+            from_current
+                && exit_reached
+                && transition
+                    .conditions
+                    .iter()
+                    .all(|condition| self.condition_met(condition))
+        });
+        let transition = match transition {
+            Some(transition) => &self.transitions[transition],
+            None => return Ok(false),
+        };
+
+        let to = transition.to;
+        let (duration, sync) = (transition.duration, transition.sync);
+        let triggers: Vec<String> = transition
+            .conditions
+            .iter()
+            .filter_map(|condition| match condition {
+                Condition::Trigger(trigger) => Some(trigger.clone()),
+                _ => None,
+            })
+            .collect();
+        for trigger in triggers.iter() {
+            self.reset_trigger(trigger)?;
+        }
+
+        let motion_state = self.motion_state(to);
+        layer.cross_fade(motion_state, duration, sync);
+        self.current = to;
+        Ok(true)
+    }
+}
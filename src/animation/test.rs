@@ -0,0 +1,837 @@
+#[cfg(test)]
+mod tests {
+    use glam::*;
+
+    use super::super::*;
+    use crate::transform::Transform;
+
+    /// Straight arm along X: shoulder at the origin, elbow at 1, hand at 2
+    fn create_arm() -> (Skeleton, [BoneIndex; 3]) {
+        let mut skeleton = Skeleton::new();
+        let shoulder = skeleton
+            .add_bone(Some("shoulder".into()), None, Transform::IDENTITY)
+            .unwrap();
+        let elbow = skeleton
+            .add_bone(Some("elbow".into()), Some(shoulder), Transform::from_translation(Vec3::X))
+            .unwrap();
+        let hand = skeleton
+            .add_bone(Some("hand".into()), Some(elbow), Transform::from_translation(Vec3::X))
+            .unwrap();
+        (skeleton, [shoulder, elbow, hand])
+    }
+
+    #[test]
+    fn bones_keep_their_hierarchy() {
+        let (skeleton, [shoulder, elbow, hand]) = create_arm();
+        assert_eq!(skeleton.bone_count(), 3);
+        assert_eq!(skeleton.roots(), &[shoulder]);
+        assert_eq!(skeleton.parent(hand), Some(elbow));
+        assert_eq!(skeleton.bone(shoulder).unwrap().children, vec![elbow]);
+        assert_eq!(skeleton.find_bone("hand"), Some(hand));
+        assert!(skeleton.is_ancestor(shoulder, hand));
+        assert!(!skeleton.is_ancestor(hand, shoulder));
+    }
+
+    #[test]
+    fn child_cannot_be_added_before_parent() {
+        let mut skeleton = Skeleton::new();
+        let result = skeleton.add_bone(None, Some(3), Transform::IDENTITY);
+        assert_eq!(result, Err(SkeletonError::InvalidParent));
+    }
+
+    #[test]
+    fn bind_pose_world_matrices_match_rest_positions() {
+        let (skeleton, [_, _, hand]) = create_arm();
+        let world = skeleton.world_matrices(&skeleton.bind_pose()).unwrap();
+        assert!(world[hand].w_axis.truncate().abs_diff_eq(vec3(2.0, 0.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn bind_pose_produces_identity_palette() {
+        let (skeleton, _) = create_arm();
+        let palette = skeleton.skinning_palette(&skeleton.bind_pose()).unwrap();
+        for matrix in palette {
+            assert!(matrix.abs_diff_eq(Mat4::IDENTITY, 1e-6));
+        }
+    }
+
+    #[test]
+    fn rotating_a_parent_moves_its_children() {
+        let (skeleton, [shoulder, elbow, hand]) = create_arm();
+        let mut pose = skeleton.bind_pose();
+        pose.bones[shoulder].rotation = Quat::from_rotation_z(std::f32::consts::FRAC_PI_2);
+
+        let world = skeleton.world_matrices(&pose).unwrap();
+        assert!(world[elbow].w_axis.truncate().abs_diff_eq(vec3(0.0, 1.0, 0.0), 1e-6));
+        assert!(world[hand].w_axis.truncate().abs_diff_eq(vec3(0.0, 2.0, 0.0), 1e-6));
+
+        // A vertex skinned to the hand at its rest position follows the hand
+        let palette = skeleton.skinning_palette(&pose).unwrap();
+        let skinned = palette[hand].transform_point3(vec3(2.0, 0.0, 0.0));
+        assert!(skinned.abs_diff_eq(vec3(0.0, 2.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn skeleton_transform_is_applied_to_roots() {
+        let (mut skeleton, [_, _, hand]) = create_arm();
+        skeleton.transform = Mat4::from_translation(vec3(0.0, 0.0, 5.0));
+        let world = skeleton.world_matrices(&skeleton.bind_pose()).unwrap();
+        assert!(world[hand].w_axis.truncate().abs_diff_eq(vec3(2.0, 0.0, 5.0), 1e-6));
+    }
+
+    #[test]
+    fn pose_size_must_match_skeleton() {
+        let (skeleton, _) = create_arm();
+        let pose = SkeletonPose::new(2);
+        assert_eq!(skeleton.world_matrices(&pose), Err(SkeletonError::PoseSizeMismatch));
+    }
+
+    #[test]
+    fn transform_round_trips_through_matrix() {
+        let transform = Transform::new(
+            vec3(1.0, -2.0, 3.0),
+            Quat::from_euler(EulerRot::XYZ, 0.3, -0.7, 1.1),
+            vec3(2.0, 2.0, 2.0),
+        );
+        let round_trip = Transform::from_mat4(transform.to_mat4());
+        assert!(round_trip.abs_diff_eq(&transform, 1e-5));
+    }
+
+    fn translation_channel(target: AnimationTarget, interpolation: Interpolation) -> AnimationChannel {
+        AnimationChannel {
+            target,
+            interpolation,
+            times: vec![0.0, 1.0, 2.0],
+            values: ChannelValues::Translation(vec![Vec3::ZERO, Vec3::X, vec3(1.0, 2.0, 0.0)]),
+        }
+    }
+
+    #[test]
+    fn linear_channel_interpolates_between_keys() {
+        let clip = AnimationClip::new(
+            None,
+            vec![translation_channel(AnimationTarget::Node(0), Interpolation::Linear)],
+        );
+        assert_eq!(clip.duration, 2.0);
+
+        let mut transform = Transform::IDENTITY;
+        clip.sample_transform(AnimationTarget::Node(0), 0.5, PlaybackMode::Clamp, &mut transform);
+        assert!(transform.translation.abs_diff_eq(vec3(0.5, 0.0, 0.0), 1e-6));
+        clip.sample_transform(AnimationTarget::Node(0), 1.5, PlaybackMode::Clamp, &mut transform);
+        assert!(transform.translation.abs_diff_eq(vec3(1.0, 1.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn step_channel_holds_previous_key() {
+        let clip = AnimationClip::new(
+            None,
+            vec![translation_channel(AnimationTarget::Node(0), Interpolation::Step)],
+        );
+        let mut transform = Transform::IDENTITY;
+        clip.sample_transform(AnimationTarget::Node(0), 0.99, PlaybackMode::Clamp, &mut transform);
+        assert_eq!(transform.translation, Vec3::ZERO);
+        clip.sample_transform(AnimationTarget::Node(0), 1.0, PlaybackMode::Clamp, &mut transform);
+        assert_eq!(transform.translation, Vec3::X);
+    }
+
+    #[test]
+    fn clamp_and_loop_playback_wrap_differently() {
+        let clip = AnimationClip::new(
+            None,
+            vec![translation_channel(AnimationTarget::Node(0), Interpolation::Linear)],
+        );
+        let mut transform = Transform::IDENTITY;
+        clip.sample_transform(AnimationTarget::Node(0), 5.0, PlaybackMode::Clamp, &mut transform);
+        assert!(transform.translation.abs_diff_eq(vec3(1.0, 2.0, 0.0), 1e-6));
+        clip.sample_transform(AnimationTarget::Node(0), 2.5, PlaybackMode::Loop, &mut transform);
+        assert!(transform.translation.abs_diff_eq(vec3(0.5, 0.0, 0.0), 1e-6));
+        clip.sample_transform(AnimationTarget::Node(0), -0.5, PlaybackMode::Loop, &mut transform);
+        assert!(transform.translation.abs_diff_eq(vec3(1.0, 1.0, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn cubic_spline_passes_through_keys_and_uses_tangents() {
+        // [in_tangent, value, out_tangent] per key, a straight line with unit tangents
+        let channel = AnimationChannel {
+            target: AnimationTarget::Node(0),
+            interpolation: Interpolation::CubicSpline,
+            times: vec![0.0, 2.0],
+            values: ChannelValues::Translation(vec![
+                Vec3::X,
+                Vec3::ZERO,
+                Vec3::X,
+                Vec3::X,
+                vec3(2.0, 0.0, 0.0),
+                Vec3::X,
+            ]),
+        };
+        let clip = AnimationClip::new(None, vec![channel]);
+        let mut transform = Transform::IDENTITY;
+        for time in [0.0, 0.5, 1.0, 1.5, 2.0] {
+            clip.sample_transform(AnimationTarget::Node(0), time, PlaybackMode::Clamp, &mut transform);
+            assert!((transform.translation.x - time).abs() < 1e-5, "Sample at {}", time);
+        }
+    }
+
+    #[test]
+    fn rotation_channel_is_slerped() {
+        let channel = AnimationChannel {
+            target: AnimationTarget::Bone(1),
+            interpolation: Interpolation::Linear,
+            times: vec![0.0, 1.0],
+            values: ChannelValues::Rotation(vec![
+                Quat::IDENTITY,
+                Quat::from_rotation_z(std::f32::consts::FRAC_PI_2),
+            ]),
+        };
+        let clip = AnimationClip::new(Some("bend".into()), vec![channel]);
+        let (skeleton, [_, elbow, hand]) = create_arm();
+
+        let mut pose = skeleton.bind_pose();
+        clip.sample_pose(0.5, PlaybackMode::Clamp, &mut pose);
+        let expected = Quat::from_rotation_z(std::f32::consts::FRAC_PI_4);
+        assert!(pose.bones[elbow].rotation.abs_diff_eq(expected, 1e-5));
+        assert_eq!(pose.bones[elbow].translation, Vec3::X, "Unanimated properties are kept");
+
+        clip.sample_pose(1.0, PlaybackMode::Clamp, &mut pose);
+        let world = skeleton.world_matrices(&pose).unwrap();
+        assert!(world[hand].w_axis.truncate().abs_diff_eq(vec3(1.0, 1.0, 0.0), 1e-5));
+    }
+
+    #[test]
+    fn clip_playback_advances_and_finishes() {
+        let clip = AnimationClip::new(
+            None,
+            vec![translation_channel(AnimationTarget::Node(0), Interpolation::Linear)],
+        );
+        let mut playback = ClipPlayback::new(PlaybackMode::Clamp);
+        playback.advance(&clip, 1.0);
+        assert_eq!(playback.normalized_time(&clip), 0.5);
+        playback.advance(&clip, 3.0);
+        assert!(playback.is_finished(&clip));
+        assert_eq!(playback.time, 2.0);
+
+        let mut looping = ClipPlayback::new(PlaybackMode::Loop);
+        looping.speed = 2.0;
+        looping.advance(&clip, 1.5);
+        assert!(!looping.is_finished(&clip));
+        assert_eq!(looping.local_time(&clip), 1.0);
+    }
+
+    /// Clip moving the shoulder of the arm along X from 0 to `distance` over `duration` seconds
+    fn shoulder_clip(distance: f32, duration: f32) -> AnimationClip {
+        AnimationClip::new(
+            None,
+            vec![AnimationChannel {
+                target: AnimationTarget::Bone(0),
+                interpolation: Interpolation::Linear,
+                times: vec![0.0, duration],
+                values: ChannelValues::Translation(vec![Vec3::ZERO, vec3(distance, 0.0, 0.0)]),
+            }],
+        )
+    }
+
+    #[test]
+    fn pose_blend_respects_mask() {
+        let (skeleton, [shoulder, elbow, hand]) = create_arm();
+        let mut pose = skeleton.bind_pose();
+        let mut target = skeleton.bind_pose();
+        for transform in target.bones.iter_mut() {
+            transform.translation += Vec3::Y;
+        }
+        let mask = BoneMask::from_hierarchy(&skeleton, &[elbow]);
+        assert_eq!(mask.weights, vec![0.0, 1.0, 1.0]);
+
+        pose.blend(&target, 0.5, Some(&mask));
+        assert_eq!(pose.bones[shoulder].translation, Vec3::ZERO);
+        assert!(pose.bones[elbow].translation.abs_diff_eq(vec3(1.0, 0.5, 0.0), 1e-6));
+        assert!(pose.bones[hand].translation.abs_diff_eq(vec3(1.0, 0.5, 0.0), 1e-6));
+    }
+
+    #[test]
+    fn weighted_blend_averages_poses() {
+        let mut a = SkeletonPose::new(1);
+        let mut b = SkeletonPose::new(1);
+        let mut c = SkeletonPose::new(1);
+        a.bones[0].translation = Vec3::ZERO;
+        b.bones[0].translation = vec3(3.0, 0.0, 0.0);
+        c.bones[0].translation = vec3(0.0, 6.0, 0.0);
+
+        let mut result = SkeletonPose::new(1);
+        assert!(result.blend_weighted([(&a, 1.0), (&b, 1.0), (&c, 1.0)]));
+        assert!(result.bones[0].translation.abs_diff_eq(vec3(1.0, 2.0, 0.0), 1e-5));
+        assert!(!result.blend_weighted([(&a, 0.0)]));
+    }
+
+    #[test]
+    fn additive_difference_round_trips() {
+        let mut reference = SkeletonPose::new(1);
+        reference.bones[0] = Transform::from_rotation(Quat::from_rotation_y(0.3));
+        let mut pose = reference.clone();
+        pose.bones[0].translation = vec3(0.0, 1.0, 0.0);
+        pose.bones[0].rotation = Quat::from_rotation_y(0.8);
+
+        let additive = pose.additive_difference(&reference);
+        let mut applied = reference.clone();
+        applied.apply_additive(&additive, 1.0, None);
+        assert!(applied.bones[0].abs_diff_eq(&pose.bones[0], 1e-5));
+
+        let mut half = reference.clone();
+        half.apply_additive(&additive, 0.5, None);
+        assert!(half.bones[0].rotation.abs_diff_eq(Quat::from_rotation_y(0.55), 1e-5));
+    }
+
+    #[test]
+    fn blend_space_1d_weights() {
+        let space = BlendSpace1D::new()
+            .with_point(4.0, 2)
+            .with_point(0.0, 0)
+            .with_point(1.5, 1);
+        assert_eq!(space.weights(-1.0), vec![(0, 1.0)]);
+        assert_eq!(space.weights(10.0), vec![(2, 1.0)]);
+        let weights = space.weights(2.75);
+        assert_eq!(weights[0].0, 1);
+        assert_eq!(weights[1].0, 2);
+        assert!((weights[0].1 - 0.5).abs() < 1e-6);
+        assert!((weights[1].1 - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn blend_space_2d_weights() {
+        let space = BlendSpace2D::new()
+            .with_point(Vec2::ZERO, 0)
+            .with_point(Vec2::X, 1)
+            .with_point(Vec2::Y, 2);
+        let at_point = space.weights(Vec2::X);
+        assert_eq!(at_point, vec![(1, 1.0)]);
+
+        let between = space.weights(vec2(0.5, 0.0));
+        let total: f32 = between.iter().map(|(_, weight)| weight).sum();
+        assert!((total - 1.0).abs() < 1e-6);
+        let weight_of = |clip| between.iter().find(|(c, _)| *c == clip).map(|(_, w)| *w).unwrap_or(0.0);
+        assert!((weight_of(0) - weight_of(1)).abs() < 1e-6);
+        assert!(weight_of(2) < weight_of(0));
+    }
+
+    #[test]
+    fn blend_space_motion_keeps_clips_in_sync() {
+        let (skeleton, [shoulder, _, _]) = create_arm();
+        let mut mixer = AnimationMixer::new(vec![shoulder_clip(1.0, 1.0), shoulder_clip(4.0, 2.0)]);
+        let space = BlendSpace1D::new().with_point(0.0, 0).with_point(1.0, 1);
+        let layer = mixer.add_layer(AnimationLayer::new(LayerBlendMode::Override));
+        mixer.layer_mut(layer).unwrap().play(MotionState::new(
+            Motion::BlendSpace1D {
+                space,
+                parameter: 0.5,
+            },
+            PlaybackMode::Loop,
+        ));
+
+        // Blended duration is 1.5 seconds, both clips are at half of their cycle
+        mixer.advance(0.75);
+        let state = mixer.layer(layer).unwrap().state.as_ref().unwrap();
+        assert!((state.normalized_time - 0.5).abs() < 1e-6);
+
+        let mut pose = skeleton.bind_pose();
+        mixer.evaluate(&skeleton, &mut pose);
+        assert!(pose.bones[shoulder].translation.abs_diff_eq(vec3(1.25, 0.0, 0.0), 1e-5));
+    }
+
+    #[test]
+    fn cross_fade_blends_and_finishes() {
+        let (skeleton, [shoulder, _, _]) = create_arm();
+        let mut mixer = AnimationMixer::new(vec![shoulder_clip(0.0, 1.0), shoulder_clip(2.0, 1.0)]);
+        let layer = mixer.add_layer(AnimationLayer::new(LayerBlendMode::Override));
+        mixer.layers[layer].play(MotionState::clip(0, PlaybackMode::Clamp));
+        mixer.layers[layer].cross_fade(MotionState::clip(1, PlaybackMode::Clamp), 1.0, false);
+
+        mixer.advance(0.5);
+        assert_eq!(mixer.layers[layer].fade_progress(), Some(0.5));
+        let mut pose = skeleton.bind_pose();
+        mixer.evaluate(&skeleton, &mut pose);
+        // Clip 1 is at 1.0 and weighted by half
+        assert!(pose.bones[shoulder].translation.abs_diff_eq(vec3(0.5, 0.0, 0.0), 1e-5));
+
+        mixer.advance(0.5);
+        assert!(!mixer.layers[layer].is_fading());
+        mixer.evaluate(&skeleton, &mut pose);
+        assert!(pose.bones[shoulder].translation.abs_diff_eq(vec3(2.0, 0.0, 0.0), 1e-5));
+    }
+
+    #[test]
+    fn synced_cross_fade_keeps_normalized_time() {
+        let mut mixer = AnimationMixer::new(vec![shoulder_clip(1.0, 1.0), shoulder_clip(1.0, 3.0)]);
+        let layer = mixer.add_layer(AnimationLayer::new(LayerBlendMode::Override));
+        mixer.layers[layer].play(MotionState::clip(0, PlaybackMode::Loop));
+        mixer.advance(0.25);
+        mixer.layers[layer].cross_fade(MotionState::clip(1, PlaybackMode::Loop), 0.5, true);
+        let state = mixer.layers[layer].state.as_ref().unwrap();
+        assert!((state.normalized_time - 0.25).abs() < 1e-6);
+
+        // At the start of the fade the rate follows the walk clip
+        mixer.advance(0.1);
+        let state = mixer.layers[layer].state.as_ref().unwrap();
+        assert!((state.normalized_time - 0.35).abs() < 1e-5);
+    }
+
+    #[test]
+    fn additive_layer_with_mask() {
+        let (skeleton, [shoulder, elbow, _]) = create_arm();
+        let lean = AnimationClip::new(
+            None,
+            vec![
+                AnimationChannel {
+                    target: AnimationTarget::Bone(elbow),
+                    interpolation: Interpolation::Linear,
+                    times: vec![0.0, 1.0],
+                    values: ChannelValues::Translation(vec![Vec3::X, vec3(1.0, 1.0, 0.0)]),
+                },
+                AnimationChannel {
+                    target: AnimationTarget::Bone(shoulder),
+                    interpolation: Interpolation::Linear,
+                    times: vec![0.0, 1.0],
+                    values: ChannelValues::Translation(vec![Vec3::ZERO, Vec3::Z]),
+                },
+            ],
+        );
+        let mut mixer = AnimationMixer::new(vec![shoulder_clip(2.0, 1.0), lean]);
+        mixer.add_layer(AnimationLayer::new(LayerBlendMode::Override));
+        mixer.add_layer(
+            AnimationLayer::new(LayerBlendMode::Additive)
+                .with_weight(0.5)
+                .with_mask(BoneMask::from_hierarchy(&skeleton, &[elbow])),
+        );
+        mixer.layers[0].play(MotionState::clip(0, PlaybackMode::Clamp));
+        mixer.layers[1].play(MotionState::clip(1, PlaybackMode::Clamp));
+        mixer.advance(1.0);
+
+        let mut pose = skeleton.bind_pose();
+        mixer.evaluate(&skeleton, &mut pose);
+        assert!(pose.bones[shoulder].translation.abs_diff_eq(vec3(2.0, 0.0, 0.0), 1e-5));
+        assert!(pose.bones[elbow].translation.abs_diff_eq(vec3(1.0, 0.5, 0.0), 1e-5));
+    }
+
+    const LOCOMOTION_JSON: &str = r#"{
+        "parameters": {
+            "speed": { "Float": 0.0 },
+            "grounded": { "Bool": true },
+            "jump": { "Trigger": false }
+        },
+        "states": [
+            {
+                "name": "locomotion",
+                "motion": { "BlendSpace1D": { "parameter": "speed", "points": [[0.0, "idle"], [2.0, "run"]] } }
+            },
+            { "name": "jump", "motion": { "Clip": "jump" }, "mode": "Clamp" },
+            { "name": "fall", "motion": { "Clip": "idle" } }
+        ],
+        "default_state": "locomotion",
+        "transitions": [
+            { "from": "locomotion", "to": "jump", "conditions": [{ "Trigger": "jump" }], "duration": 0.2 },
+            { "from": "jump", "to": "locomotion", "exit_time": 1.0, "duration": 0.1 },
+            { "to": "fall", "conditions": [{ "IsFalse": "grounded" }] }
+        ]
+    }"#;
+
+    fn create_locomotion_mixer() -> AnimationMixer {
+        let mut idle = shoulder_clip(0.0, 1.0);
+        idle.name = Some("idle".into());
+        let mut run = shoulder_clip(2.0, 1.0);
+        run.name = Some("run".into());
+        let mut jump = shoulder_clip(1.0, 0.5);
+        jump.name = Some("jump".into());
+        let mut mixer = AnimationMixer::new(vec![idle, run, jump]);
+        mixer.add_layer(AnimationLayer::new(LayerBlendMode::Override));
+        mixer
+    }
+
+    #[test]
+    fn state_machine_definition_round_trips_json() {
+        let definition = AnimationStateMachineDefinition::from_json(LOCOMOTION_JSON).unwrap();
+        assert_eq!(definition.states.len(), 3);
+        assert_eq!(definition.states[1].mode, PlaybackMode::Clamp);
+        assert_eq!(definition.states[0].speed, 1.0);
+        assert_eq!(definition.transitions[2].from, None);
+
+        let json = definition.to_json().unwrap();
+        assert_eq!(AnimationStateMachineDefinition::from_json(&json).unwrap(), definition);
+    }
+
+    #[test]
+    fn state_machine_rejects_unknown_names() {
+        let mut definition = AnimationStateMachineDefinition::from_json(LOCOMOTION_JSON).unwrap();
+        definition.states[2].motion = MotionDefinition::Clip("swim".into());
+        let mut mixer = create_locomotion_mixer();
+        assert_eq!(
+            AnimationStateMachine::new(&definition, &mut mixer, 0).err(),
+            Some(StateMachineError::UnknownClip("swim".into()))
+        );
+
+        let definition = AnimationStateMachineDefinition::from_json(LOCOMOTION_JSON).unwrap();
+        let mut state_machine = AnimationStateMachine::new(&definition, &mut mixer, 0).unwrap();
+        assert_eq!(
+            state_machine.set_bool("speed", true),
+            Err(StateMachineError::ParameterTypeMismatch("speed".into()))
+        );
+        assert_eq!(
+            state_machine.set_float("height", 1.0),
+            Err(StateMachineError::UnknownParameter("height".into()))
+        );
+    }
+
+    #[test]
+    fn state_machine_rejects_mismatched_parameter_kinds() {
+        let mut mixer = create_locomotion_mixer();
+        let mismatched = [
+            (r#"{ "Trigger": "grounded" }"#, "grounded"),
+            (r#"{ "IsTrue": "jump" }"#, "jump"),
+            (r#"{ "Greater": { "parameter": "grounded", "value": 1.0 } }"#, "grounded"),
+        ];
+        for (condition, parameter) in mismatched {
+            let json = LOCOMOTION_JSON.replace(r#"{ "Trigger": "jump" }"#, condition);
+            let definition = AnimationStateMachineDefinition::from_json(&json).unwrap();
+            assert_eq!(
+                AnimationStateMachine::new(&definition, &mut mixer, 0).err(),
+                Some(StateMachineError::ParameterTypeMismatch(parameter.into()))
+            );
+        }
+
+        let json = LOCOMOTION_JSON.replace(
+            r#""BlendSpace1D": { "parameter": "speed""#,
+            r#""BlendSpace1D": { "parameter": "jump""#,
+        );
+        let definition = AnimationStateMachineDefinition::from_json(&json).unwrap();
+        assert_eq!(
+            AnimationStateMachine::new(&definition, &mut mixer, 0).err(),
+            Some(StateMachineError::ParameterTypeMismatch("jump".into()))
+        );
+    }
+
+    #[test]
+    fn state_machine_follows_transitions() {
+        let definition = AnimationStateMachineDefinition::from_json(LOCOMOTION_JSON).unwrap();
+        let mut mixer = create_locomotion_mixer();
+        let mut state_machine = AnimationStateMachine::new(&definition, &mut mixer, 0).unwrap();
+        assert_eq!(state_machine.current_state(), "locomotion");
+
+        // The blend space parameter follows the speed parameter
+        state_machine.set_float("speed", 1.0).unwrap();
+        assert!(!state_machine.update(&mut mixer).unwrap());
+        mixer.advance(0.5);
+        let (skeleton, [shoulder, _, _]) = create_arm();
+        let mut pose = skeleton.bind_pose();
+        mixer.evaluate(&skeleton, &mut pose);
+        assert!(pose.bones[shoulder].translation.abs_diff_eq(vec3(0.5, 0.0, 0.0), 1e-5));
+
+        state_machine.set_trigger("jump").unwrap();
+        assert!(state_machine.update(&mut mixer).unwrap());
+        assert_eq!(state_machine.current_state(), "jump");
+        assert_eq!(state_machine.parameter("jump"), Some(Parameter::Trigger(false)));
+        assert!(mixer.layers[0].is_fading());
+
+        // Jump returns to locomotion once it reaches its exit time
+        mixer.advance(0.3);
+        assert!(!state_machine.update(&mut mixer).unwrap());
+        mixer.advance(0.3);
+        assert!(state_machine.update(&mut mixer).unwrap());
+        assert_eq!(state_machine.current_state(), "locomotion");
+
+        // Any state transition
+        mixer.advance(0.2);
+        state_machine.set_bool("grounded", false).unwrap();
+        assert!(state_machine.update(&mut mixer).unwrap());
+        assert_eq!(state_machine.current_state(), "fall");
+        mixer.advance(0.2);
+        assert!(!state_machine.update(&mut mixer).unwrap());
+    }
+
+    fn world_position(skeleton: &Skeleton, pose: &SkeletonPose, bone: BoneIndex) -> Vec3 {
+        skeleton.world_matrices(pose).unwrap()[bone].w_axis.truncate()
+    }
+
+    #[test]
+    fn two_bone_ik_reaches_target() {
+        let (skeleton, [shoulder, elbow, hand]) = create_arm();
+        let ik = TwoBoneIk::new(shoulder, elbow, hand);
+        for target in [vec3(1.0, 1.0, 0.0), vec3(0.5, 0.0, 1.2), vec3(-1.0, 0.3, -0.4)] {
+            let mut pose = skeleton.bind_pose();
+            assert!(ik.solve(&skeleton, &mut pose, target).unwrap());
+            let reached = world_position(&skeleton, &pose, hand);
+            assert!(reached.abs_diff_eq(target, 1e-3), "{} != {}", reached, target);
+            let elbow_distance = world_position(&skeleton, &pose, elbow).length();
+            assert!((elbow_distance - 1.0).abs() < 1e-4, "Bone lengths are kept");
+        }
+    }
+
+    #[test]
+    fn two_bone_ik_bends_towards_pole() {
+        let (skeleton, [shoulder, elbow, hand]) = create_arm();
+        let target = vec3(1.5, 0.0, 0.0);
+        for pole in [vec3(0.75, 0.0, 2.0), vec3(0.75, -2.0, 0.0)] {
+            let mut pose = skeleton.bind_pose();
+            let ik = TwoBoneIk::new(shoulder, elbow, hand).with_pole(pole);
+            assert!(ik.solve(&skeleton, &mut pose, target).unwrap());
+            assert!(world_position(&skeleton, &pose, hand).abs_diff_eq(target, 1e-3));
+            let elbow_position = world_position(&skeleton, &pose, elbow);
+            let pole_direction = (pole - vec3(0.75, 0.0, 0.0)).normalize();
+            assert!(elbow_position.dot(pole_direction) > 0.5, "Elbow at {}", elbow_position);
+        }
+    }
+
+    #[test]
+    fn two_bone_ik_stretches_to_unreachable_target() {
+        let (skeleton, [shoulder, elbow, hand]) = create_arm();
+        let mut pose = skeleton.bind_pose();
+        let ik = TwoBoneIk::new(shoulder, elbow, hand);
+        assert!(!ik.solve(&skeleton, &mut pose, vec3(0.0, 5.0, 0.0)).unwrap());
+        assert!(world_position(&skeleton, &pose, hand).abs_diff_eq(vec3(0.0, 2.0, 0.0), 1e-2));
+    }
+
+    #[test]
+    fn look_at_rotates_forward_axis() {
+        let (skeleton, [_, elbow, hand]) = create_arm();
+        let mut pose = skeleton.bind_pose();
+        let target = vec3(1.0, 3.0, 0.0);
+        LookAtConstraint::new(elbow, Vec3::X)
+            .solve(&skeleton, &mut pose, target)
+            .unwrap();
+        assert!(world_position(&skeleton, &pose, hand).abs_diff_eq(vec3(1.0, 1.0, 0.0), 1e-5));
+
+        let mut limited = skeleton.bind_pose();
+        LookAtConstraint::new(elbow, Vec3::X)
+            .with_max_angle(0.5)
+            .solve(&skeleton, &mut limited, target)
+            .unwrap();
+        let angle = limited.bones[elbow].rotation.angle_between(Quat::IDENTITY);
+        assert!((angle - 0.5).abs() < 1e-4);
+    }
+
+    fn create_chain(length: usize) -> (Skeleton, Vec<BoneIndex>) {
+        let mut skeleton = Skeleton::new();
+        let mut bones = vec![skeleton.add_bone(None, None, Transform::IDENTITY).unwrap()];
+        for _ in 1..length {
+            let parent = *bones.last().unwrap();
+            let bone = skeleton
+                .add_bone(None, Some(parent), Transform::from_translation(Vec3::X))
+                .unwrap();
+            bones.push(bone);
+        }
+        (skeleton, bones)
+    }
+
+    #[test]
+    fn fabrik_reaches_target_and_keeps_lengths() {
+        let (skeleton, bones) = create_chain(5);
+        let mut pose = skeleton.bind_pose();
+        let target = vec3(1.0, 2.0, 1.5);
+        let chain = FabrikChain::new(bones.clone()).with_tolerance(1e-4, 64);
+        assert!(chain.solve(&skeleton, &mut pose, target).unwrap());
+        let positions: Vec<Vec3> = bones
+            .iter()
+            .map(|bone| world_position(&skeleton, &pose, *bone))
+            .collect();
+        assert!(positions[4].abs_diff_eq(target, 1e-3));
+        for pair in positions.windows(2) {
+            assert!(((pair[1] - pair[0]).length() - 1.0).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn fabrik_respects_joint_limits() {
+        let (skeleton, bones) = create_chain(4);
+        let limit = 0.4;
+        for limited_joint in 1..3 {
+            let mut pose = skeleton.bind_pose();
+            let chain = FabrikChain::new(bones.clone()).with_limit(limited_joint, limit);
+            chain.solve(&skeleton, &mut pose, vec3(0.0, 2.0, 0.0)).unwrap();
+            let positions: Vec<Vec3> = bones
+                .iter()
+                .map(|bone| world_position(&skeleton, &pose, *bone))
+                .collect();
+            let bend = |joint: usize| {
+                let incoming = positions[joint] - positions[joint - 1];
+                let outgoing = positions[joint + 1] - positions[joint];
+                incoming.angle_between(outgoing)
+            };
+            assert!(bend(limited_joint) <= limit + 1e-3, "Joint {}", limited_joint);
+            let other_joint = 3 - limited_joint;
+            assert!(bend(other_joint) > limit, "Joint {} is free", other_joint);
+        }
+
+        // The root and the end effector have no bend to limit
+        let chain = FabrikChain::new(bones.clone())
+            .with_limit(0, limit)
+            .with_limit(3, limit)
+            .with_limit(10, limit);
+        assert_eq!(chain.limits, vec![None; 4]);
+    }
+
+    #[test]
+    fn fabrik_stretches_to_unreachable_target() {
+        let (skeleton, bones) = create_chain(3);
+        let mut pose = skeleton.bind_pose();
+        let chain = FabrikChain::new(bones.clone());
+        assert!(!chain.solve(&skeleton, &mut pose, vec3(0.0, 0.0, -10.0)).unwrap());
+        let end = world_position(&skeleton, &pose, bones[2]);
+        assert!(end.abs_diff_eq(vec3(0.0, 0.0, -2.0), 1e-4));
+        assert_eq!(
+            FabrikChain::new(vec![bones[2], bones[0]]).solve(&skeleton, &mut pose, Vec3::ZERO),
+            Err(SkeletonError::InvalidBone)
+        );
+    }
+
+    #[test]
+    fn weight_channel_samples_every_target() {
+        let channel = AnimationChannel {
+            target: AnimationTarget::Node(3),
+            interpolation: Interpolation::Linear,
+            times: vec![0.0, 1.0],
+            values: ChannelValues::Weights {
+                target_count: 2,
+                values: vec![0.0, 1.0, 1.0, 0.5],
+            },
+        };
+        let clip = AnimationClip::new(None, vec![channel]);
+        let mut weights = Vec::new();
+        clip.sample_weights(AnimationTarget::Node(3), 0.5, PlaybackMode::Clamp, &mut weights);
+        assert_eq!(weights, vec![0.5, 0.75]);
+
+        let mut transform = Transform::IDENTITY;
+        clip.sample_transform(AnimationTarget::Node(3), 0.5, PlaybackMode::Clamp, &mut transform);
+        assert_eq!(transform, Transform::IDENTITY);
+    }
+
+    #[test]
+    fn cubic_spline_weight_channel() {
+        // Per key: in tangents, values and out tangents of both targets
+        let channel = AnimationChannel {
+            target: AnimationTarget::Node(0),
+            interpolation: Interpolation::CubicSpline,
+            times: vec![0.0, 1.0],
+            values: ChannelValues::Weights {
+                target_count: 2,
+                values: vec![
+                    0.0, 0.0, 0.0, 1.0, 1.0, 0.0, //
+                    1.0, 0.0, 1.0, 1.0, 1.0, 0.0,
+                ],
+            },
+        };
+        let mut weights = vec![0.0; 2];
+        channel.sample_weights(0.5, &mut weights);
+        assert!((weights[0] - 0.5).abs() < 1e-6);
+        assert!((weights[1] - 1.0).abs() < 1e-6);
+    }
+
+    fn event_names(events: &[&AnimationEvent]) -> Vec<String> {
+        events.iter().map(|event| event.name.clone()).collect()
+    }
+
+    #[test]
+    fn events_fire_across_loop_wraps() {
+        let clip = shoulder_clip(1.0, 1.0)
+            .with_event("right", 0.75)
+            .with_event("left", 0.25);
+        let mut playback = ClipPlayback::new(PlaybackMode::Loop);
+        assert_eq!(event_names(&playback.advance(&clip, 0.5)), vec!["left"]);
+        assert_eq!(event_names(&playback.advance(&clip, 0.5)), vec!["right"]);
+        assert!(playback.advance(&clip, 0.2).is_empty());
+
+        // A long frame crosses several cycles, every event fires once per cycle
+        let events = playback.advance(&clip, 2.6);
+        assert_eq!(
+            event_names(&events),
+            vec!["left", "right", "left", "right", "left", "right"]
+        );
+
+        playback.speed = -1.0;
+        assert_eq!(event_names(&playback.advance(&clip, 0.6)), vec!["right", "left"]);
+    }
+
+    #[test]
+    fn clamped_events_fire_once() {
+        let clip = shoulder_clip(1.0, 1.0).with_event("hit", 1.0);
+        let mut playback = ClipPlayback::new(PlaybackMode::Clamp);
+        assert!(playback.advance(&clip, 0.5).is_empty());
+        assert_eq!(event_names(&playback.advance(&clip, 5.0)), vec!["hit"]);
+        assert!(playback.advance(&clip, 5.0).is_empty());
+    }
+
+    #[test]
+    fn mixer_events_are_weighted() {
+        let walk = shoulder_clip(1.0, 1.0).with_event("step", 0.5);
+        let mut mixer = AnimationMixer::new(vec![walk, shoulder_clip(1.0, 1.0)]);
+        mixer.add_layer(AnimationLayer::new(LayerBlendMode::Override));
+        let layer = mixer.add_layer(AnimationLayer::new(LayerBlendMode::Override).with_weight(0.5));
+        mixer.layers[layer].play(MotionState::clip(0, PlaybackMode::Loop));
+
+        let update = mixer.advance(1.75);
+        assert_eq!(update.events.len(), 2);
+        assert_eq!(update.events[0].layer, layer);
+        assert_eq!(update.events[0].name, "step");
+        assert!((update.events[0].weight - 0.5).abs() < 1e-6);
+
+        // Events of the clip fading out lose weight as the fade progresses
+        mixer.layers[layer].cross_fade(MotionState::clip(1, PlaybackMode::Loop), 1.0, false);
+        mixer.advance(0.5);
+        let update = mixer.advance(0.5);
+        assert_eq!(update.events.len(), 1);
+        assert!((update.events[0].weight - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn root_motion_accumulates_loops() {
+        let (skeleton, [shoulder, _, _]) = create_arm();
+        let mut mixer = AnimationMixer::new(vec![shoulder_clip(2.0, 1.0)])
+            .with_root_motion(RootMotion::new(&skeleton, shoulder));
+        let layer = mixer.add_layer(AnimationLayer::new(LayerBlendMode::Override));
+        mixer.layers[layer].play(MotionState::clip(0, PlaybackMode::Loop));
+
+        let update = mixer.advance(2.5);
+        assert!(update.root_motion.translation.abs_diff_eq(vec3(5.0, 0.0, 0.0), 1e-4));
+        let mut pose = skeleton.bind_pose();
+        mixer.evaluate(&skeleton, &mut pose);
+        assert!(pose.bones[shoulder].translation.abs_diff_eq(Vec3::ZERO, 1e-6));
+
+        let update = mixer.advance(0.25);
+        assert!(update.root_motion.translation.abs_diff_eq(vec3(0.5, 0.0, 0.0), 1e-4));
+    }
+
+    #[test]
+    fn root_motion_follows_rotation() {
+        let (skeleton, [shoulder, _, _]) = create_arm();
+        let turn = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let clip = AnimationClip::new(
+            None,
+            vec![
+                AnimationChannel {
+                    target: AnimationTarget::Bone(shoulder),
+                    interpolation: Interpolation::Linear,
+                    times: vec![0.0, 1.0],
+                    values: ChannelValues::Translation(vec![Vec3::ZERO, Vec3::Z]),
+                },
+                AnimationChannel {
+                    target: AnimationTarget::Bone(shoulder),
+                    interpolation: Interpolation::Linear,
+                    times: vec![0.0, 1.0],
+                    values: ChannelValues::Rotation(vec![Quat::IDENTITY, turn]),
+                },
+            ],
+        );
+        let root_motion = RootMotion::new(&skeleton, shoulder).with_rotation(true);
+
+        // The second cycle starts facing the direction the first one ended with
+        let delta = root_motion.delta(&clip, 0.0, 2.0, PlaybackMode::Loop);
+        assert!(delta.translation.abs_diff_eq(vec3(1.0, 0.0, 1.0), 1e-4));
+        assert!(delta.rotation.angle_between(turn * turn) < 1e-3);
+
+        let back = root_motion.delta(&clip, 1.0, 0.0, PlaybackMode::Loop);
+        let forward = root_motion.delta(&clip, 0.0, 1.0, PlaybackMode::Loop);
+        assert!(forward.mul_transform(&back).abs_diff_eq(&Transform::IDENTITY, 1e-4));
+
+        let ground = RootMotion::new(&skeleton, shoulder).with_axes(vec3(1.0, 0.0, 1.0));
+        let mut pose = skeleton.bind_pose();
+        pose.bones[shoulder].translation = vec3(1.0, 0.5, 2.0);
+        ground.remove_from_pose(&mut pose);
+        assert_eq!(pose.bones[shoulder].translation, vec3(0.0, 0.5, 0.0));
+    }
+}
@@ -0,0 +1,332 @@
+use glam::*;
+
+use crate::time::Time;
+use crate::transform::Transform;
+use crate::{Orientable, Translatable};
+
+/// Input of one frame for the camera controllers, filled from mouse, keyboard, touch or
+/// gamepad events by the application
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CameraInput {
+    /// Pointer movement, positive x to the right and positive y down
+    pub look: Vec2,
+    /// Scroll steps, positive values move the camera closer
+    pub zoom: f32,
+    /// Movement axes between -1 and 1: x right, y up and z forward
+    pub movement: Vec3,
+    /// Moves faster while held, e.g. shift
+    pub boost: bool,
+}
+
+/// Rotation of a camera looking down -Z that points it along `forward`, keeping `up` as close
+/// as possible to its up axis
+pub fn look_rotation(forward: Vec3, up: Vec3) -> Quat {
+    let forward = match forward.try_normalize() {
+        Some(forward) => forward,
+        None => return Quat::IDENTITY,
+    };
+    let right = forward
+        .cross(up)
+        .try_normalize()
+        .unwrap_or_else(|| forward.any_orthonormal_vector());
+    let up = right.cross(forward);
+    Quat::from_mat3(&Mat3::from_cols(right, up, -forward)).normalize()
+}
+
+/// Heading around +Y followed by pitch around the camera right axis, both in radians
+fn yaw_pitch_rotation(yaw: f32, pitch: f32) -> Quat {
+    Quat::from_euler(EulerRot::YXZ, yaw, pitch, 0.0)
+}
+
+/// Rotates around a target point, the zoom changes the distance to it
+#[derive(Debug, Clone)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    /// Radians per unit of look input
+    pub rotate_sensitivity: f32,
+    /// Fraction of the distance removed per zoom step
+    pub zoom_sensitivity: f32,
+}
+
+impl OrbitController {
+    pub fn new(target: Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            min_distance: 0.1,
+            max_distance: f32::INFINITY,
+            min_pitch: -89f32.to_radians(),
+            max_pitch: 89f32.to_radians(),
+            rotate_sensitivity: 0.005,
+            zoom_sensitivity: 0.1,
+        }
+    }
+
+    pub fn with_distance_limits(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self.distance = self.distance.clamp(min_distance, max_distance);
+        self
+    }
+
+    pub fn with_pitch_limits(mut self, min_pitch: f32, max_pitch: f32) -> Self {
+        self.min_pitch = min_pitch;
+        self.max_pitch = max_pitch;
+        self.pitch = self.pitch.clamp(min_pitch, max_pitch);
+        self
+    }
+
+    pub fn orientation(&self) -> Quat {
+        yaw_pitch_rotation(self.yaw, self.pitch)
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.target + self.orientation() * Vec3::Z * self.distance
+    }
+
+    /// Orbiting does not depend on the frame time, the delta is accepted for consistency with
+    /// the other controllers
+    pub fn advance<C: Translatable + Orientable>(
+        &mut self,
+        input: &CameraInput,
+        _delta: f32,
+        camera: &mut C,
+    ) {
+        self.yaw -= input.look.x * self.rotate_sensitivity;
+        self.pitch = (self.pitch - input.look.y * self.rotate_sensitivity)
+            .clamp(self.min_pitch, self.max_pitch);
+        self.distance = (self.distance * (1.0 - self.zoom_sensitivity).powf(input.zoom))
+            .clamp(self.min_distance, self.max_distance);
+        camera.set_orientation(self.orientation());
+        camera.set_position(self.position());
+    }
+
+    pub fn update<C: Translatable + Orientable>(
+        &mut self,
+        input: &CameraInput,
+        time: &Time,
+        camera: &mut C,
+    ) {
+        self.advance(input, time.delta_time_seconds().0 as f32, camera);
+    }
+}
+
+/// Free camera, moves along the view direction like a spectator
+#[derive(Debug, Clone)]
+pub struct FlyController {
+    pub yaw: f32,
+    pub pitch: f32,
+    /// Units per second
+    pub speed: f32,
+    pub boost_multiplier: f32,
+    pub look_sensitivity: f32,
+}
+
+impl FlyController {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+            speed,
+            boost_multiplier: 4.0,
+            look_sensitivity: 0.003,
+        }
+    }
+
+    pub fn orientation(&self) -> Quat {
+        yaw_pitch_rotation(self.yaw, self.pitch)
+    }
+
+    pub fn advance<C: Translatable + Orientable>(
+        &mut self,
+        input: &CameraInput,
+        delta: f32,
+        camera: &mut C,
+    ) {
+        let limit = 89f32.to_radians();
+        self.yaw -= input.look.x * self.look_sensitivity;
+        self.pitch = (self.pitch - input.look.y * self.look_sensitivity).clamp(-limit, limit);
+        let orientation = self.orientation();
+
+        let mut speed = self.speed;
+        if input.boost {
+            speed *= self.boost_multiplier;
+        }
+        let movement = input.movement.clamp_length_max(1.0);
+        let local_movement = vec3(movement.x, 0.0, -movement.z);
+        let velocity = (orientation * local_movement + Vec3::Y * movement.y) * speed;
+        camera.set_orientation(orientation);
+        camera.translate(velocity * delta);
+    }
+
+    pub fn update<C: Translatable + Orientable>(
+        &mut self,
+        input: &CameraInput,
+        time: &Time,
+        camera: &mut C,
+    ) {
+        self.advance(input, time.delta_time_seconds().0 as f32, camera);
+    }
+}
+
+/// Walking camera, the movement stays on the horizontal plane and the pitch is limited
+#[derive(Debug, Clone)]
+pub struct FirstPersonController {
+    pub yaw: f32,
+    pub pitch: f32,
+    pub min_pitch: f32,
+    pub max_pitch: f32,
+    /// Units per second
+    pub speed: f32,
+    pub boost_multiplier: f32,
+    pub look_sensitivity: f32,
+}
+
+impl FirstPersonController {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+            min_pitch: -85f32.to_radians(),
+            max_pitch: 85f32.to_radians(),
+            speed,
+            boost_multiplier: 2.0,
+            look_sensitivity: 0.003,
+        }
+    }
+
+    pub fn with_pitch_limits(mut self, min_pitch: f32, max_pitch: f32) -> Self {
+        self.min_pitch = min_pitch;
+        self.max_pitch = max_pitch;
+        self.pitch = self.pitch.clamp(min_pitch, max_pitch);
+        self
+    }
+
+    pub fn orientation(&self) -> Quat {
+        yaw_pitch_rotation(self.yaw, self.pitch)
+    }
+
+    pub fn advance<C: Translatable + Orientable>(
+        &mut self,
+        input: &CameraInput,
+        delta: f32,
+        camera: &mut C,
+    ) {
+        self.yaw =
+            (self.yaw - input.look.x * self.look_sensitivity).rem_euclid(std::f32::consts::TAU);
+        self.pitch = (self.pitch - input.look.y * self.look_sensitivity)
+            .clamp(self.min_pitch, self.max_pitch);
+
+        let mut speed = self.speed;
+        if input.boost {
+            speed *= self.boost_multiplier;
+        }
+        let movement = vec2(input.movement.x, input.movement.z).clamp_length_max(1.0);
+        let heading = Quat::from_rotation_y(self.yaw);
+        let velocity = heading * vec3(movement.x, 0.0, -movement.y) * speed;
+        camera.set_orientation(self.orientation());
+        camera.translate(velocity * delta);
+    }
+
+    pub fn update<C: Translatable + Orientable>(
+        &mut self,
+        input: &CameraInput,
+        time: &Time,
+        camera: &mut C,
+    ) {
+        self.advance(input, time.delta_time_seconds().0 as f32, camera);
+    }
+}
+
+/// Third person camera pulled by a damped spring to an offset behind the target
+#[derive(Debug, Clone)]
+pub struct FollowController {
+    /// Desired position in the space of the target
+    pub offset: Vec3,
+    /// Point the camera looks at, in the space of the target
+    pub look_offset: Vec3,
+    /// Angular frequency of the spring in radians per second, higher values follow tighter
+    pub frequency: f32,
+    /// 1 reaches the target without overshooting, lower values bounce
+    pub damping_ratio: f32,
+    /// How fast the camera turns towards the look point, per second
+    pub rotation_sharpness: f32,
+    pub velocity: Vec3,
+}
+
+impl FollowController {
+    /// Longest integration step, keeps stiff springs stable with long frames
+    const MAX_STEP: f32 = 1.0 / 120.0;
+
+    pub fn new(offset: Vec3, look_offset: Vec3) -> Self {
+        Self {
+            offset,
+            look_offset,
+            frequency: 8.0,
+            damping_ratio: 1.0,
+            rotation_sharpness: 10.0,
+            velocity: Vec3::ZERO,
+        }
+    }
+
+    pub fn with_spring(mut self, frequency: f32, damping_ratio: f32) -> Self {
+        self.frequency = frequency;
+        self.damping_ratio = damping_ratio;
+        self
+    }
+
+    pub fn desired_position(&self, target: &Transform) -> Vec3 {
+        target.translation + target.rotation * self.offset
+    }
+
+    /// Places the camera at its resting position, e.g. after a teleport
+    pub fn snap<C: Translatable + Orientable>(&mut self, target: &Transform, camera: &mut C) {
+        let position = self.desired_position(target);
+        let look_point = target.translation + target.rotation * self.look_offset;
+        self.velocity = Vec3::ZERO;
+        camera.set_position(position);
+        camera.set_orientation(look_rotation(look_point - position, Vec3::Y));
+    }
+
+    pub fn advance<C: Translatable + Orientable>(
+        &mut self,
+        target: &Transform,
+        delta: f32,
+        camera: &mut C,
+    ) {
+        let goal = self.desired_position(target);
+        let mut position = camera.get_position();
+        let steps = (delta / Self::MAX_STEP).ceil().max(1.0);
+        let step = delta / steps;
+        for _ in 0..steps as u32 {
+            let acceleration = (goal - position) * self.frequency * self.frequency
+                - self.velocity * 2.0 * self.damping_ratio * self.frequency;
+            self.velocity += acceleration * step;
+            position += self.velocity * step;
+        }
+        camera.set_position(position);
+
+        let look_point = target.translation + target.rotation * self.look_offset;
+        let look = look_rotation(look_point - position, Vec3::Y);
+        let t = 1.0 - (-self.rotation_sharpness * delta).exp();
+        camera.set_orientation(camera.get_orientation().slerp(look, t));
+    }
+
+    pub fn update<C: Translatable + Orientable>(
+        &mut self,
+        target: &Transform,
+        time: &Time,
+        camera: &mut C,
+    ) {
+        self.advance(target, time.delta_time_seconds().0 as f32, camera);
+    }
+}
@@ -1,17 +1,48 @@
 mod perspective;
-use glam::Mat4;
+use glam::{vec2, Mat4, Vec2};
 pub use perspective::*;
 use rust_webgl2::*;
 
+use crate::renderer::DepthConvention;
+
 mod xr;
 pub use xr::*;
 
+mod orthographic;
+pub use orthographic::*;
+
+mod controllers;
+pub use controllers::*;
+
+mod projection;
+pub use projection::*;
+
+mod modifiers;
+pub use modifiers::*;
+
+mod test;
+
 #[derive(Clone, Copy)]
 pub struct CameraMatrices {
     pub transform_matrix: Mat4,
     pub view_matrix: Mat4,
     pub projection_matrix: Mat4,
     pub projection_view_matrix: Mat4,
+    pub inverse_projection_matrix: Mat4,
+    pub inverse_projection_view_matrix: Mat4,
+    /// Projection view of the previous frame, set with `set_previous` for motion vectors
+    pub previous_projection_view_matrix: Mat4,
+    /// Size in pixels of the viewport the camera renders to
+    pub viewport_size: Vec2,
+    pub z_near: f32,
+    /// Infinite for projections without a far plane
+    pub z_far: f32,
+    /// Seconds, see `Time::time_seconds`
+    pub time: f32,
+    /// Sub pixel offset of the projection in pixels
+    pub jitter: Vec2,
+    /// Clip space of the projection, has to match the one of the renderer
+    pub depth_convention: DepthConvention,
 }
 
 impl CameraMatrices{
@@ -21,35 +52,182 @@ impl CameraMatrices{
             view_matrix: Mat4::IDENTITY,
             projection_matrix: Mat4::IDENTITY,
             projection_view_matrix: Mat4::IDENTITY,
+            inverse_projection_matrix: Mat4::IDENTITY,
+            inverse_projection_view_matrix: Mat4::IDENTITY,
+            previous_projection_view_matrix: Mat4::IDENTITY,
+            viewport_size: Vec2::ZERO,
+            z_near: -1.0,
+            z_far: 1.0,
+            time: 0.0,
+            jitter: Vec2::ZERO,
+            depth_convention: DepthConvention::Standard,
         }
     }
+
+    /// Derives the view and inverse matrices, the near and far planes are read from the
+    /// projection
+    pub fn new(transform_matrix: Mat4, projection_matrix: Mat4) -> Self {
+        Self::with_depth_convention(transform_matrix, projection_matrix, DepthConvention::Standard)
+    }
+
+    /// Matrices of a projection to the zero to one clip space of `DepthConvention::Reversed`
+    pub fn with_depth_convention(
+        transform_matrix: Mat4,
+        projection_matrix: Mat4,
+        depth_convention: DepthConvention,
+    ) -> Self {
+        let view_matrix = transform_matrix.inverse();
+        let projection_view_matrix = projection_matrix.mul_mat4(&view_matrix);
+        let mut camera_matrices = Self {
+            transform_matrix,
+            view_matrix,
+            projection_matrix,
+            projection_view_matrix,
+            inverse_projection_matrix: projection_matrix.inverse(),
+            inverse_projection_view_matrix: projection_view_matrix.inverse(),
+            previous_projection_view_matrix: projection_view_matrix,
+            depth_convention,
+            ..Self::identity()
+        };
+        let (near, far) = (
+            camera_matrices.linear_depth(0.0),
+            camera_matrices.linear_depth(1.0),
+        );
+        let (z_near, z_far) = match (near.is_finite(), far.is_finite()) {
+            (true, true) => (near.min(far), near.max(far)),
+            (true, false) => (near, f32::INFINITY),
+            (false, true) => (far, f32::INFINITY),
+            (false, false) => (0.0, f32::INFINITY),
+        };
+        camera_matrices.z_near = z_near;
+        camera_matrices.z_far = z_far;
+        camera_matrices
+    }
+
+    pub fn with_frame_data(mut self, viewport_size: Vec2, time: f32) -> Self {
+        self.viewport_size = viewport_size;
+        self.time = time;
+        self
+    }
+
+    /// Keeps the projection view of the last frame, e.g. `matrices.set_previous(&last_matrices)`
+    pub fn set_previous(&mut self, previous: &CameraMatrices) {
+        self.previous_projection_view_matrix = previous.projection_view_matrix;
+    }
+
+    /// Offsets the projection by a fraction of a pixel, used for temporal anti aliasing.\
+    /// Requires the viewport size.
+    pub fn with_jitter(mut self, jitter: Vec2) -> Self {
+        if self.viewport_size.cmple(Vec2::ZERO).any() {
+            return self;
+        }
+        let offset = (jitter - self.jitter) * 2.0 / self.viewport_size;
+        // Adds the offset scaled by w, so it works for perspective and orthographic projections
+        for column in 0..4 {
+            let column = self.projection_matrix.col_mut(column);
+            column.x += offset.x * column.w;
+            column.y += offset.y * column.w;
+        }
+        self.jitter = jitter;
+        self.projection_view_matrix = self.projection_matrix.mul_mat4(&self.view_matrix);
+        self.inverse_projection_matrix = self.projection_matrix.inverse();
+        self.inverse_projection_view_matrix = self.projection_view_matrix.inverse();
+        self
+    }
 }
 
+/// Element of the Halton (2, 3) sequence centered around zero, in pixels between -0.5 and 0.5
+pub fn halton_jitter(frame_index: u32) -> Vec2 {
+    let halton = |mut index: u32, base: u32| {
+        let mut result = 0.0;
+        let mut fraction = 1.0;
+        while index > 0 {
+            fraction /= base as f32;
+            result += fraction * (index % base) as f32;
+            index /= base;
+        }
+        result
+    };
+    let index = frame_index + 1;
+    vec2(halton(index, 2), halton(index, 3)) - Vec2::splat(0.5)
+}
+
+#[derive(Clone, Copy)]
 pub struct ViewData {
     pub camera_matrices: CameraMatrices,
     pub viewport: Viewport,
 }
 
+/// Size in bytes of the `ViewMatrices` block
+pub const CAMERA_BUFFER_SIZE: usize = 480;
+
 pub fn create_camera_uniform_buffer(graphics: &Graphics) -> GlUniformBuffer {
     GlUniformBuffer::with_capacity(
         graphics,
-        16 * 3 * 4,
+        CAMERA_BUFFER_SIZE as u32,
         BufferUsage::DYNAMIC_DRAW,
         crate::CAMERA_BINDING_NUMBER,
     )
     .unwrap()
 }
 
-pub fn update_camera_buffer(camera_matrices: CameraMatrices, camera_buffer: &mut GlUniformBuffer) {
-    let mut view_buffer = Vec::new();
+/// Contents of the camera buffer with the std140 layout of `get_camera_uniform_block_definition`
+pub fn camera_buffer_data(camera_matrices: &CameraMatrices) -> Vec<f32> {
+    let mut view_buffer = Vec::with_capacity(CAMERA_BUFFER_SIZE / 4);
     view_buffer.extend(camera_matrices.transform_matrix.to_cols_array());
     view_buffer.extend(camera_matrices.view_matrix.to_cols_array());
     view_buffer.extend(camera_matrices.projection_matrix.to_cols_array());
     view_buffer.extend(camera_matrices.projection_view_matrix.to_cols_array());
+    view_buffer.extend(camera_matrices.inverse_projection_matrix.to_cols_array());
+    view_buffer.extend(camera_matrices.inverse_projection_view_matrix.to_cols_array());
+    view_buffer.extend(camera_matrices.previous_projection_view_matrix.to_cols_array());
+    view_buffer.extend(camera_matrices.viewport_size.to_array());
+    view_buffer.push(camera_matrices.z_near);
+    view_buffer.push(camera_matrices.z_far);
+    view_buffer.push(camera_matrices.time);
+    // Padding, vec2 members are aligned to 8 bytes
+    view_buffer.push(0.0);
+    view_buffer.extend(camera_matrices.jitter.to_array());
+    view_buffer
+}
 
+/// Binds the buffer to the `ViewMatrices` block, the binding point keeps the last camera buffer
+/// created or bound
+pub fn bind_camera_buffer(camera_buffer: &GlUniformBuffer) {
+    camera_buffer.bind_buffer_base(crate::CAMERA_BINDING_NUMBER);
+}
+
+pub fn update_camera_buffer(camera_matrices: CameraMatrices, camera_buffer: &mut GlUniformBuffer) {
+    let view_buffer = camera_buffer_data(&camera_matrices);
     camera_buffer.buffer_data(view_buffer.as_slice());
 }
 
+/// Byte offset of every uniform and the size of the block following the std140 rules,
+/// `None` for types that can't be part of a uniform block
+pub fn std140_layout(uniforms: &[ShaderUniform]) -> Option<(Vec<usize>, usize)> {
+    let mut offsets = Vec::with_capacity(uniforms.len());
+    let mut offset: usize = 0;
+    for uniform in uniforms.iter() {
+        let (alignment, size): (usize, usize) = match uniform.kind {
+            WebGLDataType::Float | WebGLDataType::Int => (4, 4),
+            WebGLDataType::Vec2 => (8, 8),
+            WebGLDataType::Vec3 => (16, 12),
+            WebGLDataType::Vec4 => (16, 16),
+            WebGLDataType::Mat4 => (16, 64),
+            _ => return None,
+        };
+        // Array elements are padded to the size of a vec4
+        let (alignment, size) = match uniform.array_length {
+            Some(length) => (16, size.max(16).next_multiple_of(16) * length as usize),
+            None => (alignment, size),
+        };
+        offset = offset.next_multiple_of(alignment);
+        offsets.push(offset);
+        offset += size;
+    }
+    Some((offsets, offset.next_multiple_of(16)))
+}
+
 pub fn get_camera_uniform_block_definition() -> ShaderUniformBlock {
     ShaderUniformBlock {
         name: "ViewMatrices".into(),
@@ -75,6 +253,46 @@ pub fn get_camera_uniform_block_definition() -> ShaderUniformBlock {
                 kind: WebGLDataType::Mat4,
                 name: "proj_x_view".into(),
             },
+            ShaderUniform {
+                array_length: None,
+                kind: WebGLDataType::Mat4,
+                name: "inverse_projection".into(),
+            },
+            ShaderUniform {
+                array_length: None,
+                kind: WebGLDataType::Mat4,
+                name: "inverse_proj_x_view".into(),
+            },
+            ShaderUniform {
+                array_length: None,
+                kind: WebGLDataType::Mat4,
+                name: "previous_proj_x_view".into(),
+            },
+            ShaderUniform {
+                array_length: None,
+                kind: WebGLDataType::Vec2,
+                name: "viewport_size".into(),
+            },
+            ShaderUniform {
+                array_length: None,
+                kind: WebGLDataType::Float,
+                name: "z_near".into(),
+            },
+            ShaderUniform {
+                array_length: None,
+                kind: WebGLDataType::Float,
+                name: "z_far".into(),
+            },
+            ShaderUniform {
+                array_length: None,
+                kind: WebGLDataType::Float,
+                name: "time".into(),
+            },
+            ShaderUniform {
+                array_length: None,
+                kind: WebGLDataType::Vec2,
+                name: "jitter".into(),
+            },
         ],
     }
 }
@@ -0,0 +1,294 @@
+use glam::*;
+
+use crate::time::Time;
+
+/// Offset added on top of the camera pose by the modifiers, the camera position and orientation
+/// are not changed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraOffset {
+    /// In the space of the camera
+    pub translation: Vec3,
+    /// Applied after the camera orientation
+    pub rotation: Quat,
+    pub fov_radians: f32,
+}
+
+impl Default for CameraOffset {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl CameraOffset {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        fov_radians: 0.0,
+    };
+
+    pub fn combine(&self, other: &CameraOffset) -> CameraOffset {
+        CameraOffset {
+            translation: self.translation + other.translation,
+            rotation: (self.rotation * other.rotation).normalize(),
+            fov_radians: self.fov_radians + other.fov_radians,
+        }
+    }
+
+    /// Camera transform with the offset applied
+    pub fn apply(&self, position: Vec3, orientation: Quat) -> Mat4 {
+        Mat4::from_rotation_translation(
+            (orientation * self.rotation).normalize(),
+            position + orientation * self.translation,
+        )
+    }
+}
+
+fn hash(value: i32, seed: u32) -> f32 {
+    let mut x = (value as u32).wrapping_mul(0x9E37_79B1) ^ seed.wrapping_mul(0x85EB_CA77);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x2C1B_3C6D);
+    x ^= x >> 12;
+    x = x.wrapping_mul(0x297A_2D39);
+    x ^= x >> 15;
+    x as f32 / u32::MAX as f32 * 2.0 - 1.0
+}
+
+/// Smooth 1D gradient noise between -1 and 1
+fn gradient_noise(x: f32, seed: u32) -> f32 {
+    let cell = x.floor();
+    let t = x - cell;
+    let cell = cell as i32;
+    let a = hash(cell, seed) * t;
+    let b = hash(cell + 1, seed) * (t - 1.0);
+    let s = t * t * (3.0 - 2.0 * t);
+    (a + (b - a) * s) * 2.0
+}
+
+/// Noise shake scaled by the square of the trauma, so small hits barely move the camera and
+/// big ones stack up
+#[derive(Debug, Clone)]
+pub struct TraumaShake {
+    /// Between 0 and 1
+    pub trauma: f32,
+    /// Trauma removed per second
+    pub decay: f32,
+    pub max_translation: Vec3,
+    /// Maximum pitch, yaw and roll in radians
+    pub max_rotation: Vec3,
+    /// Noise samples per second, higher values shake faster
+    pub frequency: f32,
+    pub seed: u32,
+    time: f32,
+}
+
+impl TraumaShake {
+    pub fn new(max_translation: Vec3, max_rotation: Vec3) -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 1.0,
+            max_translation,
+            max_rotation,
+            frequency: 25.0,
+            seed: 0,
+            time: 0.0,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        self.time += delta;
+        self.trauma = (self.trauma - self.decay * delta).max(0.0);
+    }
+
+    pub fn offset(&self) -> CameraOffset {
+        let shake = self.trauma * self.trauma;
+        if shake <= 0.0 {
+            return CameraOffset::IDENTITY;
+        }
+        let x = self.time * self.frequency;
+        let noise = |channel: u32| gradient_noise(x, self.seed.wrapping_add(channel));
+        let translation = self.max_translation * vec3(noise(0), noise(1), noise(2)) * shake;
+        let angles = self.max_rotation * vec3(noise(3), noise(4), noise(5)) * shake;
+        CameraOffset {
+            translation,
+            rotation: Quat::from_euler(EulerRot::YXZ, angles.y, angles.x, angles.z),
+            fov_radians: 0.0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.trauma <= 0.0
+    }
+}
+
+/// Field of view change that recovers exponentially, e.g. when sprinting or on an impact
+#[derive(Debug, Clone)]
+pub struct FovKick {
+    pub fov_radians: f32,
+    /// Fraction recovered per second follows `1 - exp(-recovery * time)`
+    pub recovery: f32,
+}
+
+impl FovKick {
+    pub fn new(recovery: f32) -> Self {
+        Self {
+            fov_radians: 0.0,
+            recovery,
+        }
+    }
+
+    pub fn kick(&mut self, fov_radians: f32) {
+        self.fov_radians += fov_radians;
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        self.fov_radians *= (-self.recovery * delta).exp();
+    }
+
+    pub fn offset(&self) -> CameraOffset {
+        CameraOffset {
+            fov_radians: self.fov_radians,
+            ..CameraOffset::IDENTITY
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.fov_radians.abs() < 1e-5
+    }
+}
+
+/// Kick of the camera that returns to rest, e.g. weapon recoil pitching the view up
+#[derive(Debug, Clone)]
+pub struct Recoil {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub recovery: f32,
+}
+
+impl Recoil {
+    pub fn new(recovery: f32) -> Self {
+        Self {
+            translation: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            recovery,
+        }
+    }
+
+    pub fn kick(&mut self, translation: Vec3, rotation: Quat) {
+        self.translation += translation;
+        self.rotation = (self.rotation * rotation).normalize();
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        let remaining = (-self.recovery * delta).exp();
+        self.translation *= remaining;
+        self.rotation = Quat::IDENTITY.slerp(self.rotation, remaining);
+    }
+
+    pub fn offset(&self) -> CameraOffset {
+        CameraOffset {
+            translation: self.translation,
+            rotation: self.rotation,
+            fov_radians: 0.0,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.translation.length_squared() < 1e-10 && self.rotation.abs_diff_eq(Quat::IDENTITY, 1e-5)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CameraModifier {
+    Shake(TraumaShake),
+    FovKick(FovKick),
+    Recoil(Recoil),
+}
+
+impl CameraModifier {
+    pub fn advance(&mut self, delta: f32) {
+        match self {
+            CameraModifier::Shake(shake) => shake.advance(delta),
+            CameraModifier::FovKick(kick) => kick.advance(delta),
+            CameraModifier::Recoil(recoil) => recoil.advance(delta),
+        }
+    }
+
+    pub fn offset(&self) -> CameraOffset {
+        match self {
+            CameraModifier::Shake(shake) => shake.offset(),
+            CameraModifier::FovKick(kick) => kick.offset(),
+            CameraModifier::Recoil(recoil) => recoil.offset(),
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match self {
+            CameraModifier::Shake(shake) => shake.is_finished(),
+            CameraModifier::FovKick(kick) => kick.is_finished(),
+            CameraModifier::Recoil(recoil) => recoil.is_finished(),
+        }
+    }
+}
+
+/// Modifiers of a camera, their offsets are added together when generating the camera matrices.\
+/// Modifiers are kept when they come to rest so their index stays valid, transient ones can be
+/// dropped with `remove_finished`.
+#[derive(Debug, Clone, Default)]
+pub struct CameraModifierStack {
+    pub modifiers: Vec<CameraModifier>,
+}
+
+impl CameraModifierStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, modifier: CameraModifier) -> usize {
+        self.modifiers.push(modifier);
+        self.modifiers.len() - 1
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut CameraModifier> {
+        self.modifiers.get_mut(index)
+    }
+
+    /// Adds trauma to every shake of the stack
+    pub fn add_trauma(&mut self, amount: f32) {
+        for modifier in self.modifiers.iter_mut() {
+            if let CameraModifier::Shake(shake) = modifier {
+                shake.add_trauma(amount);
+            }
+        }
+    }
+
+    pub fn remove_finished(&mut self) {
+        self.modifiers.retain(|modifier| !modifier.is_finished());
+    }
+
+    pub fn advance(&mut self, delta: f32) {
+        for modifier in self.modifiers.iter_mut() {
+            modifier.advance(delta);
+        }
+    }
+
+    pub fn update(&mut self, time: &Time) {
+        self.advance(time.delta_time_seconds().0 as f32);
+    }
+
+    pub fn offset(&self) -> CameraOffset {
+        self.modifiers
+            .iter()
+            .fold(CameraOffset::IDENTITY, |offset, modifier| {
+                offset.combine(&modifier.offset())
+            })
+    }
+}
@@ -0,0 +1,207 @@
+use glam::*;
+
+pub struct OrthographicProperties {
+    /// Visible height in world units, the width follows the aspect ratio
+    pub size: f32,
+    pub aspect_ratio: f32,
+    pub z_near: f32,
+    pub z_far: f32,
+    /// Has to match the convention of the renderer
+    pub depth_convention: DepthConvention,
+}
+
+impl OrthographicProperties {
+    pub fn new(width: u32, height: u32, size: f32, z_near: f32, z_far: f32) -> Self {
+        Self {
+            size,
+            aspect_ratio: width as f32 / height as f32,
+            z_near,
+            z_far,
+            depth_convention: DepthConvention::Standard,
+        }
+    }
+
+    pub fn with_depth_convention(mut self, depth_convention: DepthConvention) -> Self {
+        self.depth_convention = depth_convention;
+        self
+    }
+}
+
+/// Keeps one world unit at a whole number of screen pixels
+#[derive(Clone, Copy)]
+struct PixelPerfect {
+    pixels_per_unit: f32,
+    viewport_size: UVec2,
+}
+
+pub struct OrthographicCamera {
+    orthographic_props: OrthographicProperties,
+    pixel_perfect: Option<PixelPerfect>,
+    pub camera_position: Vec3,
+    pub orientation: Quat,
+    /// Values above 1 show a smaller area of the world
+    pub zoom: f32,
+    /// Shakes and kicks applied on top of the camera pose, the field of view offsets are ignored
+    pub modifiers: CameraModifierStack,
+}
+
+impl OrthographicCamera {
+    pub fn new(ortho: OrthographicProperties) -> Self {
+        Self {
+            orthographic_props: ortho,
+            pixel_perfect: None,
+            camera_position: Vec3::ZERO,
+            orientation: Quat::IDENTITY,
+            zoom: 1.0,
+            modifiers: CameraModifierStack::new(),
+        }
+    }
+
+    /// Camera where a world unit covers `pixels_per_unit` pixels of the viewport.\
+    /// The view size follows the viewport, the zoom is rounded to whole numbers and the
+    /// position is snapped to the pixel grid.
+    pub fn pixel_perfect(
+        viewport_size: UVec2,
+        pixels_per_unit: f32,
+        z_near: f32,
+        z_far: f32,
+    ) -> Self {
+        let mut camera = Self::new(OrthographicProperties::new(
+            viewport_size.x,
+            viewport_size.y,
+            viewport_size.y as f32 / pixels_per_unit,
+            z_near,
+            z_far,
+        ));
+        camera.pixel_perfect = Some(PixelPerfect {
+            pixels_per_unit,
+            viewport_size,
+        });
+        camera
+    }
+
+    pub fn is_pixel_perfect(&self) -> bool {
+        self.pixel_perfect.is_some()
+    }
+
+    pub fn update_orthographic_props(
+        &mut self,
+        size: Option<UVec2>,
+        z_value: Option<Vec2>,
+        view_size: Option<f32>,
+    ) {
+        if let Some(size) = size {
+            self.orthographic_props.aspect_ratio = (size.x as f32) / (size.y as f32);
+            if let Some(pixel_perfect) = self.pixel_perfect.as_mut() {
+                pixel_perfect.viewport_size = size;
+                self.orthographic_props.size = size.y as f32 / pixel_perfect.pixels_per_unit;
+            }
+        }
+        if let Some(z_value) = z_value {
+            self.orthographic_props.z_near = z_value.x;
+            self.orthographic_props.z_far = z_value.y;
+        }
+        if let Some(view_size) = view_size {
+            if self.pixel_perfect.is_none() {
+                self.orthographic_props.size = view_size;
+            }
+        }
+    }
+
+    /// Zoom applied to the projection, whole numbers of at least 1 in pixel perfect mode
+    pub fn effective_zoom(&self) -> f32 {
+        match self.pixel_perfect {
+            Some(_) => self.zoom.round().max(1.0),
+            None => self.zoom,
+        }
+    }
+
+    /// Width and height of the visible area in world units
+    pub fn view_size(&self) -> Vec2 {
+        let height = self.orthographic_props.size / self.effective_zoom();
+        vec2(height * self.orthographic_props.aspect_ratio, height)
+    }
+
+    fn view_matrix(&self) -> Mat4 {
+        let offset = self.modifiers.offset();
+        let mut view_matrix = offset
+            .apply(self.camera_position, self.orientation)
+            .inverse();
+        if let Some(pixel_perfect) = self.pixel_perfect {
+            // World pixels line up with screen pixels, the center of an odd sized viewport is
+            // in the middle of a pixel
+            let pixel_size = 1.0 / (pixel_perfect.pixels_per_unit * self.effective_zoom());
+            let odd_offset = (pixel_perfect.viewport_size % 2).as_vec2() * 0.5 * pixel_size;
+            let snap = |value: f32, offset: f32| {
+                ((value - offset) / pixel_size).round() * pixel_size + offset
+            };
+            view_matrix.w_axis.x = snap(view_matrix.w_axis.x, odd_offset.x);
+            view_matrix.w_axis.y = snap(view_matrix.w_axis.y, odd_offset.y);
+        }
+        view_matrix
+    }
+
+    pub fn generate_camera_matrices(&mut self) -> CameraMatrices {
+        let view_matrix = self.view_matrix();
+        let transform_matrix = view_matrix.inverse();
+        let half_size = self.view_size() * 0.5;
+        let (near, far) = (self.orthographic_props.z_near, self.orthographic_props.z_far);
+        let projection_matrix = match self.orthographic_props.depth_convention {
+            DepthConvention::Standard => glam::Mat4::orthographic_rh_gl(
+                -half_size.x,
+                half_size.x,
+                -half_size.y,
+                half_size.y,
+                near,
+                far,
+            ),
+            // Zero to one depth with the planes swapped
+            DepthConvention::Reversed => glam::Mat4::orthographic_rh(
+                -half_size.x,
+                half_size.x,
+                -half_size.y,
+                half_size.y,
+                far,
+                near,
+            ),
+        };
+        CameraMatrices::with_depth_convention(
+            transform_matrix,
+            projection_matrix,
+            self.orthographic_props.depth_convention,
+        )
+    }
+}
+
+use crate::renderer::DepthConvention;
+use crate::{Orientable, Translatable};
+
+use super::{CameraMatrices, CameraModifierStack};
+
+impl Orientable for OrthographicCamera {
+    fn rotate(&mut self, rotation: glam::Quat) {
+        self.orientation = (rotation * self.orientation).normalize();
+    }
+
+    fn set_orientation(&mut self, orientation: glam::Quat) {
+        self.orientation = orientation.normalize();
+    }
+
+    fn get_orientation(&self) -> glam::Quat {
+        self.orientation
+    }
+}
+
+impl Translatable for OrthographicCamera {
+    fn translate(&mut self, translate: glam::Vec3) {
+        self.camera_position += translate;
+    }
+
+    fn set_position(&mut self, position: glam::Vec3) {
+        self.camera_position = position;
+    }
+
+    fn get_position(&self) -> glam::Vec3 {
+        self.camera_position
+    }
+}
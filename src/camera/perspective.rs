@@ -5,6 +5,13 @@ pub struct PerspectiveProperties {
     pub aspect_ratio: f32,
     pub z_near: f32,
     pub z_far: f32,
+    /// Has to match the convention of the renderer, `DepthConvention::Reversed` maps the near
+    /// plane to depth 1 and the far plane to 0
+    pub depth_convention: DepthConvention,
+    /// Ignores `z_far`, nothing is clipped by distance.\
+    /// Costs almost no precision compared to a distant far plane, with the standard convention
+    /// raising `z_near` is what reduces z-fighting.
+    pub infinite_far: bool,
 }
 
 impl PerspectiveProperties {
@@ -14,15 +21,54 @@ impl PerspectiveProperties {
             aspect_ratio: width as f32 / height as f32,
             z_near,
             z_far,
+            depth_convention: DepthConvention::Standard,
+            infinite_far: false,
         }
     }
+
+    pub fn with_depth_convention(mut self, depth_convention: DepthConvention) -> Self {
+        self.depth_convention = depth_convention;
+        self
+    }
+
+    pub fn with_infinite_far(mut self) -> Self {
+        self.infinite_far = true;
+        self
+    }
+
+    /// Projection with OpenGL clip space, depth goes from -1 to 1. The reversed convention uses
+    /// the zero to one clip space and goes from 1 to 0.
+    pub fn projection_matrix(&self) -> Mat4 {
+        self.projection_matrix_with_fov(self.fov_radians)
+    }
+
+    fn projection_matrix_with_fov(&self, fov_radians: f32) -> Mat4 {
+        let focal_length = 1.0 / (0.5 * fov_radians).tan();
+        let (near, far) = (self.z_near, self.z_far);
+        let (depth_scale, depth_offset) = match (self.depth_convention, self.infinite_far) {
+            (DepthConvention::Standard, false) => {
+                return Mat4::perspective_rh_gl(fov_radians, self.aspect_ratio, near, far);
+            }
+            (DepthConvention::Standard, true) => (-1.0, -2.0 * near),
+            (DepthConvention::Reversed, false) => (near / (far - near), far * near / (far - near)),
+            (DepthConvention::Reversed, true) => (0.0, near),
+        };
+        Mat4::from_cols(
+            vec4(focal_length / self.aspect_ratio, 0.0, 0.0, 0.0),
+            vec4(0.0, focal_length, 0.0, 0.0),
+            vec4(0.0, 0.0, depth_scale, -1.0),
+            vec4(0.0, 0.0, depth_offset, 0.0),
+        )
+    }
 }
 
 pub struct PerspectiveCamera {
     perspective_props: PerspectiveProperties,
     pub camera_position: glam::Vec3,
-    pub view_forward: glam::Vec3,
-    pub view_up: glam::Vec3,
+    /// Rotation from the initial orientation, looking down -Z with +Y up
+    pub orientation: glam::Quat,
+    /// Shakes and kicks applied on top of the camera pose in `generate_camera_matrices`
+    pub modifiers: CameraModifierStack,
 }
 
 impl PerspectiveCamera {
@@ -32,17 +78,31 @@ impl PerspectiveCamera {
     pub fn new(
         persp: PerspectiveProperties,
     ) -> Self {
-        let view_forward = Self::INIT_FORWARD;
-        let view_up = Self::INIT_UP;
-
         Self {
             camera_position: Vec3::ZERO,
             perspective_props: persp,
-            view_forward,
-            view_up,
+            orientation: Quat::IDENTITY,
+            modifiers: CameraModifierStack::new(),
         }
     }
 
+    pub fn view_forward(&self) -> Vec3 {
+        self.orientation.mul_vec3(Self::INIT_FORWARD)
+    }
+
+    pub fn view_up(&self) -> Vec3 {
+        self.orientation.mul_vec3(Self::INIT_UP)
+    }
+
+    pub fn view_right(&self) -> Vec3 {
+        self.orientation.mul_vec3(Vec3::X)
+    }
+
+    pub fn set_depth_options(&mut self, depth_convention: DepthConvention, infinite_far: bool) {
+        self.perspective_props.depth_convention = depth_convention;
+        self.perspective_props.infinite_far = infinite_far;
+    }
+
     pub fn update_perspective_props(
         &mut self,
         size: Option<UVec2>,
@@ -62,40 +122,47 @@ impl PerspectiveCamera {
     }
 
     pub fn generate_camera_matrices(&mut self) -> CameraMatrices {
-        let view_matrix = glam::Mat4::look_at_rh(self.camera_position, self.camera_position + self.view_forward, self.view_up);
-        let transform_matrix = view_matrix.inverse();
-        let projection_matrix =glam::Mat4::perspective_rh_gl(
-            self.perspective_props.fov_radians,
-            self.perspective_props.aspect_ratio,
-            self.perspective_props.z_near,
-            self.perspective_props.z_far,
-        );
-        let projection_view_matrix = projection_matrix.mul_mat4(&view_matrix);
-        CameraMatrices {
+        let offset = self.modifiers.offset();
+        let transform_matrix = offset.apply(self.camera_position, self.orientation);
+        let fov_radians = (self.perspective_props.fov_radians + offset.fov_radians).clamp(0.01, 3.1);
+        let projection_matrix = self.perspective_props.projection_matrix_with_fov(fov_radians);
+        CameraMatrices::with_depth_convention(
             transform_matrix,
-            view_matrix,
             projection_matrix,
-            projection_view_matrix,
-        }
+            self.perspective_props.depth_convention,
+        )
     }
 }
 
-use crate::Orientable;
+use crate::renderer::DepthConvention;
+use crate::{Orientable, Translatable};
 
-use super::CameraMatrices;
+use super::{CameraMatrices, CameraModifierStack};
 
 impl Orientable for PerspectiveCamera {
     fn rotate(&mut self, rotation: glam::Quat) {
-        self.view_forward = rotation.mul_vec3(self.view_forward);
-        self.view_up = rotation.mul_vec3(self.view_up);
+        self.orientation = rotation.mul_quat(self.orientation).normalize();
     }
 
     fn set_orientation(&mut self, orientation: glam::Quat) {
-        self.view_forward = orientation.mul_vec3(Self::INIT_FORWARD);
-        self.view_up = orientation.mul_vec3(Self::INIT_UP);
+        self.orientation = orientation.normalize();
     }
 
     fn get_orientation(&self) -> glam::Quat {
-        glam::Quat::from_rotation_arc(Self::INIT_FORWARD, self.view_forward)
+        self.orientation
+    }
+}
+
+impl Translatable for PerspectiveCamera {
+    fn translate(&mut self, translate: glam::Vec3) {
+        self.camera_position += translate;
+    }
+
+    fn set_position(&mut self, position: glam::Vec3) {
+        self.camera_position = position;
+    }
+
+    fn get_position(&self) -> glam::Vec3 {
+        self.camera_position
     }
 }
@@ -0,0 +1,79 @@
+use glam::*;
+use rust_webgl2::Viewport;
+
+use super::CameraMatrices;
+use crate::collision::Ray;
+
+/// Converts a pixel position to normalized device coordinates.\
+/// Pixels follow the `Viewport` convention with the origin at the bottom left, cursor positions
+/// have to be flipped with `canvas_height - y` first.
+pub fn pixel_to_ndc(viewport: &Viewport, pixel: Vec2) -> Vec2 {
+    (pixel - viewport.position.as_vec2()) / viewport.size.as_vec2() * 2.0 - Vec2::ONE
+}
+
+pub fn ndc_to_pixel(viewport: &Viewport, ndc: Vec2) -> Vec2 {
+    (ndc + Vec2::ONE) * 0.5 * viewport.size.as_vec2() + viewport.position.as_vec2()
+}
+
+impl CameraMatrices {
+    /// Point in view space at the normalized device coordinates
+    fn unproject_view(&self, ndc: Vec3) -> Vec3 {
+        self.inverse_projection_matrix.project_point3(ndc)
+    }
+
+    /// World space ray starting on the camera plane and going through the point of the screen.
+    /// Works for perspective and orthographic projections, including reverse depth and infinite
+    /// far planes.
+    pub fn ndc_to_ray(&self, ndc: Vec2) -> Ray {
+        // Both depths are inside the frustum for every supported projection
+        let a = self.unproject_view(ndc.extend(self.depth_convention.ndc_depth(0.5)));
+        let b = self.unproject_view(ndc.extend(self.depth_convention.ndc_depth(0.75)));
+        let mut direction = (b - a).try_normalize().unwrap_or(Vec3::NEG_Z);
+        if direction.z > 0.0 {
+            direction = -direction;
+        }
+        let origin = a - direction * (a.z / direction.z);
+        Ray {
+            position: self.transform_matrix.transform_point3(origin),
+            direction: self
+                .transform_matrix
+                .transform_vector3(direction)
+                .normalize(),
+            distance: None,
+        }
+    }
+
+    pub fn screen_to_ray(&self, viewport: &Viewport, pixel: Vec2) -> Ray {
+        self.ndc_to_ray(pixel_to_ndc(viewport, pixel))
+    }
+
+    /// Normalized device coordinates of a world point, `None` when it is behind the camera
+    pub fn world_to_ndc(&self, point: Vec3) -> Option<Vec3> {
+        let clip = self.projection_view_matrix * point.extend(1.0);
+        if clip.w <= 0.0 {
+            return None;
+        }
+        Some(clip.truncate() / clip.w)
+    }
+
+    /// Pixel position of a world point, the z component is the value written to the depth
+    /// buffer
+    pub fn world_to_screen(&self, viewport: &Viewport, point: Vec3) -> Option<Vec3> {
+        let ndc = self.world_to_ndc(point)?;
+        Some(ndc_to_pixel(viewport, ndc.truncate()).extend(self.depth_convention.buffer_depth(ndc.z)))
+    }
+
+    /// Distance along the view direction of a depth buffer value between 0 and 1
+    pub fn linear_depth(&self, depth: f32) -> f32 {
+        -self
+            .unproject_view(vec3(0.0, 0.0, self.depth_convention.ndc_depth(depth)))
+            .z
+    }
+
+    /// World position of a pixel read from the depth buffer
+    pub fn depth_to_world(&self, viewport: &Viewport, pixel: Vec2, depth: f32) -> Vec3 {
+        let ndc = pixel_to_ndc(viewport, pixel).extend(self.depth_convention.ndc_depth(depth));
+        self.transform_matrix
+            .transform_point3(self.unproject_view(ndc))
+    }
+}
@@ -0,0 +1,550 @@
+#[cfg(test)]
+mod tests {
+    use glam::*;
+    use rust_webgl2::Viewport;
+
+    use super::super::*;
+    use crate::renderer::DepthConvention;
+    use crate::time::{Milisecond, Time};
+    use crate::transform::Transform;
+    use crate::{Orientable, Translatable};
+
+    fn to_ndc(matrices: &CameraMatrices, point: Vec3) -> Vec3 {
+        matrices.projection_view_matrix.project_point3(point)
+    }
+
+    #[test]
+    fn orthographic_maps_view_edges_to_ndc() {
+        let mut camera =
+            OrthographicCamera::new(OrthographicProperties::new(800, 400, 10.0, 0.1, 100.0));
+        assert_eq!(camera.view_size(), vec2(20.0, 10.0));
+        camera.set_position(vec3(2.0, 1.0, 5.0));
+        let matrices = camera.generate_camera_matrices();
+        let corner = to_ndc(&matrices, vec3(12.0, 6.0, 0.0));
+        assert!(corner.truncate().abs_diff_eq(Vec2::ONE, 1e-5));
+
+        camera.zoom = 2.0;
+        assert_eq!(camera.view_size(), vec2(10.0, 5.0));
+        let matrices = camera.generate_camera_matrices();
+        let corner = to_ndc(&matrices, vec3(7.0, 3.5, 0.0));
+        assert!(corner.truncate().abs_diff_eq(Vec2::ONE, 1e-5));
+    }
+
+    #[test]
+    fn orthographic_orientation_and_position() {
+        let mut camera = OrthographicCamera::new(OrthographicProperties::new(1, 1, 2.0, 0.1, 10.0));
+        let rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        camera.set_orientation(rotation);
+        camera.translate(vec3(1.0, 2.0, 3.0));
+        assert!(camera.get_orientation().abs_diff_eq(rotation, 1e-6));
+        assert_eq!(camera.get_position(), vec3(1.0, 2.0, 3.0));
+
+        // Looking down -Z rotated to -X
+        let matrices = camera.generate_camera_matrices();
+        let forward = matrices.transform_matrix.transform_vector3(-Vec3::Z);
+        assert!(forward.abs_diff_eq(-Vec3::X, 1e-5));
+        let ahead = to_ndc(&matrices, vec3(-4.0, 2.0, 3.0));
+        assert!(ahead.truncate().abs_diff_eq(Vec2::ZERO, 1e-5));
+    }
+
+    #[test]
+    fn pixel_perfect_follows_viewport() {
+        let mut camera = OrthographicCamera::pixel_perfect(uvec2(320, 180), 16.0, 0.1, 100.0);
+        assert_eq!(camera.view_size(), vec2(20.0, 11.25));
+        camera.zoom = 2.4;
+        assert_eq!(camera.effective_zoom(), 2.0);
+        camera.update_orthographic_props(Some(uvec2(640, 360)), None, Some(1.0));
+        assert_eq!(camera.view_size(), vec2(20.0, 11.25));
+
+        // One world unit covers exactly 32 pixels and the position is snapped to them
+        camera.set_position(vec3(0.01, 0.02, 0.0));
+        let matrices = camera.generate_camera_matrices();
+        let pixel = |point: Vec3| {
+            let ndc = to_ndc(&matrices, point).truncate();
+            (ndc * 0.5 + 0.5) * vec2(640.0, 360.0)
+        };
+        let origin = pixel(Vec3::ZERO);
+        assert!(origin.abs_diff_eq(origin.round(), 1e-3));
+        assert!((pixel(Vec3::X) - origin).abs_diff_eq(vec2(32.0, 0.0), 1e-3));
+    }
+
+    fn perspective_camera() -> PerspectiveCamera {
+        PerspectiveCamera::new(PerspectiveProperties::new(
+            16,
+            9,
+            std::f32::consts::FRAC_PI_3,
+            0.1,
+            100.0,
+        ))
+    }
+
+    #[test]
+    fn perspective_orientation_round_trips_roll() {
+        let mut camera = perspective_camera();
+        let banked = Quat::from_euler(EulerRot::YXZ, 0.7, -0.3, 0.45);
+        camera.set_orientation(banked);
+        let orientation = camera.get_orientation();
+        camera.set_orientation(orientation);
+        assert_eq!(camera.get_orientation(), orientation);
+        assert!(orientation.abs_diff_eq(banked, 1e-6));
+        assert!(camera.view_up().abs_diff_eq(banked * Vec3::Y, 1e-6));
+
+        camera.rotate(Quat::from_rotation_z(0.2));
+        assert!(camera
+            .get_orientation()
+            .abs_diff_eq(Quat::from_rotation_z(0.2) * banked, 1e-6));
+    }
+
+    #[test]
+    fn perspective_position_round_trips() {
+        let mut camera = perspective_camera();
+        camera.set_position(vec3(1.5, -2.0, 3.25));
+        assert_eq!(camera.get_position(), vec3(1.5, -2.0, 3.25));
+        camera.translate(vec3(0.5, 1.0, -0.25));
+        assert_eq!(camera.get_position(), vec3(2.0, -1.0, 3.0));
+
+        camera.set_orientation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+        let matrices = camera.generate_camera_matrices();
+        assert!(matrices.transform_matrix.abs_diff_eq(
+            Mat4::from_rotation_translation(camera.get_orientation(), camera.get_position()),
+            1e-6
+        ));
+        // Rolled by 90 degrees the camera up is -X
+        let above = to_ndc(&matrices, vec3(1.0, -1.0, -2.0));
+        assert!(above.x.abs() < 1e-5 && above.y > 0.0);
+    }
+
+    #[test]
+    fn orbit_respects_limits() {
+        let mut camera = perspective_camera();
+        let mut orbit =
+            OrbitController::new(vec3(1.0, 0.0, 0.0), 5.0).with_distance_limits(2.0, 8.0);
+        orbit.advance(&CameraInput::default(), 0.0, &mut camera);
+        assert!(camera.get_position().abs_diff_eq(vec3(1.0, 0.0, 5.0), 1e-5));
+        assert!(camera.view_forward().abs_diff_eq(-Vec3::Z, 1e-5));
+
+        let input = CameraInput {
+            look: vec2(0.0, -10000.0),
+            zoom: 100.0,
+            ..Default::default()
+        };
+        orbit.advance(&input, 0.0, &mut camera);
+        assert_eq!(orbit.distance, 2.0);
+        assert_eq!(orbit.pitch, orbit.max_pitch);
+        let to_target = (orbit.target - camera.get_position()).normalize();
+        assert!(camera.view_forward().abs_diff_eq(to_target, 1e-5));
+        assert!((camera.get_position().distance(orbit.target) - 2.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn fly_moves_along_view() {
+        let mut camera = perspective_camera();
+        let mut fly = FlyController::new(2.0);
+        fly.pitch = std::f32::consts::FRAC_PI_4;
+        let input = CameraInput {
+            movement: Vec3::Z,
+            boost: true,
+            ..Default::default()
+        };
+        fly.advance(&input, 0.5, &mut camera);
+        let expected = camera.view_forward() * 2.0 * fly.boost_multiplier * 0.5;
+        assert!(camera.get_position().abs_diff_eq(expected, 1e-5));
+        assert!(camera.get_position().y > 0.0);
+    }
+
+    #[test]
+    fn first_person_stays_on_ground() {
+        let mut camera = perspective_camera();
+        let mut walk = FirstPersonController::new(3.0);
+        let look_up = CameraInput {
+            look: vec2(0.0, -1.0e6),
+            ..Default::default()
+        };
+        walk.advance(&look_up, 0.0, &mut camera);
+        assert_eq!(walk.pitch, walk.max_pitch);
+
+        let input = CameraInput {
+            movement: vec3(1.0, 0.0, 1.0),
+            ..Default::default()
+        };
+        walk.advance(&input, 1.0, &mut camera);
+        let position = camera.get_position();
+        assert_eq!(position.y, 0.0);
+        assert!((position.length() - 3.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn follow_settles_behind_target() {
+        let mut camera = perspective_camera();
+        let mut follow = FollowController::new(vec3(0.0, 2.0, 5.0), Vec3::ZERO);
+        let target = Transform::from_translation(vec3(10.0, 0.0, 0.0));
+        let goal = follow.desired_position(&target);
+
+        let mut time = Time::new();
+        time.update(Milisecond(0.0));
+        let mut previous_distance = f32::INFINITY;
+        for frame in 1..=120 {
+            time.update(Milisecond(frame as f64 * 1000.0 / 60.0));
+            follow.update(&target, &time, &mut camera);
+            let distance = camera.get_position().distance(goal);
+            assert!(
+                distance <= previous_distance + 1e-5,
+                "A critically damped spring does not overshoot"
+            );
+            previous_distance = distance;
+        }
+        assert!(previous_distance < 1e-2);
+        let to_target = (target.translation - camera.get_position()).normalize();
+        assert!(camera.view_forward().abs_diff_eq(to_target, 1e-2));
+
+        // Long frames are integrated in small steps and stay stable
+        let moved = Transform::from_translation(vec3(-10.0, 0.0, 0.0));
+        follow.advance(&moved, 2.0, &mut camera);
+        assert!(
+            camera
+                .get_position()
+                .distance(follow.desired_position(&moved))
+                < 1.0
+        );
+    }
+
+    fn viewport() -> Viewport {
+        Viewport {
+            position: uvec2(10, 20),
+            size: uvec2(800, 600),
+        }
+    }
+
+    /// A ray through the screen position of a point passes through the point
+    fn assert_ray_round_trip(matrices: &CameraMatrices, point: Vec3) {
+        let screen = matrices.world_to_screen(&viewport(), point).unwrap();
+        let ray = matrices.screen_to_ray(&viewport(), screen.truncate());
+        let to_point = point - ray.position;
+        let along = to_point.dot(ray.direction);
+        assert!(along > 0.0);
+        assert!(ray.get_position(along).abs_diff_eq(point, 1e-3));
+
+        let world = matrices.depth_to_world(&viewport(), screen.truncate(), screen.z);
+        assert!(world.abs_diff_eq(point, 1e-2));
+    }
+
+    #[test]
+    fn perspective_screen_rays() {
+        let mut camera = perspective_camera();
+        camera.set_position(vec3(1.0, 2.0, 3.0));
+        camera.set_orientation(Quat::from_rotation_y(0.5));
+        let matrices = camera.generate_camera_matrices();
+
+        let center = ndc_to_pixel(&viewport(), Vec2::ZERO);
+        assert_eq!(center, vec2(410.0, 320.0));
+        let ray = matrices.screen_to_ray(&viewport(), center);
+        assert!(ray.position.abs_diff_eq(camera.get_position(), 1e-4));
+        assert!(ray.direction.abs_diff_eq(camera.view_forward(), 1e-5));
+
+        assert_ray_round_trip(&matrices, vec3(-2.0, 3.0, -6.0));
+        assert!(matrices.world_to_ndc(vec3(1.0, 2.0, 10.0)).is_none());
+
+        let ahead = camera.get_position() + camera.view_forward() * 25.0;
+        let depth = matrices.world_to_screen(&viewport(), ahead).unwrap().z;
+        assert!((matrices.linear_depth(depth) - 25.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn orthographic_screen_rays() {
+        let mut camera =
+            OrthographicCamera::new(OrthographicProperties::new(800, 600, 10.0, 0.1, 100.0));
+        camera.set_position(vec3(0.0, 0.0, 10.0));
+        let matrices = camera.generate_camera_matrices();
+
+        // Rays are parallel and start on the camera plane
+        let corner = matrices.screen_to_ray(&viewport(), vec2(810.0, 620.0));
+        assert!(corner.direction.abs_diff_eq(-Vec3::Z, 1e-5));
+        assert!(corner
+            .position
+            .abs_diff_eq(vec3(20.0 / 3.0, 5.0, 10.0), 1e-4));
+
+        assert_ray_round_trip(&matrices, vec3(1.0, -2.0, -5.0));
+        let depth = matrices
+            .world_to_screen(&viewport(), vec3(0.0, 0.0, -40.0))
+            .unwrap()
+            .z;
+        assert!((matrices.linear_depth(depth) - 50.0).abs() < 1e-3);
+
+        let mut reversed = OrthographicCamera::new(
+            OrthographicProperties::new(800, 600, 10.0, 0.1, 100.0)
+                .with_depth_convention(DepthConvention::Reversed),
+        );
+        reversed.set_position(vec3(0.0, 0.0, 10.0));
+        let matrices = reversed.generate_camera_matrices();
+        assert_ray_round_trip(&matrices, vec3(1.0, -2.0, -5.0));
+        let near = matrices.world_to_screen(&viewport(), vec3(0.0, 0.0, 9.9)).unwrap();
+        assert!((near.z - 1.0).abs() < 1e-5);
+        let depth = matrices
+            .world_to_screen(&viewport(), vec3(0.0, 0.0, -40.0))
+            .unwrap()
+            .z;
+        assert!((matrices.linear_depth(depth) - 50.0).abs() < 1e-3);
+    }
+
+    fn ndc_depth(projection: Mat4, distance: f32) -> f32 {
+        projection.project_point3(vec3(0.0, 0.0, -distance)).z
+    }
+
+    #[test]
+    fn depth_projection_options() {
+        let props = PerspectiveProperties::new(16, 9, 1.0, 0.5, 200.0);
+        let standard = props.projection_matrix();
+        assert_eq!(
+            standard,
+            Mat4::perspective_rh_gl(1.0, 16.0 / 9.0, 0.5, 200.0)
+        );
+
+        let reversed = PerspectiveProperties::new(16, 9, 1.0, 0.5, 200.0)
+            .with_depth_convention(DepthConvention::Reversed)
+            .projection_matrix();
+        assert!((ndc_depth(reversed, 0.5) - 1.0).abs() < 1e-5);
+        assert!(ndc_depth(reversed, 200.0).abs() < 1e-5);
+        assert!(ndc_depth(reversed, 10.0) > ndc_depth(reversed, 20.0));
+
+        let infinite = PerspectiveProperties::new(16, 9, 1.0, 0.5, 200.0)
+            .with_infinite_far()
+            .projection_matrix();
+        assert!((ndc_depth(infinite, 0.5) + 1.0).abs() < 1e-5);
+        assert!(ndc_depth(infinite, 1.0e6) < 1.0);
+        assert!(ndc_depth(infinite, 1.0e6) > ndc_depth(infinite, 1.0e3));
+
+        let reversed_infinite = PerspectiveProperties::new(16, 9, 1.0, 0.5, 200.0)
+            .with_depth_convention(DepthConvention::Reversed)
+            .with_infinite_far()
+            .projection_matrix();
+        assert!((ndc_depth(reversed_infinite, 0.5) - 1.0).abs() < 1e-5);
+        assert!(ndc_depth(reversed_infinite, 1.0e6) > 0.0);
+        assert!(ndc_depth(reversed_infinite, 1.0e6) < 1.0e-6);
+
+        // The x and y projection does not change
+        for projection in [reversed, infinite, reversed_infinite] {
+            assert!(projection
+                .project_point3(vec3(1.0, 2.0, -5.0))
+                .truncate()
+                .abs_diff_eq(
+                    standard.project_point3(vec3(1.0, 2.0, -5.0)).truncate(),
+                    1e-5
+                ));
+        }
+    }
+
+    #[test]
+    fn reversed_infinite_screen_rays() {
+        let mut camera = PerspectiveCamera::new(
+            PerspectiveProperties::new(4, 3, 1.2, 0.1, 100.0)
+                .with_depth_convention(DepthConvention::Reversed)
+                .with_infinite_far(),
+        );
+        camera.set_position(vec3(0.0, 1.0, 0.0));
+        let matrices = camera.generate_camera_matrices();
+        assert_ray_round_trip(&matrices, vec3(3.0, 0.0, -50.0));
+        let depth = matrices
+            .world_to_screen(&viewport(), vec3(0.0, 1.0, -1000.0))
+            .unwrap()
+            .z;
+        assert!((matrices.linear_depth(depth) - 1000.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn trauma_shake_decays() {
+        let mut shake = TraumaShake::new(Vec3::splat(0.5), Vec3::splat(0.1)).with_seed(7);
+        assert_eq!(shake.offset(), CameraOffset::IDENTITY);
+        shake.add_trauma(0.6);
+        shake.add_trauma(0.6);
+        assert_eq!(shake.trauma, 1.0);
+
+        let mut moved = false;
+        for _ in 0..10 {
+            shake.advance(0.01);
+            let offset = shake.offset();
+            assert!(offset.translation.abs().cmple(Vec3::splat(0.5)).all());
+            moved |= offset.translation.length() > 1e-3;
+        }
+        assert!(moved);
+
+        // The same seed and time produce the same shake
+        let mut other = TraumaShake::new(Vec3::splat(0.5), Vec3::splat(0.1)).with_seed(7);
+        other.add_trauma(1.0);
+        other.advance(0.1);
+        assert!(other
+            .offset()
+            .translation
+            .abs_diff_eq(shake.offset().translation, 1e-5));
+
+        shake.advance(1.0);
+        assert!(shake.is_finished());
+        assert_eq!(shake.offset(), CameraOffset::IDENTITY);
+    }
+
+    #[test]
+    fn kicks_recover_over_time() {
+        let mut fov_kick = FovKick::new(5.0);
+        fov_kick.kick(0.2);
+        fov_kick.advance(0.1);
+        assert!((fov_kick.fov_radians - 0.2 * (-0.5f32).exp()).abs() < 1e-6);
+
+        let mut recoil = Recoil::new(10.0);
+        recoil.kick(vec3(0.0, 0.0, 0.1), Quat::from_rotation_x(0.1));
+        assert!(!recoil.is_finished());
+        let mut time = Time::new();
+        time.update(Milisecond(0.0));
+        time.update(Milisecond(2000.0));
+        let mut stack = CameraModifierStack::new();
+        stack.push(CameraModifier::Recoil(recoil));
+        stack.push(CameraModifier::FovKick(fov_kick));
+        stack.update(&time);
+        stack.remove_finished();
+        assert!(stack.modifiers.is_empty());
+    }
+
+    #[test]
+    fn modifiers_offset_camera_matrices() {
+        let mut camera = perspective_camera();
+        camera.set_position(vec3(0.0, 1.0, 0.0));
+        let rest = camera.generate_camera_matrices();
+
+        let mut recoil = Recoil::new(1.0);
+        recoil.kick(vec3(0.0, 0.0, 0.5), Quat::from_rotation_x(0.1));
+        let mut fov_kick = FovKick::new(1.0);
+        fov_kick.kick(0.3);
+        let recoil = camera.modifiers.push(CameraModifier::Recoil(recoil));
+        camera.modifiers.push(CameraModifier::FovKick(fov_kick));
+        let kicked = camera.generate_camera_matrices();
+
+        // The gameplay transform is untouched
+        assert_eq!(camera.get_position(), vec3(0.0, 1.0, 0.0));
+        assert!(kicked
+            .transform_matrix
+            .w_axis
+            .truncate()
+            .abs_diff_eq(vec3(0.0, 1.0, 0.5), 1e-6));
+        let forward = kicked.transform_matrix.transform_vector3(-Vec3::Z);
+        assert!(forward.y > 0.0);
+        // A wider field of view shrinks the projection scale
+        assert!(kicked.projection_matrix.y_axis.y < rest.projection_matrix.y_axis.y);
+
+        if let Some(CameraModifier::Recoil(recoil)) = camera.modifiers.get_mut(recoil) {
+            recoil.kick(vec3(0.0, 0.0, 0.5), Quat::IDENTITY);
+        }
+        let combined = camera.modifiers.offset();
+        assert!(combined.translation.abs_diff_eq(vec3(0.0, 0.0, 1.0), 1e-6));
+        assert!((combined.fov_radians - 0.3).abs() < 1e-6);
+    }
+
+    #[test]
+    fn camera_block_std140_layout() {
+        let block = get_camera_uniform_block_definition();
+        let (offsets, size) = std140_layout(&block.uniforms).unwrap();
+        assert_eq!(size, CAMERA_BUFFER_SIZE);
+        assert_eq!(offsets[6], 384);
+        assert_eq!(offsets[7..], [448, 456, 460, 464, 472]);
+
+        let mut matrices = perspective_camera()
+            .generate_camera_matrices()
+            .with_frame_data(vec2(1280.0, 720.0), 12.5)
+            .with_jitter(vec2(0.25, -0.25));
+        matrices.previous_projection_view_matrix = Mat4::from_scale(Vec3::splat(3.0));
+        let data = camera_buffer_data(&matrices);
+        assert_eq!(data.len() * 4, CAMERA_BUFFER_SIZE);
+
+        let float_at = |uniform: usize| data[offsets[uniform] / 4];
+        let mat4_at = |uniform: usize| {
+            let start = offsets[uniform] / 4;
+            Mat4::from_cols_slice(&data[start..start + 16])
+        };
+        assert_eq!(mat4_at(0), matrices.transform_matrix);
+        assert_eq!(mat4_at(3), matrices.projection_view_matrix);
+        assert_eq!(mat4_at(5), matrices.inverse_projection_view_matrix);
+        assert_eq!(mat4_at(6), matrices.previous_projection_view_matrix);
+        assert_eq!(float_at(7), 1280.0);
+        assert_eq!(data[offsets[7] / 4 + 1], 720.0);
+        assert_eq!(float_at(8), matrices.z_near);
+        assert_eq!(float_at(9), matrices.z_far);
+        assert_eq!(float_at(10), 12.5);
+        assert_eq!(float_at(11), 0.25);
+        assert_eq!(data[offsets[11] / 4 + 1], -0.25);
+    }
+
+    #[test]
+    fn std140_layout_rules() {
+        let uniform = |kind: WebGLDataType, array_length: Option<u32>| ShaderUniform {
+            array_length,
+            kind,
+            name: String::new(),
+        };
+        let (offsets, size) = std140_layout(&[
+            uniform(WebGLDataType::Float, None),
+            uniform(WebGLDataType::Vec3, None),
+            uniform(WebGLDataType::Float, None),
+            uniform(WebGLDataType::Float, Some(2)),
+            uniform(WebGLDataType::Vec2, None),
+        ])
+        .unwrap();
+        assert_eq!(offsets, vec![0, 16, 28, 32, 64]);
+        assert_eq!(size, 80);
+        assert!(std140_layout(&[uniform(WebGLDataType::Sampler2D, None)]).is_none());
+    }
+
+    #[test]
+    fn camera_matrices_inverses_and_planes() {
+        let mut camera = perspective_camera();
+        camera.set_position(vec3(1.0, 2.0, 3.0));
+        let matrices = camera.generate_camera_matrices();
+        assert!(
+            (matrices.inverse_projection_matrix * matrices.projection_matrix)
+                .abs_diff_eq(Mat4::IDENTITY, 1e-4)
+        );
+        assert!(
+            (matrices.inverse_projection_view_matrix * matrices.projection_view_matrix)
+                .abs_diff_eq(Mat4::IDENTITY, 1e-4)
+        );
+        assert!((matrices.z_near - 0.1).abs() < 1e-4);
+        assert!((matrices.z_far - 100.0).abs() < 0.1);
+
+        camera.set_depth_options(DepthConvention::Reversed, true);
+        let matrices = camera.generate_camera_matrices();
+        assert!((matrices.z_near - 0.1).abs() < 1e-4);
+        assert_eq!(matrices.z_far, f32::INFINITY);
+
+        let mut next = camera.generate_camera_matrices();
+        next.set_previous(&matrices);
+        assert_eq!(
+            next.previous_projection_view_matrix,
+            matrices.projection_view_matrix
+        );
+    }
+
+    #[test]
+    fn jitter_offsets_projected_pixels() {
+        let size = vec2(800.0, 600.0);
+        let point = vec3(0.3, -0.2, -5.0);
+        let pixel = |matrices: &CameraMatrices| {
+            (matrices
+                .projection_view_matrix
+                .project_point3(point)
+                .truncate()
+                + Vec2::ONE)
+                * 0.5
+                * size
+        };
+        let matrices = perspective_camera()
+            .generate_camera_matrices()
+            .with_frame_data(size, 0.0);
+        let jittered = matrices.with_jitter(vec2(0.5, -0.25));
+        assert!((pixel(&jittered) - pixel(&matrices)).abs_diff_eq(vec2(0.5, -0.25), 1e-3));
+        // Jitter replaces the previous one instead of stacking
+        let rejittered = jittered.with_jitter(vec2(-0.5, 0.0));
+        assert!((pixel(&rejittered) - pixel(&matrices)).abs_diff_eq(vec2(-0.5, 0.0), 1e-3));
+
+        for frame in 0..16 {
+            let jitter = halton_jitter(frame);
+            assert!(jitter.cmpge(Vec2::splat(-0.5)).all() && jitter.cmple(Vec2::splat(0.5)).all());
+        }
+        assert_eq!(halton_jitter(0), vec2(0.0, 1.0 / 3.0 - 0.5));
+    }
+}
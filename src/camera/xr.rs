@@ -1,6 +1,8 @@
 use glam::*;
 
-use super::CameraMatrices;
+use super::{CameraMatrices, ViewData};
+use crate::transform::Transform;
+use crate::xr::{mat4_from_slice, views_to_view_data, XrViewSample};
 
 pub struct XrCamera {}
 
@@ -15,29 +17,20 @@ impl XrCamera {
         cam_matrix: Vec<f32>,
         camera_offset: Vec3,
     ) -> CameraMatrices {
-        let mut proj_matrix_arr = [0.0; 16];
-
-        for (index, val) in proj_matrix.iter().enumerate() {
-            proj_matrix_arr[index] = *val;
-        }
-        let proj_matrix = Mat4::from_cols_array(&proj_matrix_arr);
-
-        let mut cam_matrix_arr = [0.0; 16];
-        for (index, val) in cam_matrix.iter().enumerate() {
-            cam_matrix_arr[index] = *val;
-        }
+        let proj_matrix = mat4_from_slice(&proj_matrix).unwrap_or(Mat4::IDENTITY);
+        let cam_matrix = mat4_from_slice(&cam_matrix).unwrap_or(Mat4::IDENTITY);
         let player_camera_offset = glam::Mat4::from_translation(camera_offset);
-        let cam_matrix = player_camera_offset.mul_mat4(&Mat4::from_cols_array(&cam_matrix_arr));
-
-        let view_matrix = cam_matrix.inverse();
+        let cam_matrix = player_camera_offset.mul_mat4(&cam_matrix);
 
-        let proj_x_view = proj_matrix.mul_mat4(&view_matrix);
+        CameraMatrices::new(cam_matrix, proj_matrix)
+    }
 
-        CameraMatrices {
-            transform_matrix: cam_matrix,
-            view_matrix,
-            projection_matrix: proj_matrix,
-            projection_view_matrix: proj_x_view,
-        }
+    /// View data of every eye of the frame, e.g. a left and right view for stereo rendering
+    pub fn generate_view_data(
+        &mut self,
+        views: &[XrViewSample],
+        camera_offset: Vec3,
+    ) -> Vec<ViewData> {
+        views_to_view_data(views, &Transform::from_translation(camera_offset))
     }
 }
@@ -3,7 +3,7 @@ use std::rc::Rc;
 use glam::*;
 use rust_webgl2::*;
 
-use crate::{console_log_format, curves::UVLengthAlignedValue, renderer::Renderer};
+use crate::{console_log_format, curves::UVLengthAlignedValue, renderer::{RenderState, Renderer}};
 
 use super::{curve2d_data::Curve2DData, Curve2DVertexData, CurveType};
 
@@ -333,14 +333,17 @@ impl Curve2DRenderer {
         (data_buffer, index_buffer)
     }
 
+    /// Draws with the capabilities of `DefaultCurve2DMaterial`, the depth test follows the
+    /// convention of the renderer
     pub fn request_render(
         graphics: &Graphics,
+        render_state: &RenderState,
         vao: &GlVertexArrayObject,
         index_count: u32,
         index_type: IndexType,
         material: &mut GlMaterial,
     ) {
-        material.set_capabilities(graphics, 0);
+        render_state.set_capabilities(graphics, DrawCapabilities::default_opaque());
         vao.bind();
 
         let mut current_program = material.program.use_program();
@@ -263,8 +263,12 @@ mod icosahaedron {
             positions,
             indices: Some(indices),
             normals: None,
+            tangents: None,
             uvs: None,
-			colors: None
+			colors: None,
+			joints: None,
+			weights: None,
+			morph_targets: None
         }
     }
 }
\ No newline at end of file
@@ -29,9 +29,13 @@ pub fn generate_rect(width: f32, height: f32) -> MeshData {
     MeshData {
         positions: vertices,
         normals: None,
+        tangents: None,
         uvs: Some(vec![uv]),
         indices: Some(IndexData::U16(triangles_)),
         colors: None,
+        joints: None,
+        weights: None,
+        morph_targets: None,
     }
 }
 
@@ -68,8 +72,12 @@ pub fn generate_circle(radius: f32, edge_count: u32) -> MeshData {
         positions,
         indices: Some(indices),
         normals: None,
+        tangents: None,
         uvs: None,
         colors: None,
+        joints: None,
+        weights: None,
+        morph_targets: None,
     }
 }
 
@@ -120,9 +128,13 @@ pub fn generate_cilinder(
     MeshData {
         positions,
         normals: None,
+        tangents: None,
         uvs: Some(vec![uv]),
         indices: Some(indices),
         colors: None,
+        joints: None,
+        weights: None,
+        morph_targets: None,
     }
 }
 
@@ -173,8 +185,12 @@ pub fn generate_ring(inner_radius: f32, outer_radius: f32, edge_count: u32) -> M
         positions,
         indices: Some(indices),
         normals: None,
+        tangents: None,
         uvs: None,
         colors: None,
+        joints: None,
+        weights: None,
+        morph_targets: None,
     }
 }
 
@@ -212,8 +228,12 @@ pub fn generate_arc(radius: f32, arc_angle_deg: f32, edge_count: u32) -> MeshDat
         positions,
         indices: Some(indices),
         normals: None,
+        tangents: None,
         uvs: None,
         colors: None,
+        joints: None,
+        weights: None,
+        morph_targets: None,
     }
 }
 
@@ -292,8 +312,12 @@ pub fn generate_cone_flat(
     MeshData {
         positions: m_positions,
         normals: Some(m_normals),
+        tangents: None,
         uvs: None,
         colors: None,
+        joints: None,
+        weights: None,
+        morph_targets: None,
         indices: None,
     }
 }
@@ -211,9 +211,9 @@ impl CubeGizmo {
     ) {
         let render_data = CubeGizmoRenderData::new(&self);
         renderer.insert_render_request(
-            Box::new(move |gr: &Graphics, _| {
+            Box::new(move |gr: &Graphics, render_state| {
                 let mat = render_data.material.upgrade().unwrap();
-                mat.borrow().set_capabilities(gr, 0);
+                render_state.set_capabilities(gr, DrawCapabilities::default_opaque());
                 let vao = render_data.vao.upgrade().unwrap();
                 vao.bind();
                 let mut mat = mat.borrow_mut();
@@ -16,6 +16,9 @@ pub mod renderer;
 pub mod slotmap;
 pub mod sdf_generation;
 pub mod time;
+pub mod transform;
+pub mod tween;
+pub mod xr;
 
 pub use uuid;
 pub use rand;
@@ -31,6 +34,14 @@ pub fn set_camera_uniform_block_binding(program: &rust_webgl2::GlProgram) {
         .expect("Cannot set uniform block");
 }
 
+const SKINNING_BINDING_NUMBER: u32 = 1;
+
+pub fn set_skinning_uniform_block_binding(program: &rust_webgl2::GlProgram) {
+    program
+        .set_uniform_block_binding_str("BonePalette", SKINNING_BINDING_NUMBER)
+        .expect("Cannot set uniform block");
+}
+
 #[macro_export]
 macro_rules! console_log_format {
 	($($elem:expr),*) => {
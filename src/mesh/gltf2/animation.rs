@@ -0,0 +1,70 @@
+use glam::*;
+use gltf::animation::util::ReadOutputs;
+
+use crate::animation::{
+    AnimationChannel, AnimationClip, AnimationTarget, ChannelValues, Interpolation,
+};
+
+fn convert_interpolation(interpolation: gltf::animation::Interpolation) -> Interpolation {
+    match interpolation {
+        gltf::animation::Interpolation::Linear => Interpolation::Linear,
+        gltf::animation::Interpolation::Step => Interpolation::Step,
+        gltf::animation::Interpolation::CubicSpline => Interpolation::CubicSpline,
+    }
+}
+
+/// Creates a clip with channels targeting `AnimationTarget::Node`, using the glTF node indices.\
+/// Use `Gltf2Skin::retarget_clip` to play it on a skeleton.
+pub fn generate_gltf2_animation_clip<'err>(
+    animation: &gltf::Animation,
+    buffer_vec: &[gltf::buffer::Data],
+) -> Result<AnimationClip, &'err str> {
+    let mut channels = Vec::new();
+    for channel in animation.channels() {
+        let reader = channel.reader(|buffer| Some(&buffer_vec[buffer.index()]));
+        let times: Vec<f32> = match reader.read_inputs() {
+            Some(inputs) => inputs.collect(),
+            None => return Err("Animation channel without keyframe times"),
+        };
+        let values = match reader.read_outputs() {
+            Some(ReadOutputs::Translations(translations)) => {
+                ChannelValues::Translation(translations.map(Vec3::from_array).collect())
+            }
+            Some(ReadOutputs::Rotations(rotations)) => ChannelValues::Rotation(
+                rotations.into_f32().map(Quat::from_array).collect(),
+            ),
+            Some(ReadOutputs::Scales(scales)) => {
+                ChannelValues::Scale(scales.map(Vec3::from_array).collect())
+            }
+            Some(ReadOutputs::MorphTargetWeights(weights)) => {
+                let values: Vec<f32> = weights.into_f32().collect();
+                let values_per_key = match channel.sampler().interpolation() {
+                    gltf::animation::Interpolation::CubicSpline => times.len() * 3,
+                    _ => times.len(),
+                };
+                if values_per_key == 0 {
+                    return Err("Animation channel without keyframe times");
+                }
+                let target_count = values.len() / values_per_key;
+                if target_count * values_per_key != values.len() {
+                    return Err("Animation channel with an invalid number of weights");
+                }
+                ChannelValues::Weights {
+                    target_count,
+                    values,
+                }
+            }
+            None => return Err("Animation channel without keyframe values"),
+        };
+        channels.push(AnimationChannel {
+            target: AnimationTarget::Node(channel.target().node().index()),
+            interpolation: convert_interpolation(channel.sampler().interpolation()),
+            times,
+            values,
+        });
+    }
+    Ok(AnimationClip::new(
+        animation.name().map(String::from),
+        channels,
+    ))
+}
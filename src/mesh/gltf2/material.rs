@@ -1,8 +1,10 @@
 use std::{cell::RefCell, rc::Rc};
 
-use crate::{set_camera_uniform_block_binding};
+use crate::renderer::RenderState;
+use crate::{set_camera_uniform_block_binding, set_skinning_uniform_block_binding};
 
-use super::{shader::default_shader};
+use super::shader::{default_shader, skinned_shader, with_morph_targets, MAX_SHADER_MORPH_TARGETS};
+use super::skinning::{pack_palette_data, palette_texture_size, SkinningMode, MAX_UNIFORM_BONES};
 use glam::*;
 use rust_webgl2::*;
 
@@ -20,17 +22,23 @@ pub struct PropertyIndex {
     pub roughness: UniformIndex,
 }
 
+fn gltf2_draw_capabilities() -> DrawCapabilities {
+    DrawCapabilities {
+        cull_face: Some(CullMode::BACK),
+        depth_test: Some(DepthFunction::LEQUAL),
+        ..Default::default()
+    }
+}
+
 impl Gltf2DefaultMaterial {
     pub fn new(graphics: &Graphics) -> Result<Self, String> {
+        Self::with_shader_source(graphics, &default_shader())
+    }
 
-        let draw_capabilities = vec![DrawCapabilities {
-            cull_face: Some(CullMode::BACK),
-            depth_test: Some(DepthFunction::LEQUAL),
-            ..Default::default()
-        }];
+    fn with_shader_source(graphics: &Graphics, shader_source: &ShaderSource) -> Result<Self, String> {
+        let draw_capabilities = vec![gltf2_draw_capabilities()];
 
-        let shader_source = default_shader();
-        let mut material = GlMaterial::with_source(graphics, draw_capabilities, &shader_source).expect("Material creation error");
+        let mut material = GlMaterial::with_source(graphics, draw_capabilities, shader_source).expect("Material creation error");
         set_camera_uniform_block_binding(&material.program);
 
         //let common_uniforms = &shader_source.common_uniforms.uniforms;
@@ -54,6 +62,12 @@ impl Gltf2DefaultMaterial {
             property_index,
         })
     }
+
+    /// Sets the capabilities of the material from a render request, the depth test follows the
+    /// convention of the renderer
+    pub fn set_capabilities(&self, graphics: &Graphics, render_state: &RenderState) {
+        render_state.set_capabilities(graphics, gltf2_draw_capabilities());
+    }
 }
 
 //Setting material parameter
@@ -97,3 +111,233 @@ impl Gltf2DefaultMaterial {
         )
     }
 }
+
+const PALETTE_TEXTURE_PROPS: Texture2DProps = Texture2DProps {
+    wrap_x: TextureWrap::CLAMP_TO_EDGE,
+    wrap_y: TextureWrap::CLAMP_TO_EDGE,
+    mag_filter: MagFilter::NEAREST,
+    min_filter: MinFilter::NEAREST,
+    base_level: 0,
+    max_level: 0,
+    min_max_lod: (0.0, 0.0),
+};
+
+/// The uniform buffer bound to the `BonePalette` block, create a single one and share it with
+/// every `Gltf2SkinnedMaterial`
+pub fn create_skinning_palette_buffer(graphics: &Graphics) -> Result<GlUniformBuffer, String> {
+    match GlUniformBuffer::with_capacity(
+        graphics,
+        (MAX_UNIFORM_BONES * 16 * 4) as u32,
+        BufferUsage::DYNAMIC_DRAW,
+        crate::SKINNING_BINDING_NUMBER,
+    ) {
+        Ok(palette_buffer) => Ok(palette_buffer),
+        Err(_) => Err("Bone palette buffer could not be created".into()),
+    }
+}
+
+pub enum SkinningPaletteBuffer {
+    /// Shared by the materials, see `create_skinning_palette_buffer`
+    UniformBlock(Rc<RefCell<GlUniformBuffer>>),
+    Texture(Rc<GlTexture2D>),
+}
+
+/// `Gltf2DefaultMaterial` that deforms the mesh with a bone palette, see `Skeleton::skinning_palette`.\
+/// Materials that use the uniform block share one palette buffer, each one uploads its palette
+/// with `bind_palette` right before its draw so two characters don't draw with the same pose.\
+/// The palette holds the world transform of the skeleton, leave the model transform of
+/// `default_material` at identity.
+pub struct Gltf2SkinnedMaterial {
+    pub default_material: Gltf2DefaultMaterial,
+    pub mode: SkinningMode,
+    pub bone_count: usize,
+    pub palette_buffer: SkinningPaletteBuffer,
+    palette_data: Vec<f32>,
+}
+
+impl Gltf2SkinnedMaterial {
+    /// `shared_palette_buffer` is only used when the bones fit in the uniform block
+    pub fn new(
+        graphics: &Graphics,
+        bone_count: usize,
+        shared_palette_buffer: &Rc<RefCell<GlUniformBuffer>>,
+    ) -> Result<Self, String> {
+        let mode = SkinningMode::for_bone_count(bone_count);
+        Self::with_shader_source(graphics, bone_count, shared_palette_buffer, &skinned_shader(mode))
+    }
+
+    /// `shader_source` has to be built on top of the `skinned_shader` of the skinning mode of
+    /// `bone_count`
+    fn with_shader_source(
+        graphics: &Graphics,
+        bone_count: usize,
+        shared_palette_buffer: &Rc<RefCell<GlUniformBuffer>>,
+        shader_source: &ShaderSource,
+    ) -> Result<Self, String> {
+        let mode = SkinningMode::for_bone_count(bone_count);
+        let default_material = Gltf2DefaultMaterial::with_shader_source(graphics, shader_source)?;
+
+        let palette_buffer = match mode {
+            SkinningMode::UniformBlock => {
+                set_skinning_uniform_block_binding(&default_material.material.borrow().program);
+                SkinningPaletteBuffer::UniformBlock(Rc::clone(shared_palette_buffer))
+            }
+            SkinningMode::Texture => {
+                let palette_texture = match GlTexture2D::new(
+                    graphics,
+                    PALETTE_TEXTURE_PROPS,
+                    palette_texture_size(bone_count),
+                    TextureInternalFormat::RGBA32F,
+                    None,
+                    Some("BonePaletteTexture".into()),
+                ) {
+                    Ok(palette_texture) => Rc::new(palette_texture),
+                    Err(_) => return Err("Bone palette texture could not be created".into()),
+                };
+                if default_material
+                    .material
+                    .borrow_mut()
+                    .set_texture_sampler_uniform("bone_texture", Rc::clone(&palette_texture))
+                    .is_err()
+                {
+                    return Err("Bone palette texture could not be bound".into());
+                }
+                SkinningPaletteBuffer::Texture(palette_texture)
+            }
+        };
+
+        Ok(Self {
+            default_material,
+            mode,
+            bone_count,
+            palette_buffer,
+            palette_data: pack_palette_data(&vec![Mat4::IDENTITY; bone_count]),
+        })
+    }
+
+    /// Stores the skinning matrices, the palette texture is updated right away while the shared
+    /// uniform block waits for `bind_palette`
+    pub fn set_palette(&mut self, palette: &[Mat4]) -> Result<(), String> {
+        if palette.len() != self.bone_count {
+            return Err(format!(
+                "Palette has {} matrices, the material expects {}",
+                palette.len(),
+                self.bone_count
+            ));
+        }
+        self.palette_data = pack_palette_data(palette);
+        if let SkinningPaletteBuffer::Texture(palette_texture) = &self.palette_buffer {
+            if palette_texture
+                .set_texture_data(0, bytemuck::cast_slice(&self.palette_data), 0)
+                .is_err()
+            {
+                return Err("Bone palette texture could not be updated".into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Uploads the palette of this material to the shared uniform block, call it from the
+    /// render request of the mesh before drawing. Materials with a palette texture have nothing
+    /// to do.
+    pub fn bind_palette(&self) {
+        if let SkinningPaletteBuffer::UniformBlock(palette_buffer) = &self.palette_buffer {
+            palette_buffer
+                .borrow_mut()
+                .buffer_data(self.palette_data.as_slice());
+        }
+    }
+}
+
+/// `Gltf2DefaultMaterial` that applies up to `MAX_SHADER_MORPH_TARGETS` morph targets on the GPU,
+/// the vertex array is created with the locations of `gltf2_attribute_locations`.
+pub struct Gltf2MorphMaterial {
+    pub default_material: Gltf2DefaultMaterial,
+    pub target_count: usize,
+    morph_weights: UniformIndex,
+}
+
+impl Gltf2MorphMaterial {
+    pub fn new(graphics: &Graphics, target_count: usize) -> Result<Self, String> {
+        let target_count = target_count.min(MAX_SHADER_MORPH_TARGETS);
+        let shader_source = with_morph_targets(default_shader(), target_count);
+        let default_material = Gltf2DefaultMaterial::with_shader_source(graphics, &shader_source)?;
+        let morph_weights = default_material
+            .material
+            .borrow_mut()
+            .insert_uniform(Vec4::ZERO, "morph_weights");
+        Ok(Self {
+            default_material,
+            target_count,
+            morph_weights,
+        })
+    }
+
+    /// Weights of the targets, only the first `target_count` are used
+    pub fn set_morph_weights(&self, weights: &[f32]) {
+        set_morph_weights(&self.default_material, self.morph_weights, self.target_count, weights);
+    }
+}
+
+fn set_morph_weights(
+    default_material: &Gltf2DefaultMaterial,
+    morph_weights: UniformIndex,
+    target_count: usize,
+    weights: &[f32],
+) {
+    let mut packed_weights = [0.0; MAX_SHADER_MORPH_TARGETS];
+    for (packed, weight) in packed_weights.iter_mut().zip(weights.iter()).take(target_count) {
+        *packed = *weight;
+    }
+    default_material.material.borrow_mut().program.uniforms.set_uniform(
+        morph_weights,
+        FloatUniform::Vec4(Vec4::from_array(packed_weights)).into(),
+    );
+}
+
+/// `Gltf2SkinnedMaterial` that also applies up to `MAX_SHADER_MORPH_TARGETS` morph targets, the
+/// morph is applied before skinning. The palette is set and bound through `skinned_material`.
+pub struct Gltf2SkinnedMorphMaterial {
+    pub skinned_material: Gltf2SkinnedMaterial,
+    pub target_count: usize,
+    morph_weights: UniformIndex,
+}
+
+impl Gltf2SkinnedMorphMaterial {
+    pub fn new(
+        graphics: &Graphics,
+        bone_count: usize,
+        shared_palette_buffer: &Rc<RefCell<GlUniformBuffer>>,
+        target_count: usize,
+    ) -> Result<Self, String> {
+        let target_count = target_count.min(MAX_SHADER_MORPH_TARGETS);
+        let mode = SkinningMode::for_bone_count(bone_count);
+        let shader_source = with_morph_targets(skinned_shader(mode), target_count);
+        let skinned_material = Gltf2SkinnedMaterial::with_shader_source(
+            graphics,
+            bone_count,
+            shared_palette_buffer,
+            &shader_source,
+        )?;
+        let morph_weights = skinned_material
+            .default_material
+            .material
+            .borrow_mut()
+            .insert_uniform(Vec4::ZERO, "morph_weights");
+        Ok(Self {
+            skinned_material,
+            target_count,
+            morph_weights,
+        })
+    }
+
+    /// Weights of the targets, only the first `target_count` are used
+    pub fn set_morph_weights(&self, weights: &[f32]) {
+        set_morph_weights(
+            &self.skinned_material.default_material,
+            self.morph_weights,
+            self.target_count,
+            weights,
+        );
+    }
+}
@@ -5,9 +5,34 @@ use gltf::mesh::Reader;
 use rust_webgl2::*;
 
 pub mod material;
-mod shader;
+pub mod shader;
+pub mod skin;
+pub mod skinning;
+pub mod animation;
+mod test;
 
 use super::mesh_data::{IndexData, MeshBuffers, MeshData};
+use super::morph_target::MorphTarget;
+use crate::animation::AnimationClip;
+use animation::generate_gltf2_animation_clip;
+use skin::{generate_gltf2_skin, normalize_weights, Gltf2Skin};
+use skinning::pack_joint_attributes;
+use shader::{gltf2_attribute_locations, MAX_SHADER_MORPH_TARGETS};
+
+/// Blender and other exporters store the morph target names in the mesh extras
+#[derive(serde::Deserialize)]
+struct MeshExtras {
+    #[serde(rename = "targetNames", default)]
+    target_names: Vec<String>,
+}
+
+fn read_morph_target_names(mesh: &gltf::Mesh) -> Vec<String> {
+    mesh.extras()
+        .as_ref()
+        .and_then(|extras| serde_json::from_str::<MeshExtras>(extras.get()).ok())
+        .map(|extras| extras.target_names)
+        .unwrap_or_default()
+}
 
 pub fn generate_all_mesh_from_gltf<'a, 'err>(
     file_buffer: &[u8],
@@ -38,6 +63,65 @@ pub fn generate_all_mesh_from_gltf<'a, 'err>(
     }
 }
 
+pub struct Gltf2Asset {
+    pub meshes: Vec<Gltf2Mesh>,
+    pub skins: Vec<Gltf2Skin>,
+    /// Clips target glTF nodes, see `Gltf2Skin::retarget_clip`
+    pub animations: Vec<AnimationClip>,
+}
+
+/// Imports the meshes, skins and animations of the file.\
+/// Joint indices of skinned meshes are remapped to the `BoneIndex` of their skin.
+pub fn generate_asset_from_gltf<'err>(file_buffer: &[u8]) -> Result<Gltf2Asset, &'err str> {
+    let (document, buffer_vec, _image_vec) = match gltf::import_slice(file_buffer) {
+        Ok(import) => import,
+        Err(_) => return Err("Gltf2 asset could not be imported"),
+    };
+
+    let mut skins = Vec::new();
+    for skin in document.skins() {
+        skins.push(generate_gltf2_skin(&skin, &document, &buffer_vec)?);
+    }
+
+    let mut animations = Vec::new();
+    for animation in document.animations() {
+        animations.push(generate_gltf2_animation_clip(&animation, &buffer_vec)?);
+    }
+
+    let mut mesh_index = 0;
+    let mut meshes = Vec::new();
+    let mut mesh_ranges = Vec::new();
+    for mesh in document.meshes() {
+        let mesh_name = mesh.name().map(String::from);
+        match generate_mesh_from_primitives(&mesh, &buffer_vec, mesh_name, &mut mesh_index) {
+            Ok(mesh_vec) => {
+                let start = meshes.len();
+                meshes.extend(mesh_vec);
+                mesh_ranges.push(start..meshes.len());
+            }
+            Err(_) => return Err("Gltf2 mesh could not be generated"),
+        }
+    }
+
+    for node in document.nodes() {
+        if let (Some(mesh), Some(skin)) = (node.mesh(), node.skin()) {
+            for gltf2_mesh in meshes[mesh_ranges[mesh.index()].clone()].iter_mut() {
+                // A mesh shared by several skinned nodes keeps the first skin
+                if gltf2_mesh.skin.is_none() {
+                    skins[skin.index()].remap_joints(&mut gltf2_mesh.vertex_data);
+                    gltf2_mesh.skin = Some(skin.index());
+                }
+            }
+        }
+    }
+
+    Ok(Gltf2Asset {
+        meshes,
+        skins,
+        animations,
+    })
+}
+
 pub fn generate_mesh_from_primitives(
     mesh_data: &gltf::Mesh,
     buffer_vec: &Vec<gltf::buffer::Data>,
@@ -45,6 +129,8 @@ pub fn generate_mesh_from_primitives(
     mesh_index: &mut u32,
 ) -> Result<Vec<Gltf2Mesh>, ()> {
     let mut gltf_mesh_vec = Vec::new();
+    let target_names = read_morph_target_names(mesh_data);
+    let morph_weights = mesh_data.weights().map(|weights| weights.to_vec()).unwrap_or_default();
     for primitive in mesh_data.primitives() {
         let mesh_name = match &mesh_name {
             Some(m_name) => {
@@ -57,7 +143,29 @@ pub fn generate_mesh_from_primitives(
         };
         let reader = primitive.reader(|buffer| Some(&buffer_vec[buffer.index()]));
         match Gltf2Mesh::from_reader( reader, mesh_name) {
-            Ok(gltf_2_mesh) => {
+            Ok(mut gltf_2_mesh) => {
+                // Lines, points, strips and fans keep their vertices and the order of their
+                // indices
+                if primitive.mode() == gltf::mesh::Mode::Triangles {
+                    let vertex_data = &mut gltf_2_mesh.vertex_data;
+                    // glTF expects MikkTSpace tangents when they are not in the file, normal
+                    // maps of the first uv set are the common case
+                    if vertex_data.tangents.is_none()
+                        && vertex_data.normals.is_some()
+                        && vertex_data.uvs.is_some()
+                        && vertex_data.generate_tangents(0).is_err()
+                    {
+                        return Err(());
+                    }
+                    vertex_data.optimize();
+                }
+                if let Some(morph_targets) = gltf_2_mesh.vertex_data.morph_targets.as_mut() {
+                    for (target, name) in morph_targets.iter_mut().zip(target_names.iter()) {
+                        target.name = Some(name.clone());
+                    }
+                    gltf_2_mesh.morph_weights = morph_weights.clone();
+                    gltf_2_mesh.morph_weights.resize(morph_targets.len(), 0.0);
+                }
                 gltf_mesh_vec.push(gltf_2_mesh);
             }
             Err(_) => return Err(()),
@@ -107,6 +215,11 @@ where
         }
     };
 
+    let opt_vertex_tangents = match reader.read_tangents() {
+        Some(tangent_iter) => Some(tangent_iter.map(Vec4::from_array).collect::<Vec<Vec4>>()),
+        None => None,
+    };
+
     let vertex_uvs = {
         let mut uvs = Vec::new();
         for i in 0..12 {
@@ -132,6 +245,43 @@ where
         }
     }
 
+    let opt_vertex_joints = match reader.read_joints(0) {
+        Some(joint_iter) => Some(
+            joint_iter
+                .into_u16()
+                .map(|j| uvec4(j[0] as u32, j[1] as u32, j[2] as u32, j[3] as u32))
+                .collect::<Vec<UVec4>>(),
+        ),
+        None => None,
+    };
+
+    let opt_vertex_weights = match reader.read_weights(0) {
+        Some(weight_iter) => Some(
+            weight_iter
+                .into_f32()
+                .map(|w| normalize_weights(vec4(w[0], w[1], w[2], w[3])))
+                .collect::<Vec<Vec4>>(),
+        ),
+        None => None,
+    };
+
+    let mut morph_targets = Vec::new();
+    for (position_iter, normal_iter, _tangent_iter) in reader.read_morph_targets() {
+        let position_deltas = match position_iter {
+            Some(position_iter) => position_iter.map(Vec3::from_array).collect(),
+            None => vec![Vec3::ZERO; vertex_positions.len()],
+        };
+        let normal_deltas = match normal_iter {
+            Some(normal_iter) => Some(normal_iter.map(Vec3::from_array).collect()),
+            None => None,
+        };
+        morph_targets.push(MorphTarget {
+            name: None,
+            position_deltas,
+            normal_deltas,
+        });
+    }
+
     let opt_index_data = if let Some(index_reader) = reader.read_indices() {
         match index_reader {
             gltf::mesh::util::ReadIndices::U8(i_iter) => {
@@ -154,9 +304,10 @@ where
         None
     };
 
-    return Ok(MeshData {
+    Ok(MeshData {
         positions: vertex_positions,
         normals: opt_vertex_normals,
+        tangents: opt_vertex_tangents,
         uvs: if vertex_uvs.len() > 0 {
             Some(vertex_uvs)
         } else {
@@ -164,24 +315,78 @@ where
         },
         indices: opt_index_data,
         colors: None,
-    });
+        joints: opt_vertex_joints,
+        weights: opt_vertex_weights,
+        morph_targets: if morph_targets.len() > 0 {
+            Some(morph_targets)
+        } else {
+            None
+        },
+    })
 }
 
+/// One buffer per attribute at the locations of `gltf2_attribute_locations`, each buffer is
+/// paired with its location in `MeshBuffers::vertex_buffer_locations`
 pub fn create_mesh_buffers(graphics: &Graphics, gltf2_data: &MeshData) -> Result<MeshBuffers, ()> {
-    let position_buffer = GlBuffer::with_data_static_array_buffer(graphics, &gltf2_data.positions)?;
+    let locations = gltf2_attribute_locations(gltf2_data, MAX_SHADER_MORPH_TARGETS);
     let mut vertex_buffers = Vec::new();
-    vertex_buffers.push(Rc::new(position_buffer));
+    let mut vertex_buffer_locations = Vec::new();
+    let mut push_buffer = |location: u32, buffer: GlBuffer| {
+        vertex_buffers.push(Rc::new(buffer));
+        vertex_buffer_locations.push(location);
+    };
 
-    if let Some(normals) = &gltf2_data.normals {
-        vertex_buffers.push(Rc::new(GlBuffer::with_data_static_array_buffer(
-            graphics, &normals,
-        )?))
+    push_buffer(
+        locations.position_loc,
+        GlBuffer::with_data_static_array_buffer(graphics, &gltf2_data.positions)?,
+    );
+    if let (Some(location), Some(normals)) = (locations.normals_loc, &gltf2_data.normals) {
+        push_buffer(location, GlBuffer::with_data_static_array_buffer(graphics, normals)?);
+    }
+    if let (Some(location), Some(tangents)) = (locations.tangents_loc, &gltf2_data.tangents) {
+        push_buffer(location, GlBuffer::with_data_static_array_buffer(graphics, tangents)?);
     }
-    if let Some(uvs) = gltf2_data.uvs.as_ref() {
-        for uv in uvs.iter() {
-            vertex_buffers.push(Rc::new(GlBuffer::with_data_static_array_buffer(
-                graphics, uv,
-            )?))
+    if let (Some(uv_locations), Some(uvs)) = (&locations.uvs, &gltf2_data.uvs) {
+        for (uv_set, location) in uv_locations {
+            push_buffer(
+                *location,
+                GlBuffer::with_data_static_array_buffer(graphics, &uvs[*uv_set])?,
+            );
+        }
+    }
+    if let (Some(location), Some(joints)) = (locations.joints_loc, &gltf2_data.joints) {
+        push_buffer(
+            location,
+            GlBuffer::with_data_static_array_buffer(graphics, &pack_joint_attributes(joints))?,
+        );
+    }
+    if let (Some(location), Some(weights)) = (locations.weights_loc, &gltf2_data.weights) {
+        push_buffer(location, GlBuffer::with_data_static_array_buffer(graphics, weights)?);
+    }
+    if let Some(morph_targets) = &gltf2_data.morph_targets {
+        for (target, location) in locations.morph_positions.iter().flatten() {
+            push_buffer(
+                *location,
+                GlBuffer::with_data_static_array_buffer(
+                    graphics,
+                    &morph_targets[*target].position_deltas,
+                )?,
+            );
+        }
+        for (target, location) in locations.morph_normals.iter().flatten() {
+            let target = &morph_targets[*target];
+            let zero_deltas;
+            let normal_deltas = match &target.normal_deltas {
+                Some(normal_deltas) => normal_deltas,
+                None => {
+                    zero_deltas = vec![Vec3::ZERO; target.position_deltas.len()];
+                    &zero_deltas
+                }
+            };
+            push_buffer(
+                *location,
+                GlBuffer::with_data_static_array_buffer(graphics, normal_deltas)?,
+            );
         }
     }
     let index_buffer = match &gltf2_data.indices {
@@ -209,7 +414,8 @@ pub fn create_mesh_buffers(graphics: &Graphics, gltf2_data: &MeshData) -> Result
     };
 
     Ok(MeshBuffers {
-        vertex_buffers: vertex_buffers,
+        vertex_buffers,
+        vertex_buffer_locations,
         index_buffer: match index_buffer {
             Some(index_buffer) => Some(Rc::new(index_buffer)),
             None => None,
@@ -224,6 +430,10 @@ pub fn create_mesh_buffers(graphics: &Graphics, gltf2_data: &MeshData) -> Result
 pub struct Gltf2Mesh {
     pub name: Option<String>,
     pub vertex_data: MeshData,
+    /// Index in `Gltf2Asset::skins` of the skin deforming the mesh
+    pub skin: Option<usize>,
+    /// Default weight of every morph target of `vertex_data`
+    pub morph_weights: Vec<f32>,
 }
 
 impl Gltf2Mesh {
@@ -238,6 +448,8 @@ impl Gltf2Mesh {
             Ok(gltf2_data) => Ok(Self {
                 name: mesh_name,
                 vertex_data: gltf2_data,
+                skin: None,
+                morph_weights: Vec::new(),
             }),
             Err(_) => Err(()),
         }
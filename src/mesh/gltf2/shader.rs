@@ -2,6 +2,71 @@ use rust_webgl2::*;
 
 use crate::camera::get_camera_uniform_block_definition;
 
+use super::super::mesh_data::{AttributeLocations, MeshData};
+use super::skinning::{SkinningMode, MAX_UNIFORM_BONES};
+
+// Attribute locations of the glTF shaders, the vertex data is laid out with
+// `gltf2_attribute_locations`
+pub const POSITION_LOCATION: u32 = 0;
+pub const NORMAL_LOCATION: u32 = 1;
+pub const JOINTS_LOCATION: u32 = 2;
+pub const WEIGHTS_LOCATION: u32 = 3;
+/// Not read by the shaders of this module, for custom normal mapping shaders
+pub const TANGENT_LOCATION: u32 = 12;
+/// Location of the first uv set, the next sets use the following locations
+pub const UV_LOCATION: u32 = 13;
+/// Uv sets that fit in the 16 attributes WebGL2 guarantees
+pub const MAX_UV_SETS: usize = 3;
+
+pub fn morph_position_location(target: usize) -> u32 {
+    4 + target as u32
+}
+
+pub fn morph_normal_location(target: usize) -> u32 {
+    4 + (MAX_SHADER_MORPH_TARGETS + target) as u32
+}
+
+/// Locations of every attribute of the mesh the glTF shaders can read, with the deltas of up
+/// to `morph_target_count` morph targets for `with_morph_targets`
+pub fn gltf2_attribute_locations(
+    mesh_data: &MeshData,
+    morph_target_count: usize,
+) -> AttributeLocations {
+    let morph_target_count = mesh_data
+        .morph_targets
+        .as_ref()
+        .map_or(0, |targets| targets.len())
+        .min(morph_target_count)
+        .min(MAX_SHADER_MORPH_TARGETS);
+    let uv_set_count = mesh_data
+        .uvs
+        .as_ref()
+        .map_or(0, |uvs| uvs.len())
+        .min(MAX_UV_SETS);
+    AttributeLocations {
+        position_loc: POSITION_LOCATION,
+        normals_loc: mesh_data.normals.as_ref().map(|_| NORMAL_LOCATION),
+        tangents_loc: mesh_data.tangents.as_ref().map(|_| TANGENT_LOCATION),
+        uvs: (uv_set_count > 0).then(|| {
+            (0..uv_set_count)
+                .map(|uv_set| (uv_set, UV_LOCATION + uv_set as u32))
+                .collect()
+        }),
+        joints_loc: mesh_data.joints.as_ref().map(|_| JOINTS_LOCATION),
+        weights_loc: mesh_data.weights.as_ref().map(|_| WEIGHTS_LOCATION),
+        morph_positions: (morph_target_count > 0).then(|| {
+            (0..morph_target_count)
+                .map(|target| (target, morph_position_location(target)))
+                .collect()
+        }),
+        morph_normals: (morph_target_count > 0).then(|| {
+            (0..morph_target_count)
+                .map(|target| (target, morph_normal_location(target)))
+                .collect()
+        }),
+    }
+}
+
 const VERT_MAIN_FN: &str = r#"
 vec4 world_position = model_transform * vec4(att_position, 1.0);
 v_position = world_position.xyz;
@@ -10,6 +75,16 @@ cam_position = camera_transform[3].xyz;
 gl_Position = proj_x_view * world_position;
 "#;
 
+// The palette already places the vertices in world space, `model_transform` stays at identity
+const SKINNED_VERT_MAIN_FN: &str = r#"
+mat4 skin_transform = model_transform * get_skin_matrix(att_joints, att_weights);
+vec4 world_position = skin_transform * vec4(att_position, 1.0);
+v_position = world_position.xyz;
+v_normal = normalize((skin_transform * vec4(att_normal, 0.0)).xyz);
+cam_position = camera_transform[3].xyz;
+gl_Position = proj_x_view * world_position;
+"#;
+
 const FRAG_MAIN_FN: &str = r#"
 vec3 normal = normalize(v_normal);
 vec3 world_pos = v_position;
@@ -83,12 +158,12 @@ pub fn default_shader() -> ShaderSource {
         vertex_shader: ShaderStage {
             attributes: vec![
                 ShaderAttribute {
-                    layout_loc: 0,
+                    layout_loc: POSITION_LOCATION,
                     kind: WebGLDataType::Vec3,
                     name: "att_position".into(),
                 },
                 ShaderAttribute {
-                    layout_loc: 1,
+                    layout_loc: NORMAL_LOCATION,
                     kind: WebGLDataType::Vec3,
                     name: "att_normal".into(),
                 },
@@ -126,3 +201,139 @@ pub fn default_shader() -> ShaderSource {
         local_import: None
     }
 }
+
+pub const SKINNING_UNIFORM_BLOCK_NAME: &str = "BonePalette";
+
+fn get_skin_matrix_function(mode: SkinningMode) -> FunctionDefinition {
+    let body = match mode {
+        SkinningMode::UniformBlock => {
+            r#"
+return bone_matrices[int(joints.x)] * weights.x
+    + bone_matrices[int(joints.y)] * weights.y
+    + bone_matrices[int(joints.z)] * weights.z
+    + bone_matrices[int(joints.w)] * weights.w;
+"#
+        }
+        SkinningMode::Texture => {
+            r#"
+mat4 skin_matrix = mat4(0.0);
+for(int i = 0; i < 4; i++) {
+    int bone = int(joints[i]);
+    mat4 bone_matrix = mat4(
+        texelFetch(bone_texture, ivec2(0, bone), 0),
+        texelFetch(bone_texture, ivec2(1, bone), 0),
+        texelFetch(bone_texture, ivec2(2, bone), 0),
+        texelFetch(bone_texture, ivec2(3, bone), 0)
+    );
+    skin_matrix += bone_matrix * weights[i];
+}
+return skin_matrix;
+"#
+        }
+    };
+    FunctionDefinition {
+        name: "get_skin_matrix".into(),
+        definition: FunctionDefinitionType::InlineFn {
+            return_type: WebGLDataType::Mat4,
+            parameters: "vec4 joints, vec4 weights".into(),
+            body: body.into(),
+        },
+    }
+}
+
+/// `default_shader` with joint and weight attributes at locations 2 and 3, the vertices are
+/// deformed by the bone palette before the model transform.\
+/// `Skeleton::skinning_palette` already includes the transform of the skeleton, the model
+/// transform has to stay at identity or the mesh is moved twice.
+pub fn skinned_shader(mode: SkinningMode) -> ShaderSource {
+    let mut shader_source = default_shader();
+    shader_source.name = String::from("Mesh Skinned Shader");
+    shader_source
+        .imported_functions
+        .push(get_skin_matrix_function(mode));
+
+    let vertex_shader = &mut shader_source.vertex_shader;
+    vertex_shader.attributes.push(ShaderAttribute {
+        layout_loc: JOINTS_LOCATION,
+        kind: WebGLDataType::Vec4,
+        name: "att_joints".into(),
+    });
+    vertex_shader.attributes.push(ShaderAttribute {
+        layout_loc: WEIGHTS_LOCATION,
+        kind: WebGLDataType::Vec4,
+        name: "att_weights".into(),
+    });
+    vertex_shader.import_fn.push("get_skin_matrix".into());
+    match mode {
+        SkinningMode::UniformBlock => {
+            vertex_shader
+                .uniform_collection
+                .uniform_blocks
+                .push(ShaderUniformBlock {
+                    name: SKINNING_UNIFORM_BLOCK_NAME.into(),
+                    binding_number: crate::SKINNING_BINDING_NUMBER,
+                    uniforms: vec![ShaderUniform {
+                        array_length: Some(MAX_UNIFORM_BONES as _),
+                        kind: WebGLDataType::Mat4,
+                        name: "bone_matrices".into(),
+                    }],
+                })
+        }
+        SkinningMode::Texture => vertex_shader.uniform_collection.uniforms.push(ShaderUniform {
+            array_length: None,
+            kind: WebGLDataType::Sampler2D,
+            name: "bone_texture".into(),
+        }),
+    }
+    vertex_shader.main_fn = SKINNED_VERT_MAIN_FN.into();
+    shader_source
+}
+
+/// Morph targets applied by `with_morph_targets`, the deltas use attribute locations 4 to 11
+pub const MAX_SHADER_MORPH_TARGETS: usize = 4;
+
+const MORPH_WEIGHT_COMPONENTS: [&str; MAX_SHADER_MORPH_TARGETS] = ["x", "y", "z", "w"];
+
+/// Adds position and normal delta attributes for the first `target_count` morph targets,
+/// weighted by the `morph_weights` uniform.\
+/// Works on top of `default_shader` and `skinned_shader`, the morph is applied before skinning.
+/// `Gltf2MorphMaterial` and `Gltf2SkinnedMorphMaterial` use it.
+pub fn with_morph_targets(mut shader_source: ShaderSource, target_count: usize) -> ShaderSource {
+    let target_count = target_count.min(MAX_SHADER_MORPH_TARGETS);
+    let vertex_shader = &mut shader_source.vertex_shader;
+    let mut morphed_position = String::from("vec3 morphed_position = att_position");
+    let mut morphed_normal = String::from("vec3 morphed_normal = att_normal");
+    for (target, weight) in MORPH_WEIGHT_COMPONENTS.iter().enumerate().take(target_count) {
+        vertex_shader.attributes.push(ShaderAttribute {
+            layout_loc: morph_position_location(target),
+            kind: WebGLDataType::Vec3,
+            name: format!("att_morph_position_{}", target),
+        });
+        vertex_shader.attributes.push(ShaderAttribute {
+            layout_loc: morph_normal_location(target),
+            kind: WebGLDataType::Vec3,
+            name: format!("att_morph_normal_{}", target),
+        });
+        morphed_position.push_str(&format!(
+            " + morph_weights.{} * att_morph_position_{}",
+            weight, target
+        ));
+        morphed_normal.push_str(&format!(
+            " + morph_weights.{} * att_morph_normal_{}",
+            weight, target
+        ));
+    }
+    vertex_shader.uniform_collection.uniforms.push(ShaderUniform {
+        array_length: None,
+        kind: WebGLDataType::Vec4,
+        name: "morph_weights".into(),
+    });
+
+    let main_fn = vertex_shader
+        .main_fn
+        .replace("att_position", "morphed_position")
+        .replace("att_normal", "morphed_normal");
+    vertex_shader.main_fn = format!("\n{};\n{};{}", morphed_position, morphed_normal, main_fn);
+    shader_source.name.push_str(" Morph");
+    shader_source
+}
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+
+use glam::*;
+
+use crate::animation::{AnimationClip, AnimationTarget, BoneIndex, Skeleton};
+use crate::mesh::mesh_data::MeshData;
+use crate::transform::Transform;
+
+pub struct Gltf2Skin {
+    pub name: Option<String>,
+    pub skeleton: Skeleton,
+    /// glTF node index of every bone, indexed by `BoneIndex`
+    pub joint_nodes: Vec<usize>,
+    /// `BoneIndex` of every joint of the glTF skin, in the order used by `JOINTS_0`.\
+    /// Bones are sorted so parents come before their children, which can differ from the file.
+    pub joint_to_bone: Vec<BoneIndex>,
+}
+
+/// Scales the weights so they add up to one, as required by the glTF specification
+pub fn normalize_weights(weights: Vec4) -> Vec4 {
+    let total = weights.x + weights.y + weights.z + weights.w;
+    if total > f32::EPSILON {
+        weights / total
+    } else {
+        weights
+    }
+}
+
+pub fn node_local_transform(node: &gltf::Node) -> Transform {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    Transform {
+        translation: Vec3::from_array(translation),
+        rotation: Quat::from_array(rotation),
+        scale: Vec3::from_array(scale),
+    }
+}
+
+/// Parent node index of every node of the document
+pub fn generate_node_parents(document: &gltf::Document) -> Vec<Option<usize>> {
+    let mut parents = vec![None; document.nodes().len()];
+    for node in document.nodes() {
+        for child in node.children() {
+            parents[child.index()] = Some(node.index());
+        }
+    }
+    parents
+}
+
+/// Scene space matrix of every node of the document
+pub fn generate_node_global_matrices(
+    document: &gltf::Document,
+    parents: &[Option<usize>],
+) -> Vec<Mat4> {
+    let local_matrices: Vec<Mat4> = document
+        .nodes()
+        .map(|node| Mat4::from_cols_array_2d(&node.transform().matrix()))
+        .collect();
+
+    let mut global_matrices: Vec<Option<Mat4>> = vec![None; local_matrices.len()];
+    for node_index in 0..local_matrices.len() {
+        // Walk up until a node with a known matrix is found, then go back down
+        let mut chain = vec![node_index];
+        let mut current = parents[node_index];
+        while let Some(parent) = current {
+            if global_matrices[parent].is_some() {
+                break;
+            }
+            chain.push(parent);
+            current = parents[parent];
+        }
+        let mut parent_matrix = match current {
+            Some(parent) => global_matrices[parent].unwrap(),
+            None => Mat4::IDENTITY,
+        };
+        for index in chain.into_iter().rev() {
+            if global_matrices[index].is_none() {
+                global_matrices[index] = Some(parent_matrix * local_matrices[index]);
+            }
+            parent_matrix = global_matrices[index].unwrap();
+        }
+    }
+    global_matrices.into_iter().map(|matrix| matrix.unwrap()).collect()
+}
+
+pub fn generate_gltf2_skin<'err>(
+    skin: &gltf::Skin,
+    document: &gltf::Document,
+    buffer_vec: &[gltf::buffer::Data],
+) -> Result<Gltf2Skin, &'err str> {
+    let nodes: Vec<gltf::Node> = document.nodes().collect();
+    let parents = generate_node_parents(document);
+    let global_matrices = generate_node_global_matrices(document, &parents);
+
+    let joints: Vec<usize> = skin.joints().map(|joint| joint.index()).collect();
+    if joints.is_empty() {
+        return Err("Skin without joints");
+    }
+    let joint_lookup: HashMap<usize, usize> = joints
+        .iter()
+        .enumerate()
+        .map(|(joint_index, node)| (*node, joint_index))
+        .collect();
+
+    // Closest ancestor that is also a joint of this skin
+    let joint_parents: Vec<Option<usize>> = joints
+        .iter()
+        .map(|node| {
+            let mut current = parents[*node];
+            while let Some(parent) = current {
+                if let Some(joint_index) = joint_lookup.get(&parent) {
+                    return Some(*joint_index);
+                }
+                current = parents[parent];
+            }
+            None
+        })
+        .collect();
+
+    let mut skeleton = Skeleton::new();
+    let root_parent = joint_parents
+        .iter()
+        .position(|parent| parent.is_none())
+        .and_then(|root_joint| parents[joints[root_joint]]);
+    if let Some(root_parent) = root_parent {
+        skeleton.transform = global_matrices[root_parent];
+    }
+
+    let mut joint_to_bone: Vec<Option<BoneIndex>> = vec![None; joints.len()];
+    let mut joint_nodes = Vec::with_capacity(joints.len());
+    while joint_nodes.len() < joints.len() {
+        let added_count = joint_nodes.len();
+        for (joint_index, node) in joints.iter().enumerate() {
+            if joint_to_bone[joint_index].is_some() {
+                continue;
+            }
+            let parent_bone = match joint_parents[joint_index] {
+                Some(parent_joint) => match joint_to_bone[parent_joint] {
+                    Some(parent_bone) => Some(parent_bone),
+                    None => continue,
+                },
+                None => None,
+            };
+
+            let direct_parent = match joint_parents[joint_index] {
+                Some(parent_joint) => Some(joints[parent_joint]),
+                None => root_parent,
+            };
+            let bind_transform = if parents[*node] == direct_parent {
+                node_local_transform(&nodes[*node])
+            } else {
+                // There are non joint nodes between the bone and its parent
+                let parent_matrix = match joint_parents[joint_index] {
+                    Some(parent_joint) => global_matrices[joints[parent_joint]],
+                    None => skeleton.transform,
+                };
+                Transform::from_mat4(parent_matrix.inverse() * global_matrices[*node])
+            };
+
+            let name = nodes[*node].name().map(String::from);
+            let bone = match skeleton.add_bone(name, parent_bone, bind_transform) {
+                Ok(bone) => bone,
+                Err(_) => return Err("Skin bone could not be added"),
+            };
+            joint_to_bone[joint_index] = Some(bone);
+            joint_nodes.push(*node);
+        }
+        if joint_nodes.len() == added_count {
+            return Err("Skin joint hierarchy has a cycle");
+        }
+    }
+    let joint_to_bone: Vec<BoneIndex> = joint_to_bone
+        .into_iter()
+        .map(|bone| bone.unwrap())
+        .collect();
+
+    // The glTF default for missing inverse bind matrices is the identity matrix
+    let reader = skin.reader(|buffer| Some(&buffer_vec[buffer.index()]));
+    let inverse_bind_matrices: Vec<Mat4> = match reader.read_inverse_bind_matrices() {
+        Some(matrix_iter) => matrix_iter
+            .map(|matrix| Mat4::from_cols_array_2d(&matrix))
+            .collect(),
+        None => vec![Mat4::IDENTITY; joints.len()],
+    };
+    if inverse_bind_matrices.len() < joints.len() {
+        return Err("Skin has fewer inverse bind matrices than joints");
+    }
+    for (joint_index, bone) in joint_to_bone.iter().enumerate() {
+        skeleton
+            .set_inverse_bind_matrix(*bone, inverse_bind_matrices[joint_index])
+            .expect("Bone was just added");
+    }
+
+    Ok(Gltf2Skin {
+        name: skin.name().map(String::from),
+        skeleton,
+        joint_nodes,
+        joint_to_bone,
+    })
+}
+
+impl Gltf2Skin {
+    pub fn bone_for_node(&self, node: usize) -> Option<BoneIndex> {
+        self.joint_nodes.iter().position(|joint_node| *joint_node == node)
+    }
+
+    /// Converts the glTF joint indices of the mesh into `BoneIndex` values of the skeleton
+    pub fn remap_joints(&self, mesh_data: &mut MeshData) {
+        if let Some(joints) = mesh_data.joints.as_mut() {
+            for joint in joints.iter_mut() {
+                for component in 0..4 {
+                    let joint_index = joint[component] as usize;
+                    if let Some(bone) = self.joint_to_bone.get(joint_index) {
+                        joint[component] = *bone as u32;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Creates a copy of the clip targeting the bones of this skin, channels animating other
+    /// nodes are dropped.
+    pub fn retarget_clip(&self, clip: &AnimationClip) -> AnimationClip {
+        let channels = clip
+            .channels
+            .iter()
+            .filter_map(|channel| match channel.target {
+                AnimationTarget::Node(node) => self.bone_for_node(node).map(|bone| {
+                    let mut channel = channel.clone();
+                    channel.target = AnimationTarget::Bone(bone);
+                    channel
+                }),
+                AnimationTarget::Bone(_) => None,
+            })
+            .collect();
+        AnimationClip {
+            name: clip.name.clone(),
+            duration: clip.duration,
+            channels,
+            events: clip.events.clone(),
+        }
+    }
+}
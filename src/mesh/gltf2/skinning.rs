@@ -0,0 +1,45 @@
+use glam::*;
+
+/// Bones that fit in the `BonePalette` uniform block, 128 matrices use 8KB of the 16KB
+/// guaranteed by WebGL2
+pub const MAX_UNIFORM_BONES: usize = 128;
+/// Texels used by every bone in the palette texture, one per matrix column
+pub const PALETTE_TEXTURE_WIDTH: u32 = 4;
+
+/// Storage used to send the bone matrices to the shader
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkinningMode {
+    /// `mat4` array in a uniform block, limited to `MAX_UNIFORM_BONES`
+    UniformBlock,
+    /// `RGBA32F` texture with a row per bone, for large rigs
+    Texture,
+}
+
+impl SkinningMode {
+    pub fn for_bone_count(bone_count: usize) -> Self {
+        if bone_count <= MAX_UNIFORM_BONES {
+            SkinningMode::UniformBlock
+        } else {
+            SkinningMode::Texture
+        }
+    }
+}
+
+/// Packs the matrices column by column.\
+/// The layout matches both a std140 `mat4` array and the rows of the palette texture.
+pub fn pack_palette_data(palette: &[Mat4]) -> Vec<f32> {
+    let mut data = Vec::with_capacity(palette.len() * 16);
+    for matrix in palette.iter() {
+        data.extend_from_slice(&matrix.to_cols_array());
+    }
+    data
+}
+
+pub fn palette_texture_size(bone_count: usize) -> UVec2 {
+    uvec2(PALETTE_TEXTURE_WIDTH, bone_count.max(1) as u32)
+}
+
+/// Joint indices as floats, WebGL attributes are read as floats by the skinning shader
+pub fn pack_joint_attributes(joints: &[UVec4]) -> Vec<Vec4> {
+    joints.iter().map(|joint| joint.as_vec4()).collect()
+}
@@ -0,0 +1,363 @@
+#[cfg(test)]
+mod tests {
+    use glam::*;
+    use rust_webgl2::*;
+
+    use super::super::super::mesh_data::*;
+    use super::super::super::morph_target::MorphTarget;
+    use super::super::generate_asset_from_gltf;
+    use super::super::shader::*;
+    use super::super::skin::normalize_weights;
+    use super::super::skinning::*;
+
+    #[test]
+    fn skinning_mode_selection() {
+        assert_eq!(SkinningMode::for_bone_count(1), SkinningMode::UniformBlock);
+        assert_eq!(
+            SkinningMode::for_bone_count(MAX_UNIFORM_BONES),
+            SkinningMode::UniformBlock
+        );
+        assert_eq!(
+            SkinningMode::for_bone_count(MAX_UNIFORM_BONES + 1),
+            SkinningMode::Texture
+        );
+    }
+
+    #[test]
+    fn palette_data_is_column_major() {
+        let palette = vec![Mat4::IDENTITY, Mat4::from_translation(vec3(1.0, 2.0, 3.0))];
+        let data = pack_palette_data(&palette);
+        assert_eq!(data.len(), 32);
+        assert_eq!(&data[0..16], &Mat4::IDENTITY.to_cols_array());
+        // Translation is stored in the fourth column, the fourth texel of the second row
+        assert_eq!(&data[28..32], &[1.0, 2.0, 3.0, 1.0]);
+    }
+
+    #[test]
+    fn palette_texture_has_a_row_per_bone() {
+        assert_eq!(palette_texture_size(200), uvec2(PALETTE_TEXTURE_WIDTH, 200));
+        assert_eq!(palette_texture_size(0), uvec2(PALETTE_TEXTURE_WIDTH, 1));
+        let palette = vec![Mat4::IDENTITY; 200];
+        let texel_count = palette_texture_size(palette.len());
+        assert_eq!(
+            pack_palette_data(&palette).len(),
+            (texel_count.x * texel_count.y * 4) as usize
+        );
+    }
+
+    #[test]
+    fn joint_attributes_are_floats() {
+        let joints = vec![uvec4(0, 1, 2, 3), uvec4(7, 0, 0, 0)];
+        assert_eq!(
+            pack_joint_attributes(&joints),
+            vec![vec4(0.0, 1.0, 2.0, 3.0), vec4(7.0, 0.0, 0.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn weights_are_normalized() {
+        let weights = normalize_weights(vec4(2.0, 1.0, 1.0, 0.0));
+        assert!(weights.abs_diff_eq(vec4(0.5, 0.25, 0.25, 0.0), 1e-6));
+        assert_eq!(normalize_weights(Vec4::ZERO), Vec4::ZERO);
+    }
+
+    fn has_attribute(shader: &ShaderSource, name: &str, layout_loc: u32) -> bool {
+        shader
+            .vertex_shader
+            .attributes
+            .iter()
+            .any(|attribute| attribute.name == name && attribute.layout_loc == layout_loc)
+    }
+
+    #[test]
+    fn skinned_shader_uniform_block() {
+        let shader = skinned_shader(SkinningMode::UniformBlock);
+        assert!(has_attribute(&shader, "att_position", 0));
+        assert!(has_attribute(&shader, "att_normal", 1));
+        assert!(has_attribute(&shader, "att_joints", 2));
+        assert!(has_attribute(&shader, "att_weights", 3));
+
+        let vertex_uniforms = &shader.vertex_shader.uniform_collection;
+        let palette_block = vertex_uniforms
+            .uniform_blocks
+            .iter()
+            .find(|block| block.name == SKINNING_UNIFORM_BLOCK_NAME)
+            .expect("Missing palette block");
+        assert_eq!(palette_block.uniforms.len(), 1);
+        assert_eq!(palette_block.uniforms[0].name, "bone_matrices");
+        assert_eq!(
+            palette_block.uniforms[0].array_length,
+            Some(MAX_UNIFORM_BONES as _)
+        );
+        assert!(vertex_uniforms
+            .uniforms
+            .iter()
+            .all(|uniform| uniform.name != "bone_texture"));
+
+        assert!(shader
+            .vertex_shader
+            .import_fn
+            .contains(&String::from("get_skin_matrix")));
+        assert!(shader
+            .imported_functions
+            .iter()
+            .any(|function| function.name == "get_skin_matrix"));
+        assert!(shader.vertex_shader.main_fn.contains("get_skin_matrix"));
+    }
+
+    #[test]
+    fn skinned_shader_texture() {
+        let shader = skinned_shader(SkinningMode::Texture);
+        let vertex_uniforms = &shader.vertex_shader.uniform_collection;
+        assert!(vertex_uniforms
+            .uniform_blocks
+            .iter()
+            .all(|block| block.name != SKINNING_UNIFORM_BLOCK_NAME));
+        assert!(vertex_uniforms
+            .uniforms
+            .iter()
+            .any(|uniform| uniform.name == "bone_texture"));
+
+        let skin_function = shader
+            .imported_functions
+            .iter()
+            .find(|function| function.name == "get_skin_matrix")
+            .unwrap();
+        match &skin_function.definition {
+            FunctionDefinitionType::InlineFn { body, .. } => {
+                assert!(body.contains("texelFetch(bone_texture"))
+            }
+            #[allow(unreachable_patterns)]
+            _ => panic!("Skin matrix function should be inline"),
+        }
+    }
+
+    #[test]
+    fn morph_shader_adds_delta_attributes() {
+        let shader = with_morph_targets(default_shader(), 2);
+        assert!(has_attribute(&shader, "att_morph_position_0", 4));
+        assert!(has_attribute(&shader, "att_morph_position_1", 5));
+        assert!(has_attribute(&shader, "att_morph_normal_0", 8));
+        assert!(has_attribute(&shader, "att_morph_normal_1", 9));
+        assert!(!has_attribute(&shader, "att_morph_position_2", 6));
+        assert!(shader
+            .vertex_shader
+            .uniform_collection
+            .uniforms
+            .iter()
+            .any(|uniform| uniform.name == "morph_weights"));
+
+        let main_fn = &shader.vertex_shader.main_fn;
+        assert!(main_fn.contains("morph_weights.y * att_morph_position_1"));
+        assert!(main_fn.contains("vec4(morphed_position, 1.0)"));
+        assert!(main_fn.contains("vec4(morphed_normal, 0.0)"));
+
+        let clamped = with_morph_targets(skinned_shader(SkinningMode::UniformBlock), 10);
+        assert!(has_attribute(&clamped, "att_morph_normal_3", 11));
+        assert!(has_attribute(&clamped, "att_joints", 2));
+        assert!(clamped
+            .vertex_shader
+            .main_fn
+            .contains("vec4(morphed_position, 1.0)"));
+    }
+
+    #[test]
+    fn skinned_morph_shader_locations_do_not_collide() {
+        for mode in [SkinningMode::UniformBlock, SkinningMode::Texture] {
+            let shader = with_morph_targets(skinned_shader(mode), MAX_SHADER_MORPH_TARGETS);
+            let mut locations: Vec<u32> = shader
+                .vertex_shader
+                .attributes
+                .iter()
+                .map(|attribute| attribute.layout_loc)
+                .collect();
+            let attribute_count = locations.len();
+            locations.sort();
+            locations.dedup();
+            assert_eq!(locations.len(), attribute_count);
+            // WebGL2 guarantees 16 attributes
+            assert!(locations.iter().all(|location| *location < 16));
+            assert!(!locations.contains(&TANGENT_LOCATION));
+            assert!(!locations.contains(&UV_LOCATION));
+            assert!(has_attribute(&shader, "att_joints", JOINTS_LOCATION));
+            assert!(has_attribute(&shader, "att_morph_normal_3", 11));
+
+            let main_fn = &shader.vertex_shader.main_fn;
+            assert!(main_fn.contains("get_skin_matrix(att_joints, att_weights)"));
+            assert!(main_fn.contains("vec4(morphed_position, 1.0)"));
+        }
+    }
+
+    #[test]
+    fn morph_deltas_are_interleaved_at_shader_locations() {
+        let mut smile = MorphTarget::new(vec![Vec3::Y, vec3(0.0, 2.0, 0.0)]);
+        smile.normal_deltas = Some(vec![Vec3::X, Vec3::NEG_X]);
+        let blink = MorphTarget::new(vec![Vec3::Z, Vec3::NEG_Z]);
+        let mesh = MeshData {
+            positions: vec![Vec3::ZERO, Vec3::ONE],
+            normals: Some(vec![Vec3::Y; 2]),
+            tangents: None,
+            uvs: Some(vec![vec![Vec2::ZERO, Vec2::ONE]]),
+            colors: None,
+            joints: None,
+            weights: None,
+            morph_targets: Some(vec![smile, blink]),
+            indices: None,
+        };
+
+        let locations = gltf2_attribute_locations(&mesh, MAX_SHADER_MORPH_TARGETS);
+        let vertex_data = mesh.interleaved_vertex_data(&locations).unwrap();
+        let layout: Vec<(u32, u32)> = vertex_data
+            .attributes
+            .iter()
+            .map(|(location, offset, _)| (*location, *offset))
+            .collect();
+        assert_eq!(
+            layout,
+            vec![
+                (POSITION_LOCATION, 0),
+                (NORMAL_LOCATION, 3),
+                (UV_LOCATION, 6),
+                (morph_position_location(0), 8),
+                (morph_position_location(1), 11),
+                (morph_normal_location(0), 14),
+                (morph_normal_location(1), 17),
+            ]
+        );
+        assert_eq!(vertex_data.stride, 20);
+        assert_eq!(
+            &vertex_data.data[20..],
+            &[
+                1.0, 1.0, 1.0, 0.0, 1.0, 0.0, 1.0, 1.0, //
+                0.0, 2.0, 0.0, 0.0, 0.0, -1.0, //
+                -1.0, 0.0, 0.0, 0.0, 0.0, 0.0,
+            ]
+        );
+
+        // Same locations as the attributes of the morph shader
+        let shader = with_morph_targets(default_shader(), 2);
+        assert!(has_attribute(
+            &shader,
+            "att_morph_position_1",
+            morph_position_location(1)
+        ));
+        assert!(has_attribute(
+            &shader,
+            "att_morph_normal_0",
+            morph_normal_location(0)
+        ));
+
+        let missing = AttributeLocations {
+            morph_positions: Some(vec![(2, morph_position_location(2))]),
+            ..gltf2_attribute_locations(&mesh, 0)
+        };
+        assert!(mesh.interleaved_vertex_data(&missing).is_err());
+    }
+
+    /// Binary glTF with a single buffer
+    fn create_glb(json: &str, bin: &[u8]) -> Vec<u8> {
+        let mut json = json.as_bytes().to_vec();
+        json.resize(json.len().div_ceil(4) * 4, b' ');
+        let mut bin = bin.to_vec();
+        bin.resize(bin.len().div_ceil(4) * 4, 0);
+        let length = 12 + 8 + json.len() + 8 + bin.len();
+        let mut glb = Vec::with_capacity(length);
+        glb.extend_from_slice(b"glTF");
+        glb.extend_from_slice(&2u32.to_le_bytes());
+        glb.extend_from_slice(&(length as u32).to_le_bytes());
+        glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"JSON");
+        glb.extend_from_slice(&json);
+        glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+        glb.extend_from_slice(b"BIN\0");
+        glb.extend_from_slice(&bin);
+        glb
+    }
+
+    /// Two quads sharing an edge with mirrored UVs, drawn with `mode`
+    fn create_mirrored_quads_glb(mode: u32, indices: &[u16]) -> Vec<u8> {
+        let positions = [
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(2.0, 0.0, 0.0),
+            vec3(2.0, 1.0, 0.0),
+        ];
+        let normals = [Vec3::Z; 6];
+        let uvs = [
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(1.0, 1.0),
+            vec2(0.0, 1.0),
+            vec2(0.0, 0.0),
+            vec2(0.0, 1.0),
+        ];
+        let mut bin = Vec::new();
+        bin.extend_from_slice(bytemuck::cast_slice(&positions));
+        bin.extend_from_slice(bytemuck::cast_slice(&normals));
+        bin.extend_from_slice(bytemuck::cast_slice(&uvs));
+        bin.extend_from_slice(bytemuck::cast_slice(indices));
+        let json = format!(
+            r#"{{
+                "asset": {{"version": "2.0"}},
+                "buffers": [{{"byteLength": {buffer_length}}}],
+                "bufferViews": [
+                    {{"buffer": 0, "byteOffset": 0, "byteLength": 72}},
+                    {{"buffer": 0, "byteOffset": 72, "byteLength": 72}},
+                    {{"buffer": 0, "byteOffset": 144, "byteLength": 48}},
+                    {{"buffer": 0, "byteOffset": 192, "byteLength": {index_length}}}
+                ],
+                "accessors": [
+                    {{"bufferView": 0, "componentType": 5126, "count": 6, "type": "VEC3",
+                      "min": [0, 0, 0], "max": [2, 1, 0]}},
+                    {{"bufferView": 1, "componentType": 5126, "count": 6, "type": "VEC3"}},
+                    {{"bufferView": 2, "componentType": 5126, "count": 6, "type": "VEC2"}},
+                    {{"bufferView": 3, "componentType": 5123, "count": {index_count},
+                      "type": "SCALAR"}}
+                ],
+                "meshes": [{{"primitives": [{{
+                    "attributes": {{"POSITION": 0, "NORMAL": 1, "TEXCOORD_0": 2}},
+                    "indices": 3,
+                    "mode": {mode}
+                }}]}}]
+            }}"#,
+            buffer_length = bin.len(),
+            index_length = indices.len() * 2,
+            index_count = indices.len(),
+            mode = mode,
+        );
+        create_glb(&json, &bin)
+    }
+
+    #[test]
+    fn only_triangle_lists_are_processed() {
+        let indices = [0, 1, 2, 0, 2, 3, 1, 4, 5, 1, 5, 2];
+        const TRIANGLES: u32 = 4;
+        let asset =
+            generate_asset_from_gltf(&create_mirrored_quads_glb(TRIANGLES, &indices)).unwrap();
+        let vertex_data = &asset.meshes[0].vertex_data;
+        assert!(vertex_data.tangents.is_some());
+        assert_eq!(
+            vertex_data.vertex_count(),
+            8,
+            "The mirrored UV seam is split for the tangents"
+        );
+
+        const LINES: u32 = 1;
+        const TRIANGLE_STRIP: u32 = 5;
+        for mode in [LINES, TRIANGLE_STRIP] {
+            let asset =
+                generate_asset_from_gltf(&create_mirrored_quads_glb(mode, &indices)).unwrap();
+            let vertex_data = &asset.meshes[0].vertex_data;
+            assert!(
+                matches!(&vertex_data.indices, Some(IndexData::U16(data)) if data[..] == indices)
+            );
+            assert_eq!(vertex_data.vertex_count(), 6);
+            assert!(
+                vertex_data.tangents.is_none(),
+                "Tangents need triangle lists"
+            );
+        }
+    }
+}
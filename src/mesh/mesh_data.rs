@@ -1,8 +1,12 @@
+use std::collections::HashMap;
 use std::rc::Rc;
 
 use glam::*;
 use rust_webgl2::*;
 
+use super::morph_target::{blend_morph_normals, blend_morph_positions, MorphTarget};
+use super::tangents::generate_tangents;
+
 macro_rules! get_triangle_from_indices {
     ($indices: ident, $array: ident) => {
         [
@@ -164,40 +168,190 @@ impl IndexData {
     pub fn get_iter_triangle_fan(&self)->IndexDataIter{
         IndexDataIter::new(self, TriangleIteratorKind::TriangleFan)
     }
+
+    /// Stores the indices in the smallest type that can hold them
+    pub fn from_indices(indices: &[usize]) -> Self {
+        let max_index = indices.iter().copied().max().unwrap_or(0);
+        if max_index <= u8::MAX as usize {
+            IndexData::U8(indices.iter().map(|index| *index as u8).collect())
+        } else if max_index <= u16::MAX as usize {
+            IndexData::U16(indices.iter().map(|index| *index as u16).collect())
+        } else {
+            IndexData::U32(indices.iter().map(|index| *index as u32).collect())
+        }
+    }
+
+    pub fn to_indices(&self) -> Vec<usize> {
+        match self {
+            IndexData::U8(indices) => indices.iter().map(|index| *index as usize).collect(),
+            IndexData::U16(indices) => indices.iter().map(|index| *index as usize).collect(),
+            IndexData::U32(indices) => indices.iter().map(|index| *index as usize).collect(),
+        }
+    }
+
+    /// Converts to the smallest type that can hold every index, e.g. `U32` indices of a mesh
+    /// with less than 65536 vertices become `U16`
+    pub fn narrow(&mut self) {
+        *self = Self::from_indices(&self.to_indices());
+    }
 }
 
 pub struct MeshData {
     pub positions: Vec<Vec3>,
     pub normals: Option<Vec<Vec3>>,
+    /// Tangent in `xyz` and handedness in `w`, `bitangent = cross(normal, tangent.xyz) * w`
+    pub tangents: Option<Vec<Vec4>>,
     pub uvs: Option<Vec<Vec<Vec2>>>,
     pub colors: Option<Vec<Vec<RGBA>>>,
+    /// Indices of the four bones influencing each vertex, see `animation::BoneIndex`
+    pub joints: Option<Vec<UVec4>>,
+    /// Influence of each of the `joints` of the vertex, normalized to add up to one
+    pub weights: Option<Vec<Vec4>>,
+    pub morph_targets: Option<Vec<MorphTarget>>,
     pub indices: Option<IndexData>,
 }
 
 pub struct AttributeLocations {
     pub position_loc: u32,
     pub normals_loc: Option<u32>,
+    pub tangents_loc: Option<u32>,
     pub uvs: Option<Vec<(usize, u32)>>,
+    pub joints_loc: Option<u32>,
+    pub weights_loc: Option<u32>,
+    /// Morph target index and location of its position deltas
+    pub morph_positions: Option<Vec<(usize, u32)>>,
+    /// Morph target index and location of its normal deltas, zero for targets without them
+    pub morph_normals: Option<Vec<(usize, u32)>>,
+}
+
+/// Vertex attributes packed in a single buffer by `MeshData::interleaved_vertex_data`
+pub struct InterleavedVertexData {
+    pub data: Vec<f32>,
+    /// Floats per vertex
+    pub stride: u32,
+    /// Location, offset in floats and size of each attribute
+    pub attributes: Vec<(u32, u32, AttributeSize)>,
 }
 
 pub struct MeshBuffers {
     pub vertex_buffers: Vec<Rc<GlBuffer>>,
+    /// Attribute location of each of the `vertex_buffers`
+    pub vertex_buffer_locations: Vec<u32>,
     pub index_buffer: Option<Rc<GlIndexBuffer>>,
     pub index_count: Option<u32>,
 }
 
 impl MeshData {
+    /// Positions with the morph targets applied on the CPU
+    pub fn morphed_positions(&self, weights: &[f32]) -> Vec<Vec3> {
+        match &self.morph_targets {
+            Some(targets) => blend_morph_positions(&self.positions, targets, weights),
+            None => self.positions.clone(),
+        }
+    }
+
+    pub fn morphed_normals(&self, weights: &[f32]) -> Option<Vec<Vec3>> {
+        let normals = self.normals.as_ref()?;
+        match &self.morph_targets {
+            Some(targets) => Some(blend_morph_normals(normals, targets, weights)),
+            None => Some(normals.clone()),
+        }
+    }
+
+    /// Replaces the tangents with MikkTSpace tangents computed from the normals and the
+    /// `uv_set` texture coordinates, see `generate_tangents`.\
+    /// Vertices whose corners get different tangents, e.g. on both sides of mirrored UVs, are
+    /// split so the vertex count can grow. The indices are read as a triangle list.
+    pub fn generate_tangents(&mut self, uv_set: usize) -> Result<(), String> {
+        let normals = match &self.normals {
+            Some(normals) => normals,
+            None => return Err("Tangents require normals".into()),
+        };
+        let uvs = match self.uvs.as_ref().and_then(|uvs| uvs.get(uv_set)) {
+            Some(uvs) => uvs,
+            None => return Err(format!("Tangents require the uv set {}", uv_set)),
+        };
+        let triangles = self.triangles();
+        let corner_tangents = generate_tangents(&self.positions, normals, uvs, &triangles);
+
+        // Each vertex keeps the tangent of its first corner, other tangents get a copy of it
+        let vertex_count = self.positions.len();
+        let mut sources: Vec<usize> = (0..vertex_count).collect();
+        let mut tangents = vec![vec4(1.0, 0.0, 0.0, -1.0); vertex_count];
+        let mut assigned = vec![false; vertex_count];
+        let mut split_vertices: HashMap<(usize, [u32; 4]), usize> = HashMap::new();
+        let new_triangles: Vec<[usize; 3]> = triangles
+            .iter()
+            .zip(corner_tangents.iter())
+            .map(|(triangle, corner_tangents)| {
+                std::array::from_fn(|corner| {
+                    let (index, tangent) = (triangle[corner], corner_tangents[corner]);
+                    if index >= vertex_count {
+                        index
+                    } else if !assigned[index] {
+                        assigned[index] = true;
+                        tangents[index] = tangent;
+                        index
+                    } else if tangents[index] == tangent {
+                        index
+                    } else {
+                        *split_vertices
+                            .entry((index, tangent.to_array().map(f32::to_bits)))
+                            .or_insert_with(|| {
+                                sources.push(index);
+                                tangents.push(tangent);
+                                sources.len() - 1
+                            })
+                    }
+                })
+            })
+            .collect();
+
+        if sources.len() > vertex_count {
+            self.remap_vertices(&sources);
+            self.set_triangles(&new_triangles);
+        }
+        self.tangents = Some(tangents);
+        Ok(())
+    }
+
     pub fn get_index_count(&self) -> usize {
         match &self.indices {
             Some(index_data) => index_data.index_count(),
             None => 0,
         }
     }
-    pub fn generate_interleaved_vertex_array_object(
+    fn morph_deltas(
         &self,
-        graphics: &Graphics,
-        attribute_locations: AttributeLocations,
-    ) -> Result<GlVertexArrayObject, String> {
+        locations: &Option<Vec<(usize, u32)>>,
+        normals: bool,
+    ) -> Result<Vec<Vec<Vec3>>, String> {
+        let vertex_count = self.positions.len();
+        let mut deltas = Vec::new();
+        for (target_index, _) in locations.iter().flatten() {
+            let target = match self.morph_targets.as_ref().and_then(|targets| targets.get(*target_index)) {
+                Some(target) => target,
+                None => return Err(format!("Mesh has no morph target {}", target_index)),
+            };
+            let target_deltas = match (normals, &target.normal_deltas) {
+                (false, _) => target.position_deltas.clone(),
+                (true, Some(normal_deltas)) => normal_deltas.clone(),
+                (true, None) => vec![Vec3::ZERO; vertex_count],
+            };
+            if target_deltas.len() != vertex_count {
+                return Err(format!("Morph target {} does not have the correct count", target_index));
+            }
+            deltas.push(target_deltas);
+        }
+        Ok(deltas)
+    }
+
+    /// Packs the attributes with a location in one buffer, in the order of the fields of
+    /// `AttributeLocations`
+    pub fn interleaved_vertex_data(
+        &self,
+        attribute_locations: &AttributeLocations,
+    ) -> Result<InterleavedVertexData, String> {
         let mut stride = 0;
         let mut interleave_data: Vec<(u32, u32, AttributeSize)> = Vec::new();
 
@@ -212,33 +366,78 @@ impl MeshData {
         if let Some(normal_loc) = attribute_locations.normals_loc {
             interleave_data.push((normal_loc, stride, AttributeSize::THREE));
             stride += 3;
-            if vertex_count != self.normals.as_ref().unwrap().len() {
-                return Err("Normal does not have the correct count".into());
+            match &self.normals {
+                Some(normals) if normals.len() == vertex_count => {}
+                Some(_) => return Err("Normal does not have the correct count".into()),
+                None => return Err("Mesh has no normals".into()),
+            }
+        }
+        if let Some(tangents_loc) = attribute_locations.tangents_loc {
+            interleave_data.push((tangents_loc, stride, AttributeSize::FOUR));
+            stride += 4;
+            match &self.tangents {
+                Some(tangents) if tangents.len() == vertex_count => {}
+                Some(_) => return Err("Tangents does not have the correct count".into()),
+                None => return Err("Mesh has no tangents".into()),
             }
         }
         if let Some(uv_locs) = &attribute_locations.uvs {
             for (uv_index, uv_loc) in uv_locs {
                 interleave_data.push((*uv_loc, stride, AttributeSize::TWO));
                 stride += 2;
-                if vertex_count != self.uvs.as_ref().unwrap()[*uv_index].len() {
-                    return Err(format!("Uv does not have the correct count {}", *uv_index));
+                match self.uvs.as_ref().and_then(|uvs| uvs.get(*uv_index)) {
+                    Some(uvs) if uvs.len() == vertex_count => {}
+                    _ => return Err(format!("Uv does not have the correct count {}", *uv_index)),
                 }
             }
         }
-        let mut interleaved_vertex_buffer = Vec::new();
+        if let Some(joints_loc) = attribute_locations.joints_loc {
+            interleave_data.push((joints_loc, stride, AttributeSize::FOUR));
+            stride += 4;
+            match &self.joints {
+                Some(joints) if joints.len() == vertex_count => {}
+                _ => return Err("Joints does not have the correct count".into()),
+            }
+        }
+        if let Some(weights_loc) = attribute_locations.weights_loc {
+            interleave_data.push((weights_loc, stride, AttributeSize::FOUR));
+            stride += 4;
+            match &self.weights {
+                Some(weights) if weights.len() == vertex_count => {}
+                _ => return Err("Weights does not have the correct count".into()),
+            }
+        }
+        let morph_positions = self.morph_deltas(&attribute_locations.morph_positions, false)?;
+        let morph_normals = self.morph_deltas(&attribute_locations.morph_normals, true)?;
+        for (_, morph_loc) in attribute_locations
+            .morph_positions
+            .iter()
+            .chain(attribute_locations.morph_normals.iter())
+            .flatten()
+        {
+            interleave_data.push((*morph_loc, stride, AttributeSize::THREE));
+            stride += 3;
+        }
+
+        let mut interleaved_vertex_buffer = Vec::with_capacity(vertex_count * stride as usize);
         for index in 0..vertex_count {
             let position = self.positions[index];
             interleaved_vertex_buffer.push(position.x);
             interleaved_vertex_buffer.push(position.y);
             interleaved_vertex_buffer.push(position.z);
 
-            if let Some(_) = attribute_locations.normals_loc {
+            if attribute_locations.normals_loc.is_some() {
                 let normal = self.normals.as_ref().unwrap()[index];
                 interleaved_vertex_buffer.push(normal.x);
                 interleaved_vertex_buffer.push(normal.y);
                 interleaved_vertex_buffer.push(normal.z);
             }
 
+            if attribute_locations.tangents_loc.is_some() {
+                let tangent = self.tangents.as_ref().unwrap()[index];
+                interleaved_vertex_buffer.extend_from_slice(&tangent.to_array());
+            }
+
             if let Some(uv_locs) = &attribute_locations.uvs {
                 for (uv_index, _) in uv_locs {
                     let uv = self.uvs.as_ref().unwrap()[*uv_index][index];
@@ -246,8 +445,46 @@ impl MeshData {
                     interleaved_vertex_buffer.push(uv.y);
                 }
             }
+
+            if attribute_locations.joints_loc.is_some() {
+                let joint = self.joints.as_ref().unwrap()[index].as_vec4();
+                interleaved_vertex_buffer.extend_from_slice(&joint.to_array());
+            }
+
+            if attribute_locations.weights_loc.is_some() {
+                let weight = self.weights.as_ref().unwrap()[index];
+                interleaved_vertex_buffer.extend_from_slice(&weight.to_array());
+            }
+
+            for deltas in morph_positions.iter().chain(morph_normals.iter()) {
+                interleaved_vertex_buffer.extend_from_slice(&deltas[index].to_array());
+            }
         }
 
+        Ok(InterleavedVertexData {
+            data: interleaved_vertex_buffer,
+            stride,
+            attributes: interleave_data,
+        })
+    }
+
+    pub fn generate_interleaved_vertex_array_object(
+        &self,
+        graphics: &Graphics,
+        attribute_locations: AttributeLocations,
+    ) -> Result<GlVertexArrayObject, String> {
+        let InterleavedVertexData {
+            data: interleaved_vertex_buffer,
+            stride,
+            attributes: interleave_data,
+        } = self.interleaved_vertex_data(&attribute_locations)?;
+
+        // WebGL limits the stride to 255 bytes
+        let stride = match u8::try_from(stride * std::mem::size_of::<f32>() as u32) {
+            Ok(stride) => stride,
+            Err(_) => return Err("Too many attributes for an interleaved buffer".into()),
+        };
+
         let vertex_buffer = GlBuffer::array_buffer_with_data(
             graphics,
             &interleaved_vertex_buffer,
@@ -271,7 +508,6 @@ impl MeshData {
 
         let mut attribute_descriptions = Vec::new();
         for (loc, offset, size) in interleave_data {
-            let stride = stride as u8 * std::mem::size_of::<f32>() as u8;
             let offset = offset * std::mem::size_of::<f32>() as u32;
             attribute_descriptions.push(AttributeDescription {
                 location: loc,
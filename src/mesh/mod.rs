@@ -1,3 +1,8 @@
 pub mod gltf2;
 pub mod mesh_data;
-pub mod cube;
\ No newline at end of file
+pub mod morph_target;
+pub mod tangents;
+pub mod shading;
+pub mod optimize;
+pub mod cube;
+mod test;
\ No newline at end of file
@@ -0,0 +1,52 @@
+use glam::*;
+
+/// Per vertex offsets of a blend shape, added to the base mesh scaled by the target weight
+#[derive(Debug, Clone, PartialEq)]
+pub struct MorphTarget {
+    pub name: Option<String>,
+    pub position_deltas: Vec<Vec3>,
+    pub normal_deltas: Option<Vec<Vec3>>,
+}
+
+impl MorphTarget {
+    pub fn new(position_deltas: Vec<Vec3>) -> Self {
+        Self {
+            name: None,
+            position_deltas,
+            normal_deltas: None,
+        }
+    }
+}
+
+/// Adds the deltas of every target scaled by its weight, missing weights count as zero
+pub fn blend_morph_positions(base: &[Vec3], targets: &[MorphTarget], weights: &[f32]) -> Vec<Vec3> {
+    let mut positions = base.to_vec();
+    for (target, weight) in targets.iter().zip(weights.iter()) {
+        if *weight == 0.0 {
+            continue;
+        }
+        for (position, delta) in positions.iter_mut().zip(target.position_deltas.iter()) {
+            *position += *delta * *weight;
+        }
+    }
+    positions
+}
+
+/// Same as `blend_morph_positions` for normals, the results are normalized
+pub fn blend_morph_normals(base: &[Vec3], targets: &[MorphTarget], weights: &[f32]) -> Vec<Vec3> {
+    let mut normals = base.to_vec();
+    for (target, weight) in targets.iter().zip(weights.iter()) {
+        if *weight == 0.0 {
+            continue;
+        }
+        if let Some(normal_deltas) = &target.normal_deltas {
+            for (normal, delta) in normals.iter_mut().zip(normal_deltas.iter()) {
+                *normal += *delta * *weight;
+            }
+        }
+    }
+    for normal in normals.iter_mut() {
+        *normal = normal.normalize_or_zero();
+    }
+    normals
+}
@@ -0,0 +1,353 @@
+use glam::*;
+
+use super::mesh_data::{IndexData, MeshData};
+use super::shading::triangle_area_normal;
+
+/// Cache size the triangle order is tuned for, recent GPUs reuse at least this many vertices
+pub const VERTEX_CACHE_SIZE: usize = 32;
+
+/// Vertex cache misses `MeshData::optimize` trades for a better overdraw, 1.05 allows 5% more
+pub const OVERDRAW_THRESHOLD: f32 = 1.05;
+
+// Scoring parameters of Tom Forsyth's "Linear-Speed Vertex Cache Optimisation"
+const CACHE_DECAY_POWER: f32 = 1.5;
+const LAST_TRIANGLE_SCORE: f32 = 0.75;
+const VALENCE_BOOST_SCALE: f32 = 2.0;
+const VALENCE_BOOST_POWER: f32 = 0.5;
+
+/// Result of running the triangles through a simulated FIFO post transform cache
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VertexCacheStats {
+    pub triangle_count: usize,
+    /// Cache misses, each one runs the vertex shader
+    pub vertices_transformed: usize,
+    /// Average cache miss ratio, transformed vertices per triangle. 3 is the worst case and
+    /// regular grids reach around 0.6
+    pub acmr: f32,
+    /// Average transformed to vertex ratio, 1 means every vertex is transformed only once
+    pub atvr: f32,
+}
+
+/// Simulated FIFO post transform cache
+struct FifoCache {
+    /// Miss count when the vertex entered the cache, it leaves after `size` misses
+    entries: Vec<Option<usize>>,
+    time: usize,
+    size: usize,
+}
+
+impl FifoCache {
+    fn new(vertex_count: usize, size: usize) -> Self {
+        Self {
+            entries: vec![None; vertex_count],
+            time: 0,
+            size,
+        }
+    }
+
+    /// Returns how many vertices of the triangle were transformed
+    fn add_triangle(&mut self, triangle: &[usize; 3]) -> usize {
+        let mut misses = 0;
+        for index in triangle {
+            let cached = self.entries[*index].is_some_and(|entry| self.time - entry < self.size);
+            if !cached {
+                self.entries[*index] = Some(self.time);
+                self.time += 1;
+                misses += 1;
+            }
+        }
+        misses
+    }
+
+    fn clear(&mut self) {
+        self.time += self.size;
+    }
+}
+
+pub fn vertex_cache_stats(triangles: &[[usize; 3]], cache_size: usize) -> VertexCacheStats {
+    let vertex_count = triangles.iter().flatten().max().map_or(0, |max| max + 1);
+    let mut cache = FifoCache::new(vertex_count, cache_size);
+    let vertices_transformed: usize = triangles
+        .iter()
+        .map(|triangle| cache.add_triangle(triangle))
+        .sum();
+    let used_vertices = cache.entries.iter().filter(|entry| entry.is_some()).count();
+    VertexCacheStats {
+        triangle_count: triangles.len(),
+        vertices_transformed,
+        acmr: vertices_transformed as f32 / triangles.len().max(1) as f32,
+        atvr: vertices_transformed as f32 / used_vertices.max(1) as f32,
+    }
+}
+
+fn forsyth_vertex_score(cache_position: Option<usize>, remaining_triangles: usize) -> f32 {
+    if remaining_triangles == 0 {
+        return -1.0;
+    }
+    let cache_score = match cache_position {
+        // The last triangle is penalized so the order does not go back and forth between
+        // two strips
+        Some(position) if position < 3 => LAST_TRIANGLE_SCORE,
+        Some(position) => {
+            let scaler = 1.0 / (VERTEX_CACHE_SIZE - 3) as f32;
+            (1.0 - (position - 3) as f32 * scaler).powf(CACHE_DECAY_POWER)
+        }
+        None => 0.0,
+    };
+    // Vertices with few triangles left are finished first, so they can leave the cache
+    let valence_boost =
+        VALENCE_BOOST_SCALE * (remaining_triangles as f32).powf(-VALENCE_BOOST_POWER);
+    cache_score + valence_boost
+}
+
+/// Reorders the triangles so consecutive triangles share vertices, following Tom Forsyth's
+/// "Linear-Speed Vertex Cache Optimisation" with a simulated LRU cache of `VERTEX_CACHE_SIZE`.
+/// The winding of each triangle is kept.
+pub fn optimize_vertex_cache(triangles: &[[usize; 3]]) -> Vec<[usize; 3]> {
+    let vertex_count = triangles.iter().flatten().max().map_or(0, |max| max + 1);
+
+    let mut remaining_triangles = vec![0; vertex_count];
+    for index in triangles.iter().flatten() {
+        remaining_triangles[*index] += 1;
+    }
+    // Triangles of each vertex, `vertex_triangles[offsets[vertex]..offsets[vertex + 1]]`
+    let mut offsets = vec![0; vertex_count + 1];
+    for vertex in 0..vertex_count {
+        offsets[vertex + 1] = offsets[vertex] + remaining_triangles[vertex];
+    }
+    let mut vertex_triangles = vec![0; offsets[vertex_count]];
+    let mut filled = offsets.clone();
+    for (triangle, indices) in triangles.iter().enumerate() {
+        for index in indices {
+            vertex_triangles[filled[*index]] = triangle;
+            filled[*index] += 1;
+        }
+    }
+
+    let mut cache_positions: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut vertex_scores: Vec<f32> = remaining_triangles
+        .iter()
+        .map(|remaining| forsyth_vertex_score(None, *remaining))
+        .collect();
+    let mut triangle_scores: Vec<f32> = triangles
+        .iter()
+        .map(|indices| indices.iter().map(|index| vertex_scores[*index]).sum())
+        .collect();
+    let mut emitted = vec![false; triangles.len()];
+    let mut cache: Vec<usize> = Vec::with_capacity(VERTEX_CACHE_SIZE + 3);
+    let mut result = Vec::with_capacity(triangles.len());
+
+    let mut next_triangle = triangle_scores
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map(|(triangle, _)| triangle);
+    // Start of the search when no triangle of the cache is left
+    let mut first_pending = 0;
+
+    while let Some(triangle) = next_triangle {
+        emitted[triangle] = true;
+        result.push(triangles[triangle]);
+        let indices = triangles[triangle];
+        for index in indices {
+            remaining_triangles[index] -= 1;
+        }
+
+        // Vertices of the triangle move to the front, the ones pushed out leave the cache
+        let mut new_cache: Vec<usize> = Vec::with_capacity(VERTEX_CACHE_SIZE + 3);
+        for index in indices.iter().chain(cache.iter()) {
+            if !new_cache.contains(index) {
+                new_cache.push(*index);
+            }
+        }
+        for (position, index) in new_cache.iter().enumerate() {
+            cache_positions[*index] = (position < VERTEX_CACHE_SIZE).then_some(position);
+            vertex_scores[*index] =
+                forsyth_vertex_score(cache_positions[*index], remaining_triangles[*index]);
+        }
+        for index in new_cache.iter() {
+            for other in &vertex_triangles[offsets[*index]..offsets[*index + 1]] {
+                if !emitted[*other] {
+                    triangle_scores[*other] = triangles[*other]
+                        .iter()
+                        .map(|index| vertex_scores[*index])
+                        .sum();
+                }
+            }
+        }
+        new_cache.truncate(VERTEX_CACHE_SIZE);
+        cache = new_cache;
+
+        next_triangle = None;
+        let mut best_score = f32::MIN;
+        for index in cache.iter() {
+            for other in &vertex_triangles[offsets[*index]..offsets[*index + 1]] {
+                if !emitted[*other] && triangle_scores[*other] > best_score {
+                    best_score = triangle_scores[*other];
+                    next_triangle = Some(*other);
+                }
+            }
+        }
+        if next_triangle.is_none() {
+            while first_pending < triangles.len() && emitted[first_pending] {
+                first_pending += 1;
+            }
+            next_triangle = (first_pending < triangles.len()).then_some(first_pending);
+        }
+    }
+    result
+}
+
+/// Splits the triangles in clusters of consecutive triangles, a new cluster starts where the
+/// cache is restarted, and where the current cluster already reached an ACMR within
+/// `threshold` times the ACMR of the whole run
+fn overdraw_clusters(triangles: &[[usize; 3]], vertex_count: usize, threshold: f32) -> Vec<usize> {
+    let mut cache = FifoCache::new(vertex_count, VERTEX_CACHE_SIZE);
+    let mut hard_boundaries: Vec<usize> = triangles
+        .iter()
+        .enumerate()
+        .filter(|(_, triangle)| cache.add_triangle(triangle) == 3)
+        .map(|(triangle, _)| triangle)
+        .collect();
+    if hard_boundaries.first() != Some(&0) {
+        hard_boundaries.insert(0, 0);
+    }
+    hard_boundaries.push(triangles.len());
+
+    let mut clusters = Vec::new();
+    for run in hard_boundaries.windows(2) {
+        let (start, end) = (run[0], run[1]);
+        cache.clear();
+        let run_misses: usize = triangles[start..end]
+            .iter()
+            .map(|triangle| cache.add_triangle(triangle))
+            .sum();
+        let max_acmr = threshold * run_misses as f32 / (end - start) as f32;
+
+        cache.clear();
+        clusters.push(start);
+        let mut cluster_start = start;
+        let mut cluster_misses = 0;
+        for (next, triangle) in (start + 1..).zip(triangles[start..end].iter()) {
+            cluster_misses += cache.add_triangle(triangle);
+            let cluster_acmr = cluster_misses as f32 / (next - cluster_start) as f32;
+            if next < end && cluster_acmr <= max_acmr {
+                cluster_start = next;
+                cluster_misses = 0;
+                cache.clear();
+                clusters.push(cluster_start);
+            }
+        }
+    }
+    clusters
+}
+
+/// Reorders the clusters of an already cache optimized triangle list so the ones on the
+/// outside, facing away from the center of the mesh, are drawn first and hide the rest from
+/// the fragment shader, following the cluster sorting of meshoptimizer and "Fast
+/// Triangle Reordering for Vertex Locality and Reduced Overdraw" (Sander et al.).\
+/// `threshold` is how much the ACMR is allowed to grow to get smaller clusters, see
+/// `OVERDRAW_THRESHOLD`.
+pub fn optimize_overdraw(
+    triangles: &[[usize; 3]],
+    positions: &[Vec3],
+    threshold: f32,
+) -> Vec<[usize; 3]> {
+    if triangles.is_empty() {
+        return Vec::new();
+    }
+    let mut clusters = overdraw_clusters(triangles, positions.len(), threshold);
+    clusters.push(triangles.len());
+
+    // Area normal and center of each triangle
+    let faces: Vec<(Vec3, Vec3)> = triangles
+        .iter()
+        .map(|triangle| {
+            let [a, b, c] = triangle.map(|index| positions[index]);
+            (triangle_area_normal(a, b, c), (a + b + c) / 3.0)
+        })
+        .collect();
+    let total_area: f32 = faces.iter().map(|(normal, _)| normal.length()).sum();
+    let mesh_center = if total_area > 0.0 {
+        faces
+            .iter()
+            .map(|(normal, center)| *center * normal.length())
+            .sum::<Vec3>()
+            / total_area
+    } else {
+        faces.iter().map(|(_, center)| *center).sum::<Vec3>() / triangles.len() as f32
+    };
+
+    let mut sorted: Vec<(f32, std::ops::Range<usize>)> = clusters
+        .windows(2)
+        .map(|cluster| {
+            let range = cluster[0]..cluster[1];
+            let mut normal = Vec3::ZERO;
+            let mut center = Vec3::ZERO;
+            let mut area = 0.0;
+            for (triangle_normal, triangle_center) in &faces[range.clone()] {
+                normal += *triangle_normal;
+                center += *triangle_center * triangle_normal.length();
+                area += triangle_normal.length();
+            }
+            let center = if area > 0.0 {
+                center / area
+            } else {
+                mesh_center
+            };
+            let score = (center - mesh_center).dot(normal.normalize_or_zero());
+            (score, range)
+        })
+        .collect();
+    sorted.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    sorted
+        .into_iter()
+        .flat_map(|(_, range)| triangles[range].iter().copied())
+        .collect()
+}
+
+/// Renumbers the vertices in the order the triangles use them, so the vertex fetch reads the
+/// buffers mostly forward. Returns the remapped triangles and the previous index of each
+/// vertex, vertices no triangle uses are dropped.
+pub fn optimize_vertex_fetch(
+    triangles: &[[usize; 3]],
+    vertex_count: usize,
+) -> (Vec<[usize; 3]>, Vec<usize>) {
+    let mut remap: Vec<Option<usize>> = vec![None; vertex_count];
+    let mut sources = Vec::new();
+    let triangles = triangles
+        .iter()
+        .map(|indices| {
+            indices.map(|index| {
+                *remap[index].get_or_insert_with(|| {
+                    sources.push(index);
+                    sources.len() - 1
+                })
+            })
+        })
+        .collect();
+    (triangles, sources)
+}
+
+impl MeshData {
+    pub fn vertex_cache_stats(&self, cache_size: usize) -> VertexCacheStats {
+        vertex_cache_stats(&self.triangles(), cache_size)
+    }
+
+    /// Reorders the triangles for the vertex cache and the overdraw, then the vertices for the
+    /// vertex fetch, and stores the indices in the smallest index type.\
+    /// Only for triangle lists, vertices no triangle uses are removed. Meshes without indices
+    /// are left as they are, weld them first.
+    pub fn optimize(&mut self) {
+        if self.indices.is_none() {
+            return;
+        }
+        let triangles = optimize_vertex_cache(&self.triangles());
+        let triangles = optimize_overdraw(&triangles, &self.positions, OVERDRAW_THRESHOLD);
+        let (triangles, sources) = optimize_vertex_fetch(&triangles, self.positions.len());
+        self.remap_vertices(&sources);
+        let indices: Vec<usize> = triangles.iter().flatten().copied().collect();
+        self.indices = Some(IndexData::from_indices(&indices));
+    }
+}
@@ -0,0 +1,302 @@
+use std::collections::HashMap;
+
+use glam::*;
+
+use super::mesh_data::{IndexData, MeshData};
+
+/// Normal of the triangle scaled by twice its area, counter clockwise faces point towards the
+/// viewer like in glTF
+pub fn triangle_area_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    (b - a).cross(c - a)
+}
+
+/// Vertex normals averaged from the faces around each vertex, larger faces contribute more.\
+/// Vertices split at seams only see the faces they are part of, weld them first or use
+/// `MeshData::smooth_by_crease_angle`.
+pub fn compute_smooth_normals<I: Iterator<Item = [usize; 3]>>(
+    positions: &[Vec3],
+    triangles: I,
+) -> Vec<Vec3> {
+    let mut normals = vec![Vec3::ZERO; positions.len()];
+    for triangle in triangles {
+        if triangle.iter().any(|index| *index >= positions.len()) {
+            continue;
+        }
+        let [a, b, c] = triangle.map(|index| positions[index]);
+        let normal = triangle_area_normal(a, b, c);
+        for index in triangle {
+            normals[index] += normal;
+        }
+    }
+    normals
+        .into_iter()
+        .map(|normal| normal.normalize_or_zero())
+        .collect()
+}
+
+fn gather<T: Clone>(values: &[T], sources: &[usize]) -> Vec<T> {
+    sources
+        .iter()
+        .map(|source| values[*source].clone())
+        .collect()
+}
+
+fn position_key(position: Vec3) -> [u32; 3] {
+    position.to_array().map(f32::to_bits)
+}
+
+/// Cell of the spatial hash used by the welding, neighbouring cells hold the candidates
+fn weld_cell(position: Vec3, cell_size: f32) -> IVec3 {
+    (position / cell_size).floor().as_ivec3()
+}
+
+impl MeshData {
+    pub fn vertex_count(&self) -> usize {
+        self.positions.len()
+    }
+
+    /// Triangles of the mesh, consecutive vertices are the triangles of meshes without indices
+    pub fn triangles(&self) -> Vec<[usize; 3]> {
+        match &self.indices {
+            Some(indices) => indices.get_iter_triangle().collect(),
+            None => (0..self.positions.len() / 3)
+                .map(|triangle| {
+                    let index = triangle * 3;
+                    [index, index + 1, index + 2]
+                })
+                .collect(),
+        }
+    }
+
+    /// Replaces the indices, the current index type is kept when it can hold every index
+    pub fn set_triangles(&mut self, triangles: &[[usize; 3]]) {
+        let indices = triangles.iter().flatten();
+        let max_index = indices.clone().copied().max().unwrap_or(0);
+        self.indices = Some(match &self.indices {
+            Some(IndexData::U8(_)) if max_index <= u8::MAX as usize => {
+                IndexData::U8(indices.map(|index| *index as u8).collect())
+            }
+            Some(IndexData::U8(_)) | Some(IndexData::U16(_)) | None
+                if max_index <= u16::MAX as usize =>
+            {
+                IndexData::U16(indices.map(|index| *index as u16).collect())
+            }
+            _ => IndexData::U32(indices.map(|index| *index as u32).collect()),
+        });
+    }
+
+    /// Rebuilds every vertex attribute, including the morph targets, so the vertex `i` is the
+    /// previous vertex `sources[i]`.\
+    /// The indices are left untouched.
+    pub fn remap_vertices(&mut self, sources: &[usize]) {
+        self.positions = gather(&self.positions, sources);
+        if let Some(normals) = &mut self.normals {
+            *normals = gather(normals, sources);
+        }
+        if let Some(tangents) = &mut self.tangents {
+            *tangents = gather(tangents, sources);
+        }
+        if let Some(uvs) = &mut self.uvs {
+            for uv_set in uvs.iter_mut() {
+                *uv_set = gather(uv_set, sources);
+            }
+        }
+        if let Some(colors) = &mut self.colors {
+            for color_set in colors.iter_mut() {
+                *color_set = gather(color_set, sources);
+            }
+        }
+        if let Some(joints) = &mut self.joints {
+            *joints = gather(joints, sources);
+        }
+        if let Some(weights) = &mut self.weights {
+            *weights = gather(weights, sources);
+        }
+        if let Some(targets) = &mut self.morph_targets {
+            for target in targets.iter_mut() {
+                target.position_deltas = gather(&target.position_deltas, sources);
+                if let Some(normal_deltas) = &mut target.normal_deltas {
+                    *normal_deltas = gather(normal_deltas, sources);
+                }
+            }
+        }
+    }
+
+    /// Replaces the normals with area weighted smooth normals, see `compute_smooth_normals`
+    pub fn recompute_normals(&mut self) {
+        let normals = compute_smooth_normals(&self.positions, self.triangles().into_iter());
+        self.normals = Some(normals);
+    }
+
+    fn vertices_match(&self, a: usize, b: usize, epsilon: f32) -> bool {
+        fn attribute_match<T: Copy, F: Fn(T, T) -> bool>(
+            values: &Option<Vec<T>>,
+            a: usize,
+            b: usize,
+            matches: F,
+        ) -> bool {
+            values
+                .as_ref()
+                .is_none_or(|values| matches(values[a], values[b]))
+        }
+
+        self.positions[a].distance(self.positions[b]) <= epsilon
+            && attribute_match(&self.normals, a, b, |x, y| x.abs_diff_eq(y, epsilon))
+            && attribute_match(&self.tangents, a, b, |x, y| x.abs_diff_eq(y, epsilon))
+            && attribute_match(&self.joints, a, b, |x, y| x == y)
+            && attribute_match(&self.weights, a, b, |x, y| x.abs_diff_eq(y, epsilon))
+            && self.uvs.as_ref().is_none_or(|uvs| {
+                uvs.iter()
+                    .all(|uv_set| uv_set[a].abs_diff_eq(uv_set[b], epsilon))
+            })
+            && self.colors.as_ref().is_none_or(|colors| {
+                colors.iter().all(|color_set| {
+                    let (x, y) = (&color_set[a], &color_set[b]);
+                    vec4(x.r, x.g, x.b, x.a).abs_diff_eq(vec4(y.r, y.g, y.b, y.a), epsilon)
+                })
+            })
+            && self.morph_targets.as_ref().is_none_or(|targets| {
+                targets.iter().all(|target| {
+                    target.position_deltas[a].abs_diff_eq(target.position_deltas[b], epsilon)
+                        && target
+                            .normal_deltas
+                            .as_ref()
+                            .is_none_or(|deltas| deltas[a].abs_diff_eq(deltas[b], epsilon))
+                })
+            })
+    }
+
+    /// Merges the vertices closer than `epsilon` whose other attributes are also equal within
+    /// `epsilon`, so UV seams and hard edges are kept, and returns how many vertices were
+    /// removed.\
+    /// Meshes without indices become indexed, triangles collapsed by the welding are removed.
+    pub fn weld_vertices(&mut self, epsilon: f32) -> usize {
+        let vertex_count = self.positions.len();
+        let cell_size = if epsilon > 0.0 { epsilon } else { 1.0 };
+        let mut cells: HashMap<IVec3, Vec<usize>> = HashMap::new();
+        let mut kept = Vec::new();
+        let mut remap = vec![0; vertex_count];
+
+        for vertex in 0..vertex_count {
+            let cell = weld_cell(self.positions[vertex], cell_size);
+            let mut found = None;
+            'search: for x in -1..=1 {
+                for y in -1..=1 {
+                    for z in -1..=1 {
+                        let candidates = match cells.get(&(cell + ivec3(x, y, z))) {
+                            Some(candidates) => candidates,
+                            None => continue,
+                        };
+                        for candidate in candidates {
+                            if self.vertices_match(*candidate, vertex, epsilon) {
+                                found = Some(remap[*candidate]);
+                                break 'search;
+                            }
+                        }
+                    }
+                }
+            }
+            remap[vertex] = match found {
+                Some(index) => index,
+                None => {
+                    kept.push(vertex);
+                    cells.entry(cell).or_default().push(vertex);
+                    kept.len() - 1
+                }
+            };
+        }
+
+        let triangles: Vec<[usize; 3]> = self
+            .triangles()
+            .into_iter()
+            .map(|triangle| triangle.map(|index| remap[index]))
+            .filter(|[a, b, c]| a != b && b != c && a != c)
+            .collect();
+        self.remap_vertices(&kept);
+        self.set_triangles(&triangles);
+        vertex_count - kept.len()
+    }
+
+    /// Gives every triangle its own vertices with the face normal, the mesh loses its indices.\
+    /// Tangents are copied from the shared vertices, generate them again for normal mapping.
+    pub fn split_flat(&mut self) {
+        let triangles = self.triangles();
+        let sources: Vec<usize> = triangles.iter().flatten().copied().collect();
+        self.remap_vertices(&sources);
+        let normals = self
+            .positions
+            .chunks_exact(3)
+            .flat_map(|corners| {
+                let normal = triangle_area_normal(corners[0], corners[1], corners[2]);
+                [normal.normalize_or_zero(); 3]
+            })
+            .collect();
+        self.normals = Some(normals);
+        self.indices = None;
+    }
+
+    /// Smooth normals that keep the edges sharper than `crease_angle`, in radians.\
+    /// Each corner averages the faces around its position whose normal is within the crease
+    /// angle of its own face, vertices are split where the corners end with different normals.
+    /// `PI` smooths every edge, also across UV seams, and `0` gives flat shading.
+    pub fn smooth_by_crease_angle(&mut self, crease_angle: f32) {
+        let triangles = self.triangles();
+        let face_normals: Vec<Vec3> = triangles
+            .iter()
+            .map(|triangle| {
+                let [a, b, c] = triangle.map(|index| self.positions[index]);
+                triangle_area_normal(a, b, c)
+            })
+            .collect();
+        let face_directions: Vec<Vec3> = face_normals
+            .iter()
+            .map(|normal| normal.normalize_or_zero())
+            .collect();
+
+        let mut faces_at_position: HashMap<[u32; 3], Vec<usize>> = HashMap::new();
+        for (face, triangle) in triangles.iter().enumerate() {
+            for index in triangle {
+                let faces = faces_at_position
+                    .entry(position_key(self.positions[*index]))
+                    .or_default();
+                // Degenerate faces can touch a position twice
+                if faces.last() != Some(&face) {
+                    faces.push(face);
+                }
+            }
+        }
+
+        let min_cos = crease_angle.cos();
+        let mut vertices: HashMap<(usize, [u32; 3]), usize> = HashMap::new();
+        let mut sources = Vec::new();
+        let mut normals = Vec::new();
+        let new_triangles: Vec<[usize; 3]> = triangles
+            .iter()
+            .enumerate()
+            .map(|(face, triangle)| {
+                triangle.map(|index| {
+                    let normal: Vec3 = faces_at_position[&position_key(self.positions[index])]
+                        .iter()
+                        .filter(|other| {
+                            face_directions[face].dot(face_directions[**other]) >= min_cos
+                        })
+                        .map(|other| face_normals[*other])
+                        .sum::<Vec3>()
+                        .normalize_or_zero();
+                    // Corners of a vertex that get the same normal keep sharing it
+                    *vertices
+                        .entry((index, normal.to_array().map(f32::to_bits)))
+                        .or_insert_with(|| {
+                            sources.push(index);
+                            normals.push(normal);
+                            sources.len() - 1
+                        })
+                })
+            })
+            .collect();
+
+        self.remap_vertices(&sources);
+        self.normals = Some(normals);
+        self.set_triangles(&new_triangles);
+    }
+}
@@ -0,0 +1,82 @@
+use bevy_mikktspace::Geometry;
+use glam::*;
+
+/// Triangle list seen through the interface of the MikkTSpace implementation
+struct TangentGeometry<'a> {
+    positions: &'a [Vec3],
+    normals: &'a [Vec3],
+    uvs: &'a [Vec2],
+    triangles: &'a [[usize; 3]],
+    corner_tangents: Vec<[Vec4; 3]>,
+}
+
+impl<'a> Geometry for TangentGeometry<'a> {
+    fn num_faces(&self) -> usize {
+        self.triangles.len()
+    }
+
+    fn num_vertices_of_face(&self, _face: usize) -> usize {
+        3
+    }
+
+    fn position(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.positions[self.triangles[face][vert]].to_array()
+    }
+
+    fn normal(&self, face: usize, vert: usize) -> [f32; 3] {
+        self.normals[self.triangles[face][vert]].to_array()
+    }
+
+    fn tex_coord(&self, face: usize, vert: usize) -> [f32; 2] {
+        self.uvs[self.triangles[face][vert]].to_array()
+    }
+
+    fn set_tangent_encoded(&mut self, tangent: [f32; 4], face: usize, vert: usize) {
+        self.corner_tangents[face][vert] = Vec4::from_array(tangent);
+    }
+}
+
+/// MikkTSpace tangents for every corner of the triangles, the tangent space glTF requires when
+/// a mesh comes without tangents and the one normal maps are baked with.\
+/// `w` holds the handedness so `bitangent = cross(normal, tangent.xyz) * w`. Corners of a
+/// vertex get different tangents on each side of mirrored UVs, `MeshData::generate_tangents`
+/// splits those vertices. Corners without a usable UV mapping get the MikkTSpace default
+/// `(1, 0, 0, -1)`.\
+/// Triangles that reference missing vertices are left with the default tangent too.
+pub fn generate_tangents(
+    positions: &[Vec3],
+    normals: &[Vec3],
+    uvs: &[Vec2],
+    triangles: &[[usize; 3]],
+) -> Vec<[Vec4; 3]> {
+    let vertex_count = positions.len().min(normals.len()).min(uvs.len());
+    let default_tangent = vec4(1.0, 0.0, 0.0, -1.0);
+    let valid_triangles: Vec<usize> = (0..triangles.len())
+        .filter(|triangle| {
+            triangles[*triangle]
+                .iter()
+                .all(|index| *index < vertex_count)
+        })
+        .collect();
+    let valid_corners: Vec<[usize; 3]> = valid_triangles
+        .iter()
+        .map(|triangle| triangles[*triangle])
+        .collect();
+
+    let mut geometry = TangentGeometry {
+        positions,
+        normals,
+        uvs,
+        triangles: &valid_corners,
+        corner_tangents: vec![[default_tangent; 3]; valid_corners.len()],
+    };
+    if !valid_corners.is_empty() {
+        bevy_mikktspace::generate_tangents(&mut geometry);
+    }
+
+    let mut corner_tangents = vec![[default_tangent; 3]; triangles.len()];
+    for (triangle, tangents) in valid_triangles.iter().zip(geometry.corner_tangents) {
+        corner_tangents[*triangle] = tangents;
+    }
+    corner_tangents
+}
@@ -0,0 +1,533 @@
+#[cfg(test)]
+mod tests {
+    use glam::*;
+
+    use super::super::mesh_data::*;
+    use super::super::morph_target::*;
+    use super::super::optimize::*;
+    use super::super::shading::*;
+    use super::super::tangents::*;
+
+    fn create_targets() -> Vec<MorphTarget> {
+        let mut smile = MorphTarget::new(vec![Vec3::Y, Vec3::ZERO]);
+        smile.normal_deltas = Some(vec![vec3(1.0, -1.0, 0.0), Vec3::ZERO]);
+        let blink = MorphTarget::new(vec![Vec3::ZERO, vec3(0.0, 0.0, -2.0)]);
+        vec![smile, blink]
+    }
+
+    #[test]
+    fn morph_positions_are_weighted_sums() {
+        let base = vec![Vec3::ZERO, Vec3::X];
+        let targets = create_targets();
+        assert_eq!(blend_morph_positions(&base, &targets, &[]), base);
+        assert_eq!(
+            blend_morph_positions(&base, &targets, &[0.5, 0.25]),
+            vec![vec3(0.0, 0.5, 0.0), vec3(1.0, 0.0, -0.5)]
+        );
+    }
+
+    #[test]
+    fn morph_normals_are_normalized() {
+        let base = vec![Vec3::Y, Vec3::Y];
+        let normals = blend_morph_normals(&base, &create_targets(), &[1.0, 1.0]);
+        assert!(normals[0].abs_diff_eq(Vec3::X, 1e-6));
+        assert!(
+            normals[1].abs_diff_eq(Vec3::Y, 1e-6),
+            "Targets without normals are skipped"
+        );
+    }
+
+    fn quad(uvs: Vec<Vec2>) -> Vec<Vec4> {
+        let positions = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+        ];
+        let normals = vec![Vec3::Z; 4];
+        generate_tangents(&positions, &normals, &uvs, &[[0, 1, 2], [0, 2, 3]])
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn tangents_match_the_mikktspace_reference() {
+        // +X face of the cube used by the reference tests of MikkTSpace, four triangles around
+        // a center vertex with the normals of a sphere
+        let directions = [
+            vec3(1.0, -1.0, 1.0),
+            vec3(1.0, -1.0, -1.0),
+            vec3(1.0, 1.0, -1.0),
+            vec3(1.0, 1.0, 1.0),
+            vec3(1.0, 0.0, 0.0),
+        ];
+        let positions: Vec<Vec3> = directions
+            .iter()
+            .map(|direction| *direction / 2.0)
+            .collect();
+        let normals: Vec<Vec3> = directions
+            .iter()
+            .map(|direction| direction.normalize())
+            .collect();
+        let uvs = vec![
+            vec2(0.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(1.0, 1.0),
+            vec2(1.0, 0.0),
+            vec2(0.5, 0.5),
+        ];
+        let triangles = [[0, 1, 4], [1, 2, 4], [2, 3, 4], [3, 0, 4]];
+        let tangents = generate_tangents(&positions, &normals, &uvs, &triangles);
+
+        let a = 0.40824825;
+        let b = 0.81649655;
+        let expected = [
+            [
+                vec4(a, b, a, -1.0),
+                vec4(a, b, -a, -1.0),
+                vec4(0.0, 1.0, 0.0, -1.0),
+            ],
+            [
+                vec4(a, b, -a, -1.0),
+                vec4(-a, b, a, -1.0),
+                vec4(0.0, 1.0, 0.0, -1.0),
+            ],
+            [
+                vec4(-a, b, a, -1.0),
+                vec4(-a, b, -a, -1.0),
+                vec4(0.0, 1.0, 0.0, -1.0),
+            ],
+            [
+                vec4(-a, b, -a, -1.0),
+                vec4(a, b, a, -1.0),
+                vec4(0.0, 1.0, 0.0, -1.0),
+            ],
+        ];
+        for (corner_tangents, expected) in tangents.iter().zip(expected.iter()) {
+            for (tangent, expected) in corner_tangents.iter().zip(expected.iter()) {
+                assert!(
+                    tangent.abs_diff_eq(*expected, 1e-6),
+                    "{} != {}",
+                    tangent,
+                    expected
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tangents_follow_the_u_direction() {
+        let uvs = vec![
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(1.0, 1.0),
+            vec2(0.0, 1.0),
+        ];
+        for tangent in quad(uvs) {
+            assert!(tangent.abs_diff_eq(vec4(1.0, 0.0, 0.0, 1.0), 1e-6));
+        }
+
+        // Rotated texture, u grows along +Y
+        let uvs = vec![
+            vec2(0.0, 1.0),
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(1.0, 1.0),
+        ];
+        for tangent in quad(uvs) {
+            assert!(tangent.abs_diff_eq(vec4(0.0, 1.0, 0.0, 1.0), 1e-6));
+        }
+    }
+
+    #[test]
+    fn mirrored_uvs_flip_the_handedness() {
+        let uvs = vec![
+            vec2(1.0, 0.0),
+            vec2(0.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(1.0, 1.0),
+        ];
+        for tangent in quad(uvs) {
+            assert!(tangent.abs_diff_eq(vec4(-1.0, 0.0, 0.0, -1.0), 1e-6));
+            // The bitangent still points along +v
+            let bitangent = Vec3::Z.cross(tangent.truncate()) * tangent.w;
+            assert!(bitangent.abs_diff_eq(Vec3::Y, 1e-6));
+        }
+    }
+
+    #[test]
+    fn mirrored_uv_seams_split_the_vertices() {
+        // Two quads sharing the edge 1-2, the right one mirrors the UVs of the left one
+        let positions = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(1.0, 1.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(2.0, 0.0, 0.0),
+            vec3(2.0, 1.0, 0.0),
+        ];
+        let uvs = vec![
+            vec2(0.0, 0.0),
+            vec2(1.0, 0.0),
+            vec2(1.0, 1.0),
+            vec2(0.0, 1.0),
+            vec2(0.0, 0.0),
+            vec2(0.0, 1.0),
+        ];
+        let indices = vec![0, 1, 2, 0, 2, 3, 1, 4, 5, 1, 5, 2];
+        let mut mesh = create_mesh(positions, Some(IndexData::U16(indices)));
+        mesh.normals = Some(vec![Vec3::Z; 6]);
+        mesh.uvs = Some(vec![uvs]);
+        mesh.generate_tangents(0).unwrap();
+
+        assert_eq!(mesh.vertex_count(), 8, "Only the seam vertices are split");
+        let tangents = mesh.tangents.as_ref().unwrap();
+        let triangles = mesh.triangles();
+        for (face, triangle) in triangles.iter().enumerate() {
+            let expected = if face < 2 {
+                vec4(1.0, 0.0, 0.0, 1.0)
+            } else {
+                vec4(-1.0, 0.0, 0.0, -1.0)
+            };
+            for index in triangle {
+                assert!(tangents[*index].abs_diff_eq(expected, 1e-6));
+            }
+        }
+        assert!(mesh.positions[triangles[2][0]].abs_diff_eq(Vec3::X, 1e-6));
+    }
+
+    #[test]
+    fn tangents_are_orthogonal_to_smooth_normals() {
+        // Two faces of a roof sharing an edge with averaged normals
+        let positions = vec![
+            vec3(0.0, 0.0, 0.0),
+            vec3(0.0, 0.0, 1.0),
+            vec3(1.0, 1.0, 0.0),
+            vec3(1.0, 1.0, 1.0),
+            vec3(2.0, 0.0, 0.0),
+            vec3(2.0, 0.0, 1.0),
+        ];
+        let normals = vec![
+            vec3(-1.0, 1.0, 0.0).normalize(),
+            vec3(-1.0, 1.0, 0.0).normalize(),
+            Vec3::Y,
+            Vec3::Y,
+            vec3(1.0, 1.0, 0.0).normalize(),
+            vec3(1.0, 1.0, 0.0).normalize(),
+        ];
+        let uvs = vec![
+            vec2(0.0, 0.0),
+            vec2(0.0, 1.0),
+            vec2(0.5, 0.0),
+            vec2(0.5, 1.0),
+            vec2(1.0, 0.0),
+            vec2(1.0, 1.0),
+        ];
+        let triangles = [[0, 2, 1], [1, 2, 3], [2, 4, 3], [3, 4, 5]];
+        let tangents = generate_tangents(&positions, &normals, &uvs, &triangles);
+        for (triangle, corner_tangents) in triangles.iter().zip(tangents.iter()) {
+            for (index, tangent) in triangle.iter().zip(corner_tangents.iter()) {
+                assert!(tangent.truncate().dot(normals[*index]).abs() < 1e-5);
+                assert!((tangent.truncate().length() - 1.0).abs() < 1e-5);
+                assert!(tangent.x > 0.0);
+            }
+        }
+        // Corner of the ridge vertex 2 in the first triangle
+        assert!(tangents[0][1].abs_diff_eq(vec4(1.0, 0.0, 0.0, tangents[0][1].w), 1e-5));
+    }
+
+    #[test]
+    fn degenerate_uvs_get_the_default_tangent() {
+        let tangents = quad(vec![Vec2::ZERO; 4]);
+        for tangent in tangents {
+            assert_eq!(tangent, vec4(1.0, 0.0, 0.0, -1.0));
+        }
+    }
+
+    fn create_mesh(positions: Vec<Vec3>, indices: Option<IndexData>) -> MeshData {
+        MeshData {
+            positions,
+            normals: None,
+            tangents: None,
+            uvs: None,
+            colors: None,
+            joints: None,
+            weights: None,
+            morph_targets: None,
+            indices,
+        }
+    }
+
+    /// Two faces sharing the edge along Z, tilted 45 degrees to each side
+    fn create_roof() -> MeshData {
+        let positions = vec![
+            vec3(-1.0, 0.0, 0.0),
+            vec3(0.0, 1.0, 0.0),
+            vec3(0.0, 1.0, -1.0),
+            vec3(-1.0, 0.0, -1.0),
+            vec3(1.0, 0.0, 0.0),
+            vec3(1.0, 0.0, -1.0),
+        ];
+        let indices = vec![0, 1, 2, 0, 2, 3, 1, 4, 5, 1, 5, 2];
+        create_mesh(positions, Some(IndexData::U16(indices)))
+    }
+
+    #[test]
+    fn smooth_normals_are_area_weighted() {
+        // Large face facing +Z and a small face facing +X sharing the first vertex
+        let positions = vec![
+            Vec3::ZERO,
+            vec3(4.0, 0.0, 0.0),
+            vec3(0.0, 4.0, 0.0),
+            vec3(0.0, 0.0, -1.0),
+            vec3(0.0, 1.0, 0.0),
+        ];
+        let normals = compute_smooth_normals(&positions, [[0, 1, 2], [0, 3, 4]].into_iter());
+        assert!(normals[0].abs_diff_eq(vec3(1.0, 0.0, 16.0).normalize(), 1e-6));
+        assert!(normals[1].abs_diff_eq(Vec3::Z, 1e-6));
+        assert!(normals[3].abs_diff_eq(Vec3::X, 1e-6));
+
+        let mut roof = create_roof();
+        roof.recompute_normals();
+        let normals = roof.normals.unwrap();
+        // One face of the left side and both faces of the right side touch this ridge vertex
+        assert!(normals[1].abs_diff_eq(vec3(1.0, 3.0, 0.0).normalize(), 1e-6));
+        assert!(normals[0].abs_diff_eq(vec3(-1.0, 1.0, 0.0).normalize(), 1e-6));
+    }
+
+    #[test]
+    fn welding_merges_close_vertices_and_keeps_seams() {
+        let mut roof = create_roof();
+        roof.split_flat();
+        assert!(roof.indices.is_none());
+        assert_eq!(roof.vertex_count(), 12);
+        let normals = roof.normals.as_ref().unwrap();
+        assert!(normals[0].abs_diff_eq(vec3(-1.0, 1.0, 0.0).normalize(), 1e-6));
+        assert!(normals[6].abs_diff_eq(vec3(1.0, 1.0, 0.0).normalize(), 1e-6));
+
+        // The faces keep their own normals along the ridge
+        roof.positions[3] += Vec3::splat(1e-5);
+        assert_eq!(roof.weld_vertices(1e-4), 4);
+        assert_eq!(roof.vertex_count(), 8);
+        assert_eq!(roof.get_index_count(), 12);
+        let triangles = roof.triangles();
+        assert_eq!(triangles[0], [0, 1, 2]);
+        assert_eq!(triangles[1], [0, 2, 3]);
+
+        // Without normals only the positions are compared
+        roof.normals = None;
+        assert_eq!(roof.weld_vertices(1e-4), 2);
+        assert!(matches!(roof.indices, Some(IndexData::U16(_))));
+        assert_eq!(roof.triangles(), create_roof().triangles());
+    }
+
+    #[test]
+    fn welding_removes_collapsed_triangles() {
+        let positions = vec![
+            Vec3::ZERO,
+            Vec3::X,
+            Vec3::Y,
+            Vec3::X,
+            vec3(1.0, 1e-6, 0.0),
+            Vec3::Y,
+        ];
+        let mut mesh = create_mesh(positions, Some(IndexData::U8(vec![0, 1, 2, 3, 4, 5])));
+        assert_eq!(mesh.weld_vertices(1e-4), 3);
+        assert!(matches!(&mesh.indices, Some(IndexData::U8(indices)) if indices == &vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn crease_angle_splits_sharp_edges() {
+        let mut roof = create_roof();
+        roof.smooth_by_crease_angle(std::f32::consts::FRAC_PI_4);
+        assert_eq!(roof.vertex_count(), 8, "The ridge is split");
+        for (triangle, expected) in roof.triangles().into_iter().zip([-1.0, -1.0, 1.0, 1.0]) {
+            for index in triangle {
+                let normal = roof.normals.as_ref().unwrap()[index];
+                assert!(normal.abs_diff_eq(vec3(expected, 1.0, 0.0).normalize(), 1e-6));
+            }
+        }
+
+        let mut roof = create_roof();
+        roof.smooth_by_crease_angle(std::f32::consts::FRAC_PI_2 + 0.01);
+        assert_eq!(roof.vertex_count(), 6);
+        let mut smooth = create_roof();
+        smooth.recompute_normals();
+        assert_eq!(roof.normals, smooth.normals);
+        assert_eq!(roof.triangles(), smooth.triangles());
+    }
+
+    #[test]
+    fn indices_are_narrowed_to_the_smallest_type() {
+        let mut indices = IndexData::U32(vec![0, 1, 255]);
+        indices.narrow();
+        assert!(matches!(&indices, IndexData::U8(values) if values == &vec![0, 1, 255]));
+        assert!(matches!(
+            IndexData::from_indices(&[0, 256, 65535]),
+            IndexData::U16(_)
+        ));
+        assert!(matches!(
+            IndexData::from_indices(&[65536, 0, 1]),
+            IndexData::U32(_)
+        ));
+    }
+
+    #[test]
+    fn cache_stats_count_misses() {
+        let stats = vertex_cache_stats(&[[0, 1, 2]], 16);
+        assert_eq!(stats.vertices_transformed, 3);
+        assert_eq!(stats.acmr, 3.0);
+        assert_eq!(stats.atvr, 1.0);
+
+        let stats = vertex_cache_stats(&[[0, 1, 2], [2, 1, 3], [4, 5, 6], [0, 1, 2]], 4);
+        // The first triangle left the cache after the third one
+        assert_eq!(stats.vertices_transformed, 10);
+        assert_eq!(stats.acmr, 2.5);
+        assert!((stats.atvr - 10.0 / 7.0).abs() < 1e-6);
+    }
+
+    /// Grid of `size` by `size` quads with its triangles scattered
+    fn create_shuffled_grid(size: usize) -> Vec<[usize; 3]> {
+        let mut triangles = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let index = y * (size + 1) + x;
+                let next_row = index + size + 1;
+                triangles.push([index, index + 1, next_row]);
+                triangles.push([next_row, index + 1, next_row + 1]);
+            }
+        }
+        let count = triangles.len();
+        (0..count)
+            .map(|index| triangles[(index * 7919) % count])
+            .collect()
+    }
+
+    #[test]
+    fn forsyth_order_improves_the_cache_hits() {
+        let triangles = create_shuffled_grid(32);
+        let optimized = optimize_vertex_cache(&triangles);
+
+        let before = vertex_cache_stats(&triangles, VERTEX_CACHE_SIZE);
+        let after = vertex_cache_stats(&optimized, VERTEX_CACHE_SIZE);
+        assert!(before.acmr > 2.0);
+        assert!(after.acmr < 0.8, "{:?}", after);
+        assert!(after.atvr < 1.4, "{:?}", after);
+
+        let mut sorted_before = triangles.clone();
+        let mut sorted_after = optimized.clone();
+        sorted_before.sort();
+        sorted_after.sort();
+        assert_eq!(
+            sorted_before, sorted_after,
+            "Same triangles with the same winding"
+        );
+    }
+
+    #[test]
+    fn optimized_mesh_keeps_its_geometry() {
+        let triangles = create_shuffled_grid(4);
+        let mut positions: Vec<Vec3> = (0..25)
+            .map(|index| vec3((index % 5) as f32, (index / 5) as f32, 0.0))
+            .collect();
+        // Unused vertex
+        positions.push(Vec3::NEG_ONE);
+        let indices = triangles
+            .iter()
+            .flatten()
+            .map(|index| *index as u32)
+            .collect();
+        let mut mesh = create_mesh(positions, Some(IndexData::U32(indices)));
+        mesh.uvs = Some(vec![mesh
+            .positions
+            .iter()
+            .map(|position| position.truncate())
+            .collect()]);
+        let triangle_positions = |mesh: &MeshData| {
+            let mut corners: Vec<[[u32; 3]; 3]> = mesh
+                .triangles()
+                .iter()
+                .map(|triangle| {
+                    triangle.map(|index| mesh.positions[index].to_array().map(f32::to_bits))
+                })
+                .collect();
+            corners.sort();
+            corners
+        };
+        let before = triangle_positions(&mesh);
+
+        mesh.optimize();
+        assert!(matches!(mesh.indices, Some(IndexData::U8(_))));
+        assert_eq!(mesh.vertex_count(), 25);
+        assert_eq!(triangle_positions(&mesh), before);
+        let uvs = &mesh.uvs.as_ref().unwrap()[0];
+        for (uv, position) in uvs.iter().zip(mesh.positions.iter()) {
+            assert_eq!(*uv, position.truncate());
+        }
+        // Vertices are numbered in the order of their first use
+        let mut next = 0;
+        for index in mesh.indices.as_ref().unwrap().to_indices() {
+            assert!(index <= next);
+            next = next.max(index + 1);
+        }
+        assert!(mesh.vertex_cache_stats(VERTEX_CACHE_SIZE).acmr < 1.0);
+    }
+
+    /// Cube with four vertices per face, faces pointing outwards
+    fn push_cube(positions: &mut Vec<Vec3>, triangles: &mut Vec<[usize; 3]>, half_size: f32) {
+        let faces = [
+            (Vec3::X, Vec3::Y, Vec3::Z),
+            (Vec3::NEG_X, Vec3::Z, Vec3::Y),
+            (Vec3::Y, Vec3::Z, Vec3::X),
+            (Vec3::NEG_Y, Vec3::X, Vec3::Z),
+            (Vec3::Z, Vec3::X, Vec3::Y),
+            (Vec3::NEG_Z, Vec3::Y, Vec3::X),
+        ];
+        for (normal, u, v) in faces {
+            let first = positions.len();
+            for (x, y) in [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)] {
+                positions.push((normal + u * x + v * y) * half_size);
+            }
+            triangles.push([first, first + 1, first + 2]);
+            triangles.push([first, first + 2, first + 3]);
+        }
+    }
+
+    #[test]
+    fn overdraw_order_draws_outer_clusters_first() {
+        let mut positions = Vec::new();
+        let mut triangles = Vec::new();
+        push_cube(&mut positions, &mut triangles, 0.5);
+        push_cube(&mut positions, &mut triangles, 1.0);
+        let normal = triangle_area_normal(
+            positions[triangles[0][0]],
+            positions[triangles[0][1]],
+            positions[triangles[0][2]],
+        );
+        assert!(normal.normalize().abs_diff_eq(Vec3::X, 1e-6));
+
+        let optimized = optimize_overdraw(&triangles, &positions, OVERDRAW_THRESHOLD);
+        for triangle in &optimized[..12] {
+            assert!(
+                triangle.iter().all(|index| *index >= 24),
+                "Outer cube first"
+            );
+        }
+        let mut sorted_before = triangles.clone();
+        let mut sorted_after = optimized.clone();
+        sorted_before.sort();
+        sorted_after.sort();
+        assert_eq!(sorted_before, sorted_after);
+
+        // Clusters keep most of the vertex cache order
+        let grid = optimize_vertex_cache(&create_shuffled_grid(32));
+        let grid_positions: Vec<Vec3> = (0..33 * 33)
+            .map(|index| vec3((index % 33) as f32, (index / 33) as f32, 0.0))
+            .collect();
+        let optimized = optimize_overdraw(&grid, &grid_positions, OVERDRAW_THRESHOLD);
+        let before = vertex_cache_stats(&grid, VERTEX_CACHE_SIZE);
+        let after = vertex_cache_stats(&optimized, VERTEX_CACHE_SIZE);
+        assert!(after.acmr < before.acmr * 1.2, "{:?} {:?}", before, after);
+    }
+}
@@ -0,0 +1,64 @@
+use rust_webgl2::{DepthFunction, DrawCapabilities};
+
+/// Direction of the depth values, has to match the projection of the cameras
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DepthConvention {
+    /// OpenGL clip space, depth goes from -1 at the near plane to 1 at the far plane and is
+    /// written as `0.5 * z + 0.5`
+    #[default]
+    Standard,
+    /// Zero to one clip space with the near plane at depth 1 and the far plane at 0, set with
+    /// `Renderer::set_depth_convention`.\
+    /// Requires `EXT_clip_control` and a floating point depth buffer, the precision of the float
+    /// is spent on the distant values that need it and z-fighting goes away.
+    Reversed,
+}
+
+impl DepthConvention {
+    /// Converts a depth written for the standard convention
+    pub fn depth(&self, depth: f32) -> f32 {
+        match self {
+            DepthConvention::Standard => depth,
+            DepthConvention::Reversed => 1.0 - depth,
+        }
+    }
+
+    /// Normalized device depth of a depth buffer value
+    pub fn ndc_depth(&self, depth: f32) -> f32 {
+        match self {
+            DepthConvention::Standard => depth * 2.0 - 1.0,
+            DepthConvention::Reversed => depth,
+        }
+    }
+
+    /// Depth buffer value of a normalized device depth
+    pub fn buffer_depth(&self, ndc_depth: f32) -> f32 {
+        match self {
+            DepthConvention::Standard => ndc_depth * 0.5 + 0.5,
+            DepthConvention::Reversed => ndc_depth,
+        }
+    }
+
+    /// Converts a depth test written for the standard convention, e.g. `LESS` becomes `GREATER`
+    pub fn depth_function(&self, function: DepthFunction) -> DepthFunction {
+        match self {
+            DepthConvention::Standard => function,
+            DepthConvention::Reversed => match function {
+                DepthFunction::LESS => DepthFunction::GREATER,
+                DepthFunction::LEQUAL => DepthFunction::GEQUAL,
+                DepthFunction::GREATER => DepthFunction::LESS,
+                DepthFunction::GEQUAL => DepthFunction::LEQUAL,
+                function => function,
+            },
+        }
+    }
+
+    pub fn draw_capabilities(&self, capabilities: DrawCapabilities) -> DrawCapabilities {
+        DrawCapabilities {
+            depth_test: capabilities
+                .depth_test
+                .map(|function| self.depth_function(function)),
+            ..capabilities
+        }
+    }
+}
@@ -2,8 +2,8 @@ use std::cell::RefCell;
 
 use glam::*;
 use rust_webgl2::{
-    DrawCapabilities, Graphics, MagFilter, MinFilter, Texture2DProps,
-    TextureWrap, Viewport, RGBA,
+    DrawCapabilities, GlUniformBuffer, Graphics, MagFilter, MinFilter,
+    Texture2DProps, TextureWrap, Viewport, RGBA,
 };
 
 pub mod texture_framebuffer;
@@ -14,15 +14,24 @@ use render_queue::*;
 pub mod framebuffer;
 use framebuffer::*;
 mod framebuffer_blitter;
+use wasm_bindgen::JsCast;
 use web_sys::{HtmlCanvasElement, WebGl2RenderingContext};
 pub mod instance_buffer;
+mod render_view;
+pub use render_view::*;
+mod depth_convention;
+pub use depth_convention::*;
 
 use self::texture_render::{ColorRenderable, DepthRenderable};
+use crate::camera::ViewData;
 
 pub struct RenderState {
     pub clear_state: ClearState,
+    pub depth_convention: DepthConvention,
     pub render_buffers: Option<Framebuffer>,
     pub render_buffers_copy: Option<Framebuffer>,
+    /// View being drawn by a `RenderOp::RenderView`, its matrices are in the camera buffer
+    pub current_view: Option<ViewData>,
 }
 
 impl RenderState {
@@ -32,11 +41,20 @@ impl RenderState {
             framebuffer.bind(rust_webgl2::FramebufferBinding::DRAW_FRAMEBUFFER);
         }
     }
+
+    /// Sets capabilities written for the standard depth convention, the depth test follows the
+    /// convention of the renderer
+    pub fn set_capabilities(&self, graphics: &Graphics, capabilities: DrawCapabilities) {
+        self.depth_convention
+            .draw_capabilities(capabilities)
+            .set_capabilities(graphics);
+    }
 }
 
 #[derive(Clone, Copy)]
 pub struct ClearState {
     pub color: Option<RGBA>,
+    /// Written for the standard convention, 1 being the far plane
     pub depth: Option<f32>,
     pub stencil: Option<u32>,
 }
@@ -57,8 +75,17 @@ pub struct Renderer {
     pub render_state: RenderState,
     pub render_ops: Vec<RenderOp>,
     render_queue: RefCell<RenderQueue>,
+    views: Vec<RenderView>,
+    /// Created with the first view
+    camera_buffer: Option<GlUniformBuffer>,
+    /// `EXT_clip_control`, required by `DepthConvention::Reversed`
+    clip_control: Option<js_sys::Object>,
 }
 
+const LOWER_LEFT_EXT: u32 = 0x8CA1;
+const NEGATIVE_ONE_TO_ONE_EXT: u32 = 0x935E;
+const ZERO_TO_ONE_EXT: u32 = 0x935F;
+
 pub const BLIT_CAPABILITIES: DrawCapabilities = DrawCapabilities {
     blend_state: None,
     cull_face: None,
@@ -77,7 +104,11 @@ pub enum RenderOp{
     BlitToColor,
     BlitToCanvas{
         canvas_viewport: Viewport
-    }
+    },
+    /// Draws a view registered with `Renderer::add_view`
+    RenderView{
+        view: usize,
+    },
 }
 
 impl Renderer {
@@ -96,17 +127,18 @@ impl Renderer {
         sample_count: u32,
         request_layer_count: usize
     ) -> Self {
-        let graphics = {
-            context
-                .get_extension("EXT_color_buffer_float")
-                .expect("Color buffer floats cannot be used");
-            Graphics::new(context, canvas).expect("Cannot create graphics object")
-        };
+        context
+            .get_extension("EXT_color_buffer_float")
+            .expect("Color buffer floats cannot be used");
+        let clip_control = context.get_extension("EXT_clip_control").unwrap_or(None);
+        let graphics = Graphics::new(context, canvas).expect("Cannot create graphics object");
 
         let render_state = RenderState {
             clear_state,
+            depth_convention: DepthConvention::Standard,
             render_buffers: None,
             render_buffers_copy: None,
+            current_view: None,
         };
 
         Self {
@@ -115,6 +147,9 @@ impl Renderer {
             render_state,
             sample_count,
             render_ops: Vec::new(),
+            views: Vec::new(),
+            camera_buffer: None,
+            clip_control,
         }
     }
 
@@ -122,6 +157,40 @@ impl Renderer {
         self.graphics.finish();
     }
 
+    /// Fails for `DepthConvention::Reversed` when `EXT_clip_control` is not available.\
+    /// The cameras have to generate their matrices with the same convention, see
+    /// `PerspectiveProperties::with_depth_convention`. The main render buffers are recreated
+    /// with the depth format of the convention on the next render.\
+    /// Render requests set their capabilities through `RenderState::set_capabilities` so the
+    /// depth test follows the convention. The cameras of `RenderView`s are checked in debug
+    /// builds.
+    pub fn set_depth_convention(&mut self, depth_convention: DepthConvention) -> Result<(), ()> {
+        let depth_mode = match depth_convention {
+            DepthConvention::Standard => NEGATIVE_ONE_TO_ONE_EXT,
+            DepthConvention::Reversed => ZERO_TO_ONE_EXT,
+        };
+        match &self.clip_control {
+            Some(clip_control) => {
+                let clip_control_ext = js_sys::Reflect::get(clip_control, &"clipControlEXT".into())
+                    .map_err(|_| ())?
+                    .dyn_into::<js_sys::Function>()
+                    .map_err(|_| ())?;
+                clip_control_ext
+                    .call2(clip_control, &LOWER_LEFT_EXT.into(), &depth_mode.into())
+                    .map_err(|_| ())?;
+            }
+            // The default clip space is the standard one
+            None if depth_convention == DepthConvention::Standard => {}
+            None => return Err(()),
+        }
+        if self.render_state.depth_convention != depth_convention {
+            self.render_state.render_buffers = None;
+            self.render_state.render_buffers_copy = None;
+        }
+        self.render_state.depth_convention = depth_convention;
+        Ok(())
+    }
+
     pub fn get_graphics(&self) -> &Graphics {
         &self.graphics
     }
@@ -143,7 +212,12 @@ impl Renderer {
         );
 
         render_fb.create_color_texture(&self.graphics, ColorRenderable::RGBA8.into(), Some("Main Color RB".into())).expect("Could not create color texture");
-        render_fb.create_depth_texture(&self.graphics, DepthRenderable::DEPTH24_STENCIL8.into(), Some("Main Depth RB".into())).expect("Could not create depth texture");
+        // Reverse depth only gains precision with a floating point buffer
+        let depth_format = match self.render_state.depth_convention {
+            DepthConvention::Standard => DepthRenderable::DEPTH24_STENCIL8,
+            DepthConvention::Reversed => DepthRenderable::DEPTH32F_STENCIL8,
+        };
+        render_fb.create_depth_texture(&self.graphics, depth_format.into(), Some("Main Depth RB".into())).expect("Could not create depth texture");
 
         color_fb.create_color_texture(&self.graphics, ColorRenderable::RGBA8.into(), Some("Blit Color RB".into())).expect("Could not create color texture");
 
@@ -213,9 +287,13 @@ impl Renderer {
 
         self.render_state.set_main_framebuffer(&self.graphics);
 
+        let depth_convention = self.render_state.depth_convention;
         self.graphics.clear_current_framebuffer(
             self.render_state.clear_state.color,
-            self.render_state.clear_state.depth,
+            self.render_state
+                .clear_state
+                .depth
+                .map(|depth| depth_convention.depth(depth)),
             self.render_state.clear_state.stencil,
         );
 
@@ -231,6 +309,9 @@ impl Renderer {
                 RenderOp::BlitToCanvas{canvas_viewport} => {
                     self.blit_to_canvas(canvas_viewport);
                 }
+                RenderOp::RenderView{view} => {
+                    self.render_view(view)?;
+                }
             }
         }
         Ok(())
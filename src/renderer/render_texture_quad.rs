@@ -100,9 +100,9 @@ impl RenderTextureQuad {
 
 	pub fn request_render(&self, renderer: &Renderer){
 		let mat = Rc::clone(&self.material);
-		renderer.insert_render_request(Box::new(move |graphics, _|{
+		renderer.insert_render_request(Box::new(move |graphics, render_state|{
 			let mut material = mat.borrow_mut();
-			material.set_capabilities(graphics, 0);
+			render_state.set_capabilities(graphics, DrawCapabilities::default_opaque_no_cull());
 			material.push_texture_samplers(graphics);
             let mut current_program = material.program.use_program();
             current_program.push_all_uniforms();
@@ -0,0 +1,154 @@
+use std::rc::Rc;
+
+use glam::*;
+use rust_webgl2::{FramebufferBinding, Viewport};
+
+use super::{texture_framebuffer::TextureFramebuffer, ClearState, Renderer};
+use crate::camera::{
+    bind_camera_buffer, create_camera_uniform_buffer, update_camera_buffer, CameraMatrices,
+    ViewData,
+};
+
+pub enum ViewTarget {
+    /// Main framebuffer of the renderer, shown on the canvas by `RenderOp::BlitToCanvas`
+    Canvas,
+    /// Render to texture, e.g. mirrors and minimaps sampled by a later view
+    Texture(Rc<TextureFramebuffer>),
+}
+
+/// Camera, target and render layers drawn by a `RenderOp::RenderView`
+pub struct RenderView {
+    pub camera_matrices: CameraMatrices,
+    pub target: ViewTarget,
+    /// Region of the target, the whole target when `None`
+    pub viewport: Option<Viewport>,
+    /// Render request layers executed in order
+    pub layers: Vec<usize>,
+    /// Clears the whole target before drawing, leave it as `None` for views that draw on a
+    /// region of a shared target like picture in picture
+    pub clear_state: Option<ClearState>,
+    pub enabled: bool,
+}
+
+impl RenderView {
+    pub fn new(camera_matrices: CameraMatrices, target: ViewTarget, layers: Vec<usize>) -> Self {
+        Self {
+            camera_matrices,
+            target,
+            viewport: None,
+            layers,
+            clear_state: None,
+            enabled: true,
+        }
+    }
+
+    pub fn with_viewport(mut self, viewport: Viewport) -> Self {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    pub fn with_clear_state(mut self, clear_state: ClearState) -> Self {
+        self.clear_state = Some(clear_state);
+        self
+    }
+}
+
+impl Renderer {
+    /// Registers a view and returns its index.\
+    /// Views are drawn where a `RenderOp::RenderView` is placed in `render_ops`, e.g. a minimap
+    /// texture before the layers of the main camera and a picture in picture before
+    /// `BlitToColor`.\
+    /// The renderer binds its own camera buffer for every view, camera buffers of the
+    /// application have to be bound again before an `ExecuteRequests` that follows a view.\
+    /// Rendering fails when a layer of the view is not a layer of the render queue.\
+    /// The camera matrices have to use the depth convention of the renderer, debug builds panic
+    /// when they don't.
+    pub fn add_view(&mut self, view: RenderView) -> usize {
+        self.views.push(view);
+        self.views.len() - 1
+    }
+
+    pub fn view_mut(&mut self, index: usize) -> Option<&mut RenderView> {
+        self.views.get_mut(index)
+    }
+
+    pub fn set_view_camera(&mut self, index: usize, camera_matrices: CameraMatrices) {
+        if let Some(view) = self.views.get_mut(index) {
+            view.camera_matrices = camera_matrices;
+        }
+    }
+
+    pub(super) fn render_view(&mut self, index: usize) -> Result<(), ()> {
+        let view = self.views.get(index).ok_or(())?;
+        if !view.enabled {
+            return Ok(());
+        }
+        let layer_count = self.render_queue.borrow().queues.len();
+        if view.layers.iter().any(|layer| *layer >= layer_count) {
+            return Err(());
+        }
+
+        let target_size = match &view.target {
+            ViewTarget::Canvas => {
+                self.render_state.set_main_framebuffer(&self.graphics);
+                self.render_state.render_buffers.as_ref().ok_or(())?.size
+            }
+            ViewTarget::Texture(framebuffer) => {
+                framebuffer.bind_render_buffer(FramebufferBinding::DRAW_FRAMEBUFFER);
+                framebuffer.size()
+            }
+        };
+        let viewport = view.viewport.unwrap_or(Viewport {
+            position: UVec2::ZERO,
+            size: target_size,
+        });
+        self.graphics
+            .set_viewport(viewport.position.as_ivec2(), viewport.size);
+
+        if let Some(clear_state) = view.clear_state {
+            let depth_convention = self.render_state.depth_convention;
+            self.graphics.clear_current_framebuffer(
+                clear_state.color,
+                clear_state.depth.map(|depth| depth_convention.depth(depth)),
+                clear_state.stencil,
+            );
+        }
+
+        let mut camera_matrices = view.camera_matrices;
+        debug_assert_eq!(
+            camera_matrices.depth_convention, self.render_state.depth_convention,
+            "The camera of view {} does not use the depth convention of the renderer",
+            index
+        );
+        if camera_matrices.viewport_size == Vec2::ZERO {
+            camera_matrices.viewport_size = viewport.size.as_vec2();
+        }
+        let graphics = &self.graphics;
+        let camera_buffer = self
+            .camera_buffer
+            .get_or_insert_with(|| create_camera_uniform_buffer(graphics));
+        update_camera_buffer(camera_matrices, camera_buffer);
+        // Another camera buffer may have been created or bound since the last view
+        bind_camera_buffer(camera_buffer);
+
+        self.render_state.current_view = Some(ViewData {
+            camera_matrices,
+            viewport,
+        });
+        let render_queue = self.render_queue.borrow();
+        for layer in view.layers.iter() {
+            render_queue.queues[*layer].execute_requests(&self.graphics, &self.render_state);
+        }
+        drop(render_queue);
+        self.render_state.current_view = None;
+
+        if let ViewTarget::Texture(framebuffer) = &view.target {
+            if framebuffer.has_blit_framebuffer() {
+                framebuffer.blit_first_attachement(&self.graphics);
+            }
+        }
+        // Following ops draw to the main framebuffer
+        self.render_state.set_main_framebuffer(&self.graphics);
+        Ok(())
+    }
+}
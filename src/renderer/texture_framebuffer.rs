@@ -63,6 +63,10 @@ impl TextureFramebuffer {
         }
     }
 
+    pub fn size(&self) -> UVec2 {
+        self.render_framebuffer.size
+    }
+
     pub fn bind_render_buffer(&self, target: FramebufferBinding){
         self.render_framebuffer.bind(target);
     }
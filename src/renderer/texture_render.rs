@@ -64,6 +64,7 @@ impl DepthRenderable {
     pub const DEPTH_COMPONENT24: Self = DepthRenderable(TextureInternalFormat::DEPTH_COMPONENT24);
     pub const DEPTH_COMPONENT32F: Self = DepthRenderable(TextureInternalFormat::DEPTH_COMPONENT32F);
     pub const DEPTH24_STENCIL8: Self = DepthRenderable(TextureInternalFormat::DEPTH24_STENCIL8);
+    pub const DEPTH32F_STENCIL8: Self = DepthRenderable(TextureInternalFormat::DEPTH32F_STENCIL8);
 }
 
 pub struct ColorRenderable(TextureInternalFormat);
@@ -0,0 +1,154 @@
+use std::collections::VecDeque;
+
+use super::Milisecond;
+
+#[derive(Clone, Copy)]
+pub struct FrameStatsSummary {
+    pub min: Milisecond,
+    pub max: Milisecond,
+    pub average: Milisecond,
+    pub p50: Milisecond,
+    pub p95: Milisecond,
+    pub p99: Milisecond,
+    pub fps: f64,
+}
+
+/// Rolling window with the duration of the last N frames
+pub struct FrameStats {
+    samples: VecDeque<f64>,
+    window_size: usize,
+}
+
+impl FrameStats {
+    pub fn new(window_size: usize) -> Self {
+        if window_size == 0 {
+            panic!("Frame stats window cannot be empty");
+        }
+        Self {
+            samples: VecDeque::with_capacity(window_size),
+            window_size,
+        }
+    }
+
+    pub fn push(&mut self, frame_time: Milisecond) {
+        if self.samples.len() == self.window_size {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(frame_time.0);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    pub fn last(&self) -> Option<Milisecond> {
+        self.samples.back().map(|sample| Milisecond(*sample))
+    }
+
+    pub fn min(&self) -> Option<Milisecond> {
+        self.samples
+            .iter()
+            .copied()
+            .reduce(f64::min)
+            .map(Milisecond)
+    }
+
+    pub fn max(&self) -> Option<Milisecond> {
+        self.samples
+            .iter()
+            .copied()
+            .reduce(f64::max)
+            .map(Milisecond)
+    }
+
+    pub fn average(&self) -> Option<Milisecond> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let total: f64 = self.samples.iter().sum();
+        Some(Milisecond(total / self.samples.len() as f64))
+    }
+
+    /// Nearest-rank percentile, `percentile` is clamped between 0 and 100
+    pub fn percentile(&self, percentile: f64) -> Option<Milisecond> {
+        let sorted = self.sorted_samples();
+        percentile_of_sorted(&sorted, percentile)
+    }
+
+    /// Frames per second computed from the average frame time
+    pub fn fps(&self) -> Option<f64> {
+        match self.average() {
+            Some(average) if average.0 > 0.0 => Some(1000.0 / average.0),
+            _ => None,
+        }
+    }
+
+    pub fn summary(&self) -> Option<FrameStatsSummary> {
+        let sorted = self.sorted_samples();
+        if sorted.is_empty() {
+            return None;
+        }
+        let average = self.average().unwrap();
+        Some(FrameStatsSummary {
+            min: Milisecond(sorted[0]),
+            max: Milisecond(sorted[sorted.len() - 1]),
+            average,
+            p50: percentile_of_sorted(&sorted, 50.0).unwrap(),
+            p95: percentile_of_sorted(&sorted, 95.0).unwrap(),
+            p99: percentile_of_sorted(&sorted, 99.0).unwrap(),
+            fps: if average.0 > 0.0 { 1000.0 / average.0 } else { 0.0 },
+        })
+    }
+
+    fn sorted_samples(&self) -> Vec<f64> {
+        let mut sorted: Vec<f64> = self.samples.iter().copied().collect();
+        sorted.sort_by(f64::total_cmp);
+        sorted
+    }
+}
+
+fn percentile_of_sorted(sorted: &[f64], percentile: f64) -> Option<Milisecond> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let percentile = percentile.clamp(0.0, 100.0);
+    let rank = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+    let index = rank.max(1) - 1;
+    Some(Milisecond(sorted[index]))
+}
+
+/// Controls how the raw frame delta is turned into `Time::delta_time`
+#[derive(Clone, Copy)]
+pub struct DeltaTimeSettings {
+    /// Deltas above this value are clamped, avoiding huge steps after GC pauses or tab switches
+    pub max_delta: Option<Milisecond>,
+    /// Weight of the newest frame in an exponential moving average, between 0 and 1.\
+    /// `None` uses the clamped delta as is.
+    pub smoothing: Option<f64>,
+}
+
+impl DeltaTimeSettings {
+    pub const RAW: Self = Self {
+        max_delta: None,
+        smoothing: None,
+    };
+
+    pub fn clamped(max_delta: Milisecond) -> Self {
+        Self {
+            max_delta: Some(max_delta),
+            smoothing: None,
+        }
+    }
+}
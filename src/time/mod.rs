@@ -0,0 +1,131 @@
+pub mod timer;
+mod frame_stats;
+pub use frame_stats::*;
+mod test;
+
+#[derive(Clone, Copy)]
+pub struct Second(pub f64);
+#[derive(Clone, Copy)]
+pub struct Milisecond(pub f64);
+
+impl From<Milisecond> for Second{
+    fn from(time: Milisecond) -> Self {
+        Second(time.0 / 1000.0)
+    }
+}
+impl From<Second> for Milisecond{
+    fn from(time: Second) -> Self {
+        Milisecond(time.0 * 1000.0)
+    }
+}
+
+impl std::ops::Mul<f32> for Second{
+    type Output = f32;
+
+    fn mul(self, rhs: f32) -> Self::Output {
+        self.0 as f32 * rhs
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct FrameCount(pub u64);
+
+const DEFAULT_FRAME_STATS_WINDOW: usize = 120;
+
+pub struct Time {
+    pub prev_time: Option<Milisecond>,
+    pub time: Milisecond,
+    pub frame_count: FrameCount,
+    /// Frame delta after clamping and smoothing, `time` advances by this value
+    pub delta_time: Milisecond,
+    /// Frame delta as measured from the update timestamps
+    pub raw_delta_time: Milisecond,
+    pub delta_settings: DeltaTimeSettings,
+    pub frame_stats: FrameStats,
+    smoothed_delta: Option<f64>,
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Time {
+    pub fn new() -> Self {
+        Self::with_settings(DeltaTimeSettings::RAW)
+    }
+
+    pub fn with_settings(delta_settings: DeltaTimeSettings) -> Self {
+        Self {
+            prev_time: None,
+            time: Milisecond(0.0),
+            frame_count: FrameCount(0),
+            delta_time: Milisecond(0.0),
+            raw_delta_time: Milisecond(0.0),
+            delta_settings,
+            frame_stats: FrameStats::new(DEFAULT_FRAME_STATS_WINDOW),
+            smoothed_delta: None,
+        }
+    }
+
+    pub fn update(&mut self, time: Milisecond) {
+        match self.prev_time{
+            Some(p_time) => {
+                self.prev_time = Some(time);
+                let raw_delta_time = time.0 - p_time.0;
+                self.raw_delta_time = Milisecond(raw_delta_time);
+                self.frame_stats.push(self.raw_delta_time);
+
+                let delta_time = self.process_delta(raw_delta_time);
+                self.delta_time = Milisecond(delta_time);
+                self.time = Milisecond(self.time.0 + delta_time);
+                self.frame_count.0 += 1;
+            },
+            None => {
+                self.delta_time = Milisecond(0.0);
+                self.raw_delta_time = Milisecond(0.0);
+            },
+        }
+        self.prev_time = Some(time);
+    }
+
+    fn process_delta(&mut self, raw_delta_time: f64) -> f64 {
+        let mut delta_time = raw_delta_time.max(0.0);
+        if let Some(max_delta) = self.delta_settings.max_delta {
+            delta_time = delta_time.min(max_delta.0);
+        }
+        if let Some(smoothing) = self.delta_settings.smoothing {
+            let smoothing = smoothing.clamp(0.0, 1.0);
+            delta_time = match self.smoothed_delta {
+                Some(smoothed) => smoothed + (delta_time - smoothed) * smoothing,
+                None => delta_time,
+            };
+            self.smoothed_delta = Some(delta_time);
+        }
+        delta_time
+    }
+
+    /// The next update will have a zero delta, and the smoothing restarts from the frame after it.\
+    /// Should be called when the page is hidden so returning to it does not produce a giant step.
+    pub fn clear_time(&mut self){
+        self.prev_time = None;
+        self.smoothed_delta = None;
+    }
+
+    /// Same as `clear_time`, also dropping the frame statistics gathered so far
+    pub fn reset_frame_timing(&mut self) {
+        self.clear_time();
+        self.frame_stats.clear();
+    }
+
+    pub fn delta_time_seconds(&self) -> Second {
+        self.delta_time.into()
+    }
+    pub fn raw_delta_time_seconds(&self) -> Second {
+        self.raw_delta_time.into()
+    }
+    pub fn time_seconds(&self) -> Second {
+        self.time.into()
+    }
+}
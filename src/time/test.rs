@@ -0,0 +1,246 @@
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::super::timer::*;
+    use super::super::{DeltaTimeSettings, FrameCount, FrameStats, Milisecond, Second, Time};
+
+    /// Creates a time that already went through its first update, so the next update has a delta
+    fn started_time() -> Time {
+        let mut time = Time::new();
+        time.update(Milisecond(0.0));
+        time
+    }
+
+    fn counter_callback(counter: &Rc<Cell<u32>>) -> TimerCallback {
+        let counter = Rc::clone(counter);
+        Box::new(move || counter.set(counter.get() + 1))
+    }
+
+    #[test]
+    fn one_shot_timer_fires_once_after_delay() {
+        let mut time = started_time();
+        let mut scheduler = TimerScheduler::with_capacity(10);
+        let counter = Rc::new(Cell::new(0));
+        let key = scheduler
+            .once(TimerDelay::Seconds(Second(1.0)), counter_callback(&counter))
+            .unwrap();
+
+        time.update(Milisecond(500.0));
+        assert!(scheduler.update(&time).is_empty());
+        assert_eq!(counter.get(), 0);
+
+        time.update(Milisecond(1000.0));
+        let fired = scheduler.update(&time);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(counter.get(), 1);
+        assert!(!scheduler.is_active(key), "One shot timers are removed after firing");
+
+        time.update(Milisecond(2000.0));
+        scheduler.update(&time);
+        assert_eq!(counter.get(), 1);
+    }
+
+    #[test]
+    fn repeating_timer_fires_once_per_elapsed_interval() {
+        let mut time = started_time();
+        let mut scheduler = TimerScheduler::with_capacity(10);
+        let counter = Rc::new(Cell::new(0));
+        let key = scheduler
+            .repeating(TimerDelay::Seconds(Second(0.25)), counter_callback(&counter))
+            .unwrap();
+
+        time.update(Milisecond(300.0));
+        scheduler.update(&time);
+        assert_eq!(counter.get(), 1);
+
+        // A long frame covers several intervals
+        time.update(Milisecond(1000.0));
+        assert_eq!(scheduler.update(&time).len(), 3);
+        assert_eq!(counter.get(), 4);
+        assert!(scheduler.is_active(key));
+    }
+
+    #[test]
+    fn frame_timer_counts_frames_not_seconds() {
+        let mut time = started_time();
+        let mut scheduler = TimerScheduler::with_capacity(10);
+        let key = scheduler
+            .schedule(TimerDelay::Frames(FrameCount(3)), TimerMode::Once, None)
+            .unwrap();
+
+        for frame in 1..3 {
+            time.update(Milisecond(frame as f64 * 1000.0));
+            assert!(scheduler.update(&time).is_empty());
+        }
+        time.update(Milisecond(3000.0));
+        assert_eq!(scheduler.update(&time).len(), 1);
+        assert!(!scheduler.is_active(key));
+    }
+
+    #[test]
+    fn cancelled_timer_does_not_fire() {
+        let mut time = started_time();
+        let mut scheduler = TimerScheduler::with_capacity(10);
+        let counter = Rc::new(Cell::new(0));
+        let key = scheduler
+            .once(TimerDelay::Seconds(Second(0.1)), counter_callback(&counter))
+            .unwrap();
+
+        assert!(scheduler.cancel(key));
+        assert!(!scheduler.cancel(key), "A key can only be cancelled once");
+
+        time.update(Milisecond(1000.0));
+        scheduler.update(&time);
+        assert_eq!(counter.get(), 0);
+        assert!(scheduler.is_empty());
+    }
+
+    #[test]
+    fn paused_timer_keeps_remaining_time() {
+        let mut time = started_time();
+        let mut scheduler = TimerScheduler::with_capacity(10);
+        let key = scheduler
+            .schedule(TimerDelay::Seconds(Second(1.0)), TimerMode::Once, None)
+            .unwrap();
+
+        time.update(Milisecond(400.0));
+        scheduler.update(&time);
+        scheduler.pause(key);
+        time.update(Milisecond(5000.0));
+        scheduler.update(&time);
+
+        match scheduler.remaining(key) {
+            Some(TimerDelay::Seconds(remaining)) => assert!((remaining.0 - 0.6).abs() < 1e-9),
+            _ => panic!("Paused timer should still be active"),
+        }
+
+        scheduler.resume(key);
+        time.update(Milisecond(5600.0));
+        assert_eq!(scheduler.update(&time).len(), 1);
+    }
+
+    #[test]
+    fn full_scheduler_rejects_new_timers() {
+        let mut scheduler = TimerScheduler::with_capacity(2);
+        let delay = TimerDelay::Seconds(Second(1.0));
+        assert!(scheduler.schedule(delay, TimerMode::Once, None).is_some());
+        assert!(scheduler.schedule(delay, TimerMode::Once, None).is_some());
+        assert!(scheduler.schedule(delay, TimerMode::Once, None).is_none());
+    }
+
+    #[test]
+    fn sequence_runs_steps_in_order() {
+        let mut time = started_time();
+        let counter = Rc::new(Cell::new(0));
+        let flag = Rc::new(Cell::new(false));
+
+        let (c0, c1, f0) = (Rc::clone(&counter), Rc::clone(&counter), Rc::clone(&flag));
+        let mut sequence = Sequence::new()
+            .then(move || c0.set(c0.get() + 1))
+            .wait_seconds(1.0)
+            .then(move || c1.set(c1.get() + 10))
+            .wait_until(move || f0.get())
+            .wait_frames(2);
+
+        assert_eq!(sequence.poll(&time), SequenceState::Running);
+        assert_eq!(counter.get(), 1);
+
+        time.update(Milisecond(1200.0));
+        assert_eq!(sequence.poll(&time), SequenceState::Running);
+        assert_eq!(counter.get(), 11);
+
+        time.update(Milisecond(1300.0));
+        assert_eq!(sequence.poll(&time), SequenceState::Running);
+
+        flag.set(true);
+        time.update(Milisecond(1400.0));
+        assert_eq!(sequence.poll(&time), SequenceState::Running);
+        time.update(Milisecond(1500.0));
+        assert_eq!(sequence.poll(&time), SequenceState::Running);
+        time.update(Milisecond(1600.0));
+        assert_eq!(sequence.poll(&time), SequenceState::Finished);
+        assert!(sequence.is_finished());
+        assert_eq!(counter.get(), 11);
+    }
+
+    #[test]
+    fn frame_stats_keeps_only_the_last_frames() {
+        let mut stats = FrameStats::new(4);
+        for frame_time in [100.0, 10.0, 20.0, 30.0, 40.0] {
+            stats.push(Milisecond(frame_time));
+        }
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats.min().unwrap().0, 10.0);
+        assert_eq!(stats.max().unwrap().0, 40.0);
+        assert_eq!(stats.average().unwrap().0, 25.0);
+        assert_eq!(stats.fps().unwrap(), 40.0);
+    }
+
+    #[test]
+    fn frame_stats_percentiles_use_nearest_rank() {
+        let mut stats = FrameStats::new(100);
+        for frame_time in 1..=100 {
+            stats.push(Milisecond(frame_time as f64));
+        }
+        assert_eq!(stats.percentile(50.0).unwrap().0, 50.0);
+        assert_eq!(stats.percentile(95.0).unwrap().0, 95.0);
+        assert_eq!(stats.percentile(0.0).unwrap().0, 1.0);
+        assert_eq!(stats.percentile(100.0).unwrap().0, 100.0);
+
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.p99.0, 99.0);
+        assert_eq!(summary.average.0, 50.5);
+        assert!(FrameStats::new(10).summary().is_none());
+    }
+
+    #[test]
+    fn spikes_are_clamped_but_recorded_raw() {
+        let mut time = Time::with_settings(DeltaTimeSettings::clamped(Milisecond(100.0)));
+        time.update(Milisecond(0.0));
+        time.update(Milisecond(16.0));
+        assert_eq!(time.delta_time.0, 16.0);
+
+        time.update(Milisecond(2016.0));
+        assert_eq!(time.delta_time.0, 100.0);
+        assert_eq!(time.raw_delta_time.0, 2000.0);
+        assert_eq!(time.time.0, 116.0);
+        assert_eq!(time.frame_stats.max().unwrap().0, 2000.0);
+    }
+
+    #[test]
+    fn smoothing_blends_new_deltas() {
+        let mut time = Time::with_settings(DeltaTimeSettings {
+            max_delta: None,
+            smoothing: Some(0.5),
+        });
+        time.update(Milisecond(0.0));
+        time.update(Milisecond(10.0));
+        assert_eq!(time.delta_time.0, 10.0);
+        time.update(Milisecond(40.0));
+        assert_eq!(time.delta_time.0, 20.0);
+        assert_eq!(time.raw_delta_time.0, 30.0);
+    }
+
+    #[test]
+    fn clear_time_avoids_step_after_hidden_tab() {
+        let mut time = Time::with_settings(DeltaTimeSettings {
+            max_delta: None,
+            smoothing: Some(0.5),
+        });
+        time.update(Milisecond(0.0));
+        time.update(Milisecond(16.0));
+        time.clear_time();
+
+        time.update(Milisecond(60_000.0));
+        assert_eq!(time.delta_time.0, 0.0);
+        assert_eq!(time.frame_stats.len(), 1, "The cleared frame is not recorded");
+
+        time.update(Milisecond(60_030.0));
+        assert_eq!(time.delta_time.0, 30.0, "Smoothing restarts after clearing");
+        assert_eq!(time.time.0, 46.0);
+
+        time.reset_frame_timing();
+        assert!(time.frame_stats.is_empty());
+    }
+}
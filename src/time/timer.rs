@@ -0,0 +1,340 @@
+use crate::slotmap::prelude::*;
+
+use super::{FrameCount, Second, Time};
+
+crate::slotmap::prelude::create_custom_key!(TimerKey);
+
+#[derive(Clone, Copy)]
+pub enum TimerDelay {
+    Seconds(Second),
+    Frames(FrameCount),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum TimerMode {
+    Once,
+    Repeating,
+}
+
+pub type TimerCallback = Box<dyn FnMut()>;
+
+/// Progress made by the scheduler during a single `Time` update
+#[derive(Clone, Copy)]
+struct TimeStep {
+    seconds: f64,
+    frames: u64,
+}
+
+/// Frames elapsed since the last update. The first update only counts as a frame if `Time`
+/// already moved past its initial zero length frame.
+fn advanced_frames(last_frame: &mut Option<FrameCount>, time: &Time) -> u64 {
+    let frames = match last_frame {
+        Some(last_frame) => time.frame_count.0.saturating_sub(last_frame.0),
+        None => time.frame_count.0.min(1),
+    };
+    *last_frame = Some(time.frame_count);
+    frames
+}
+
+struct Timer {
+    key: Option<TimerKey>,
+    delay: TimerDelay,
+    mode: TimerMode,
+    elapsed_seconds: f64,
+    elapsed_frames: u64,
+    paused: bool,
+    finished: bool,
+    callback: Option<TimerCallback>,
+}
+
+impl Timer {
+    /// Advances the timer and returns how many times it fired during the step
+    fn advance(&mut self, step: TimeStep) -> u32 {
+        if self.paused || self.finished {
+            return 0;
+        }
+        let (elapsed, interval) = match self.delay {
+            TimerDelay::Seconds(delay) => {
+                self.elapsed_seconds += step.seconds;
+                (self.elapsed_seconds, delay.0)
+            }
+            TimerDelay::Frames(delay) => {
+                self.elapsed_frames += step.frames;
+                (self.elapsed_frames as f64, delay.0 as f64)
+            }
+        };
+        if elapsed < interval {
+            return 0;
+        }
+
+        match self.mode {
+            TimerMode::Once => {
+                self.finished = true;
+                1
+            }
+            // A zero length interval would fire forever, it fires once per update instead
+            TimerMode::Repeating if interval <= 0.0 => {
+                self.elapsed_seconds = 0.0;
+                self.elapsed_frames = 0;
+                1
+            }
+            TimerMode::Repeating => {
+                let fire_count = (elapsed / interval).floor() as u32;
+                match self.delay {
+                    TimerDelay::Seconds(_) => self.elapsed_seconds -= interval * fire_count as f64,
+                    TimerDelay::Frames(delay) => self.elapsed_frames -= delay.0 * fire_count as u64,
+                }
+                fire_count
+            }
+        }
+    }
+
+    fn remaining(&self) -> TimerDelay {
+        match self.delay {
+            TimerDelay::Seconds(delay) => {
+                TimerDelay::Seconds(Second((delay.0 - self.elapsed_seconds).max(0.0)))
+            }
+            TimerDelay::Frames(delay) => {
+                TimerDelay::Frames(FrameCount(delay.0.saturating_sub(self.elapsed_frames)))
+            }
+        }
+    }
+}
+
+/// Collection of one-shot and repeating timers advanced by `Time`.\
+/// Timers are referenced through a `TimerKey`, which becomes invalid once the timer is cancelled
+/// or a one-shot timer fires.
+pub struct TimerScheduler {
+    timers: Slotmap<Timer>,
+    last_frame: Option<FrameCount>,
+}
+
+impl TimerScheduler {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            timers: Slotmap::with_capacity(capacity),
+            last_frame: None,
+        }
+    }
+
+    /// Returns `None` if the scheduler is full
+    pub fn schedule(
+        &mut self,
+        delay: TimerDelay,
+        mode: TimerMode,
+        callback: Option<TimerCallback>,
+    ) -> Option<TimerKey> {
+        let timer = Timer {
+            key: None,
+            delay,
+            mode,
+            elapsed_seconds: 0.0,
+            elapsed_frames: 0,
+            paused: false,
+            finished: false,
+            callback,
+        };
+        let key = TimerKey(self.timers.push(timer)?);
+        self.timers.get_value_mut(&key).unwrap().key = Some(key);
+        Some(key)
+    }
+
+    pub fn once(&mut self, delay: TimerDelay, callback: TimerCallback) -> Option<TimerKey> {
+        self.schedule(delay, TimerMode::Once, Some(callback))
+    }
+
+    pub fn repeating(&mut self, interval: TimerDelay, callback: TimerCallback) -> Option<TimerKey> {
+        self.schedule(interval, TimerMode::Repeating, Some(callback))
+    }
+
+    /// Returns `false` if the key was not pointing to an active timer
+    pub fn cancel(&mut self, key: TimerKey) -> bool {
+        self.timers.remove(*key).is_some()
+    }
+
+    pub fn is_active(&self, key: TimerKey) -> bool {
+        self.timers.is_valid(&key)
+    }
+
+    pub fn pause(&mut self, key: TimerKey) {
+        if let Some(timer) = self.timers.get_value_mut(&key) {
+            timer.paused = true;
+        }
+    }
+
+    pub fn resume(&mut self, key: TimerKey) {
+        if let Some(timer) = self.timers.get_value_mut(&key) {
+            timer.paused = false;
+        }
+    }
+
+    /// Time left until the next time the timer fires
+    pub fn remaining(&self, key: TimerKey) -> Option<TimerDelay> {
+        self.timers.get_value(&key).map(|timer| timer.remaining())
+    }
+
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timers.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        let keys: Vec<TimerKey> = self.timers.get_iter().filter_map(|timer| timer.key).collect();
+        for key in keys {
+            self.timers.remove(*key);
+        }
+    }
+
+    /// Advances every timer, runs the callbacks of the ones that fired and returns their keys.\
+    /// A repeating timer that fired more than once during a long frame appears once per interval.
+    pub fn update(&mut self, time: &Time) -> Vec<TimerKey> {
+        let frames = advanced_frames(&mut self.last_frame, time);
+        let step = TimeStep {
+            seconds: time.delta_time_seconds().0,
+            frames,
+        };
+
+        let mut fired = Vec::new();
+        let mut finished = Vec::new();
+        for timer in self.timers.get_iter_mut() {
+            let key = timer.key.unwrap();
+            let fire_count = timer.advance(step);
+            for _ in 0..fire_count {
+                if let Some(callback) = timer.callback.as_mut() {
+                    callback();
+                }
+                fired.push(key);
+            }
+            if timer.finished {
+                finished.push(key);
+            }
+        }
+        for key in finished {
+            self.timers.remove(*key);
+        }
+        fired
+    }
+}
+
+pub enum SequenceStep {
+    Wait(TimerDelay),
+    WaitUntil(Box<dyn FnMut() -> bool>),
+    Run(Box<dyn FnMut()>),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SequenceState {
+    Running,
+    Finished,
+}
+
+/// Coroutine-like list of waits and actions, polled once per frame.\
+/// Consecutive `Run` steps and satisfied waits are processed in the same poll.
+pub struct Sequence {
+    steps: Vec<SequenceStep>,
+    current_step: usize,
+    elapsed_seconds: f64,
+    elapsed_frames: u64,
+    last_frame: Option<FrameCount>,
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sequence {
+    pub fn new() -> Self {
+        Self {
+            steps: Vec::new(),
+            current_step: 0,
+            elapsed_seconds: 0.0,
+            elapsed_frames: 0,
+            last_frame: None,
+        }
+    }
+
+    pub fn wait_seconds(mut self, seconds: f64) -> Self {
+        self.steps.push(SequenceStep::Wait(TimerDelay::Seconds(Second(seconds))));
+        self
+    }
+
+    pub fn wait_frames(mut self, frames: u64) -> Self {
+        self.steps.push(SequenceStep::Wait(TimerDelay::Frames(FrameCount(frames))));
+        self
+    }
+
+    pub fn wait_until<F: FnMut() -> bool + 'static>(mut self, condition: F) -> Self {
+        self.steps.push(SequenceStep::WaitUntil(Box::new(condition)));
+        self
+    }
+
+    pub fn then<F: FnMut() + 'static>(mut self, action: F) -> Self {
+        self.steps.push(SequenceStep::Run(Box::new(action)));
+        self
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.current_step >= self.steps.len()
+    }
+
+    pub fn restart(&mut self) {
+        self.current_step = 0;
+        self.elapsed_seconds = 0.0;
+        self.elapsed_frames = 0;
+        self.last_frame = None;
+    }
+
+    pub fn poll(&mut self, time: &Time) -> SequenceState {
+        let frames = advanced_frames(&mut self.last_frame, time);
+        self.elapsed_seconds += time.delta_time_seconds().0;
+        self.elapsed_frames += frames;
+
+        while self.current_step < self.steps.len() {
+            let step_done = match &mut self.steps[self.current_step] {
+                SequenceStep::Wait(TimerDelay::Seconds(delay)) => {
+                    if self.elapsed_seconds >= delay.0 {
+                        // The time left over is carried to the next wait
+                        self.elapsed_seconds -= delay.0;
+                        self.elapsed_frames = 0;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                SequenceStep::Wait(TimerDelay::Frames(delay)) => {
+                    if self.elapsed_frames >= delay.0 {
+                        self.elapsed_frames -= delay.0;
+                        self.elapsed_seconds = 0.0;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                SequenceStep::WaitUntil(condition) => {
+                    if condition() {
+                        // Waits after a condition start counting from the moment it was satisfied
+                        self.elapsed_seconds = 0.0;
+                        self.elapsed_frames = 0;
+                        true
+                    } else {
+                        false
+                    }
+                }
+                SequenceStep::Run(action) => {
+                    action();
+                    true
+                }
+            };
+            if !step_done {
+                return SequenceState::Running;
+            }
+            self.current_step += 1;
+        }
+        SequenceState::Finished
+    }
+}
@@ -0,0 +1,136 @@
+use glam::*;
+
+use crate::{Orientable, Scalable, Translatable};
+
+/// Translation, rotation and scale decomposition of an affine transform
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE,
+    };
+
+    pub fn new(translation: Vec3, rotation: Quat, scale: Vec3) -> Self {
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn from_translation(translation: Vec3) -> Self {
+        Self {
+            translation,
+            ..Self::IDENTITY
+        }
+    }
+
+    pub fn from_rotation(rotation: Quat) -> Self {
+        Self {
+            rotation,
+            ..Self::IDENTITY
+        }
+    }
+
+    /// Shear in the matrix is lost during the decomposition
+    pub fn from_mat4(matrix: Mat4) -> Self {
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        Self {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    pub fn to_mat4(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+
+    /// Component-wise interpolation, the rotation is interpolated with `slerp`
+    pub fn lerp(&self, other: &Transform, t: f32) -> Transform {
+        Transform {
+            translation: self.translation.lerp(other.translation, t),
+            rotation: self.rotation.slerp(other.rotation, t),
+            scale: self.scale.lerp(other.scale, t),
+        }
+    }
+
+    /// Applies `child` in the space of `self`, uniform scale is assumed for an exact result
+    pub fn mul_transform(&self, child: &Transform) -> Transform {
+        Transform {
+            translation: self.transform_point(child.translation),
+            rotation: self.rotation * child.rotation,
+            scale: self.scale * child.scale,
+        }
+    }
+
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        self.rotation * (self.scale * point) + self.translation
+    }
+
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        self.rotation * (self.scale * vector)
+    }
+
+    pub fn abs_diff_eq(&self, other: &Transform, max_abs_diff: f32) -> bool {
+        self.translation.abs_diff_eq(other.translation, max_abs_diff)
+            && (self.rotation.abs_diff_eq(other.rotation, max_abs_diff)
+                || self.rotation.abs_diff_eq(-other.rotation, max_abs_diff))
+            && self.scale.abs_diff_eq(other.scale, max_abs_diff)
+    }
+}
+
+impl Translatable for Transform {
+    fn translate(&mut self, translate: Vec3) {
+        self.translation += translate;
+    }
+
+    fn set_position(&mut self, position: Vec3) {
+        self.translation = position;
+    }
+
+    fn get_position(&self) -> Vec3 {
+        self.translation
+    }
+}
+
+impl Orientable for Transform {
+    fn rotate(&mut self, rotation: Quat) {
+        self.rotation = (rotation * self.rotation).normalize();
+    }
+
+    fn set_orientation(&mut self, orientation: Quat) {
+        self.rotation = orientation;
+    }
+
+    fn get_orientation(&self) -> Quat {
+        self.rotation
+    }
+}
+
+impl Scalable for Transform {
+    fn scale(&mut self, scale: Vec3) {
+        self.scale *= scale;
+    }
+
+    fn set_scale(&mut self, scale: Vec3) {
+        self.scale = scale;
+    }
+
+    fn get_scale(&self) -> Vec3 {
+        self.scale
+    }
+}
@@ -0,0 +1,210 @@
+use std::f32::consts::PI;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    SineIn,
+    SineOut,
+    SineInOut,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+}
+
+impl Easing {
+    /// Maps the linear progress `t` (clamped between 0 and 1) to the eased progress.\
+    /// Elastic and back curves overshoot, so the result can be outside of the 0 to 1 range.
+    pub fn apply(&self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::SineIn => sine_in(t),
+            Easing::SineOut => sine_out(t),
+            Easing::SineInOut => sine_in_out(t),
+            Easing::QuadIn => quad_in(t),
+            Easing::QuadOut => quad_out(t),
+            Easing::QuadInOut => quad_in_out(t),
+            Easing::CubicIn => cubic_in(t),
+            Easing::CubicOut => cubic_out(t),
+            Easing::CubicInOut => cubic_in_out(t),
+            Easing::ExpoIn => expo_in(t),
+            Easing::ExpoOut => expo_out(t),
+            Easing::ExpoInOut => expo_in_out(t),
+            Easing::ElasticIn => elastic_in(t),
+            Easing::ElasticOut => elastic_out(t),
+            Easing::ElasticInOut => elastic_in_out(t),
+            Easing::BounceIn => bounce_in(t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => bounce_in_out(t),
+            Easing::BackIn => back_in(t),
+            Easing::BackOut => back_out(t),
+            Easing::BackInOut => back_in_out(t),
+        }
+    }
+}
+
+const BACK_C1: f32 = 1.70158;
+const BACK_C2: f32 = BACK_C1 * 1.525;
+const BACK_C3: f32 = BACK_C1 + 1.0;
+const ELASTIC_C4: f32 = (2.0 * PI) / 3.0;
+const ELASTIC_C5: f32 = (2.0 * PI) / 4.5;
+
+pub fn sine_in(t: f32) -> f32 {
+    1.0 - f32::cos((t * PI) / 2.0)
+}
+
+pub fn sine_out(t: f32) -> f32 {
+    f32::sin((t * PI) / 2.0)
+}
+
+pub fn sine_in_out(t: f32) -> f32 {
+    -(f32::cos(PI * t) - 1.0) / 2.0
+}
+
+pub fn quad_in(t: f32) -> f32 {
+    t * t
+}
+
+pub fn quad_out(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+pub fn quad_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+pub fn cubic_in(t: f32) -> f32 {
+    t * t * t
+}
+
+pub fn cubic_out(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+pub fn cubic_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+pub fn expo_in(t: f32) -> f32 {
+    if t == 0.0 {
+        0.0
+    } else {
+        f32::powf(2.0, 10.0 * t - 10.0)
+    }
+}
+
+pub fn expo_out(t: f32) -> f32 {
+    if t == 1.0 {
+        1.0
+    } else {
+        1.0 - f32::powf(2.0, -10.0 * t)
+    }
+}
+
+pub fn expo_in_out(t: f32) -> f32 {
+    if t == 0.0 {
+        0.0
+    } else if t == 1.0 {
+        1.0
+    } else if t < 0.5 {
+        f32::powf(2.0, 20.0 * t - 10.0) / 2.0
+    } else {
+        (2.0 - f32::powf(2.0, -20.0 * t + 10.0)) / 2.0
+    }
+}
+
+pub fn elastic_in(t: f32) -> f32 {
+    if t == 0.0 || t == 1.0 {
+        t
+    } else {
+        -f32::powf(2.0, 10.0 * t - 10.0) * f32::sin((t * 10.0 - 10.75) * ELASTIC_C4)
+    }
+}
+
+pub fn elastic_out(t: f32) -> f32 {
+    if t == 0.0 || t == 1.0 {
+        t
+    } else {
+        f32::powf(2.0, -10.0 * t) * f32::sin((t * 10.0 - 0.75) * ELASTIC_C4) + 1.0
+    }
+}
+
+pub fn elastic_in_out(t: f32) -> f32 {
+    if t == 0.0 || t == 1.0 {
+        t
+    } else if t < 0.5 {
+        -(f32::powf(2.0, 20.0 * t - 10.0) * f32::sin((20.0 * t - 11.125) * ELASTIC_C5)) / 2.0
+    } else {
+        (f32::powf(2.0, -20.0 * t + 10.0) * f32::sin((20.0 * t - 11.125) * ELASTIC_C5)) / 2.0 + 1.0
+    }
+}
+
+pub fn bounce_out(t: f32) -> f32 {
+    const N1: f32 = 7.5625;
+    const D1: f32 = 2.75;
+    if t < 1.0 / D1 {
+        N1 * t * t
+    } else if t < 2.0 / D1 {
+        let t = t - 1.5 / D1;
+        N1 * t * t + 0.75
+    } else if t < 2.5 / D1 {
+        let t = t - 2.25 / D1;
+        N1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D1;
+        N1 * t * t + 0.984375
+    }
+}
+
+pub fn bounce_in(t: f32) -> f32 {
+    1.0 - bounce_out(1.0 - t)
+}
+
+pub fn bounce_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0
+    } else {
+        (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0
+    }
+}
+
+pub fn back_in(t: f32) -> f32 {
+    BACK_C3 * t * t * t - BACK_C1 * t * t
+}
+
+pub fn back_out(t: f32) -> f32 {
+    let t = t - 1.0;
+    1.0 + BACK_C3 * t.powi(3) + BACK_C1 * t.powi(2)
+}
+
+pub fn back_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        ((2.0 * t).powi(2) * ((BACK_C2 + 1.0) * 2.0 * t - BACK_C2)) / 2.0
+    } else {
+        ((2.0 * t - 2.0).powi(2) * ((BACK_C2 + 1.0) * (t * 2.0 - 2.0) + BACK_C2) + 2.0) / 2.0
+    }
+}
@@ -0,0 +1,174 @@
+use super::{TweenLoop, TweenPlayer};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GroupKind {
+    Sequence,
+    Parallel,
+}
+
+/// Plays a list of tweens one after another (`TweenGroup::sequence`) or all at the same time
+/// (`TweenGroup::parallel`). Groups can be nested since they are players themselves.
+pub struct TweenGroup {
+    kind: GroupKind,
+    players: Vec<Box<dyn TweenPlayer>>,
+    current_player: usize,
+    loop_mode: TweenLoop,
+    completed_plays: u32,
+    finished: bool,
+    on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl TweenGroup {
+    fn new(kind: GroupKind) -> Self {
+        Self {
+            kind,
+            players: Vec::new(),
+            current_player: 0,
+            loop_mode: TweenLoop::Once,
+            completed_plays: 0,
+            finished: false,
+            on_complete: None,
+        }
+    }
+
+    pub fn sequence() -> Self {
+        Self::new(GroupKind::Sequence)
+    }
+
+    pub fn parallel() -> Self {
+        Self::new(GroupKind::Parallel)
+    }
+
+    pub fn with<P: TweenPlayer + 'static>(mut self, player: P) -> Self {
+        self.players.push(Box::new(player));
+        self
+    }
+
+    pub fn push(&mut self, player: Box<dyn TweenPlayer>) {
+        self.players.push(player);
+    }
+
+    pub fn with_loop(mut self, loop_mode: TweenLoop) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    pub fn on_complete<F: FnMut() + 'static>(mut self, callback: F) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.players.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.players.is_empty()
+    }
+
+    /// Plays the group once and returns the time left over if it finished
+    fn advance_play(&mut self, delta: f64) -> Option<f64> {
+        match self.kind {
+            GroupKind::Sequence => {
+                let mut delta = delta;
+                while self.current_player < self.players.len() {
+                    let left_over = self.players[self.current_player].advance(delta);
+                    if !self.players[self.current_player].is_finished() {
+                        return None;
+                    }
+                    delta = left_over;
+                    self.current_player += 1;
+                }
+                Some(delta)
+            }
+            GroupKind::Parallel => {
+                // The group ends with its longest player, which has the smallest time left over
+                let mut all_finished = true;
+                let mut min_left_over = delta;
+                for player in self.players.iter_mut() {
+                    if player.is_finished() {
+                        continue;
+                    }
+                    let left_over = player.advance(delta);
+                    if player.is_finished() {
+                        min_left_over = min_left_over.min(left_over);
+                    } else {
+                        all_finished = false;
+                    }
+                }
+                if all_finished {
+                    Some(min_left_over)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    fn reset_players(&mut self) {
+        self.current_player = 0;
+        for player in self.players.iter_mut() {
+            player.reset();
+        }
+    }
+}
+
+impl TweenPlayer for TweenGroup {
+    fn advance(&mut self, delta: f64) -> f64 {
+        if self.finished {
+            return delta;
+        }
+        let mut delta = delta.max(0.0);
+        loop {
+            let left_over = match self.advance_play(delta) {
+                Some(left_over) => left_over,
+                None => return 0.0,
+            };
+            self.completed_plays += 1;
+            let has_more_plays = match self.loop_mode {
+                TweenLoop::Once => false,
+                TweenLoop::Count(count) => self.completed_plays < count,
+                TweenLoop::Infinite => true,
+            };
+            if !has_more_plays {
+                self.finished = true;
+                if let Some(on_complete) = self.on_complete.as_mut() {
+                    on_complete();
+                }
+                return left_over;
+            }
+            self.reset_players();
+            // A play without length would loop forever, the next play starts on the next advance
+            if left_over <= 0.0 || left_over >= delta {
+                return 0.0;
+            }
+            delta = left_over;
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.reset_players();
+        self.completed_plays = 0;
+        self.finished = false;
+    }
+
+    fn total_duration(&self) -> Option<f64> {
+        let mut play_duration = 0.0;
+        for player in self.players.iter() {
+            let duration = player.total_duration()?;
+            play_duration = match self.kind {
+                GroupKind::Sequence => play_duration + duration,
+                GroupKind::Parallel => f64::max(play_duration, duration),
+            };
+        }
+        match self.loop_mode {
+            TweenLoop::Once => Some(play_duration),
+            TweenLoop::Count(count) => Some(play_duration * count.max(1) as f64),
+            TweenLoop::Infinite => None,
+        }
+    }
+}
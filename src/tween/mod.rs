@@ -0,0 +1,233 @@
+use glam::*;
+use rust_webgl2::RGBA;
+
+use crate::math::f32_lerp;
+use crate::time::Time;
+
+mod easing;
+pub use easing::*;
+mod group;
+pub use group::*;
+mod test;
+
+pub trait Tweenable: Clone {
+    fn tween_lerp(from: &Self, to: &Self, t: f32) -> Self;
+}
+
+impl Tweenable for f32 {
+    fn tween_lerp(from: &Self, to: &Self, t: f32) -> Self {
+        f32_lerp(*from, *to, t)
+    }
+}
+
+impl Tweenable for Vec2 {
+    fn tween_lerp(from: &Self, to: &Self, t: f32) -> Self {
+        from.lerp(*to, t)
+    }
+}
+
+impl Tweenable for Vec3 {
+    fn tween_lerp(from: &Self, to: &Self, t: f32) -> Self {
+        from.lerp(*to, t)
+    }
+}
+
+impl Tweenable for Vec4 {
+    fn tween_lerp(from: &Self, to: &Self, t: f32) -> Self {
+        from.lerp(*to, t)
+    }
+}
+
+impl Tweenable for Quat {
+    fn tween_lerp(from: &Self, to: &Self, t: f32) -> Self {
+        from.slerp(*to, t)
+    }
+}
+
+impl Tweenable for RGBA {
+    fn tween_lerp(from: &Self, to: &Self, t: f32) -> Self {
+        RGBA::new(
+            f32_lerp(from.r, to.r, t),
+            f32_lerp(from.g, to.g, t),
+            f32_lerp(from.b, to.b, t),
+            f32_lerp(from.a, to.a, t),
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TweenLoop {
+    Once,
+    /// Total amount of plays, including the first one
+    Count(u32),
+    Infinite,
+}
+
+/// Anything that can be played over time, single tweens as well as groups of them
+pub trait TweenPlayer {
+    /// Advances the playback by `delta` seconds and returns the time left over if the player
+    /// finished during this step
+    fn advance(&mut self, delta: f64) -> f64;
+    fn is_finished(&self) -> bool;
+    fn reset(&mut self);
+    /// `None` for players that never finish
+    fn total_duration(&self) -> Option<f64>;
+
+    fn update(&mut self, time: &Time) {
+        self.advance(time.delta_time_seconds().0);
+    }
+}
+
+pub type TweenUpdateCallback<T> = Box<dyn FnMut(&T)>;
+
+pub struct Tween<T: Tweenable> {
+    pub from: T,
+    pub to: T,
+    /// Length of a single play in seconds
+    pub duration: f64,
+    pub delay: f64,
+    pub easing: Easing,
+    pub loop_mode: TweenLoop,
+    /// Every other play goes from `to` back to `from`
+    pub yoyo: bool,
+    elapsed: f64,
+    value: T,
+    finished: bool,
+    on_update: Option<TweenUpdateCallback<T>>,
+    on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl<T: Tweenable> Tween<T> {
+    pub fn new(from: T, to: T, duration: f64) -> Self {
+        Self {
+            value: from.clone(),
+            from,
+            to,
+            duration: duration.max(0.0),
+            delay: 0.0,
+            easing: Easing::Linear,
+            loop_mode: TweenLoop::Once,
+            yoyo: false,
+            elapsed: 0.0,
+            finished: false,
+            on_update: None,
+            on_complete: None,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: Easing) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_delay(mut self, delay: f64) -> Self {
+        self.delay = delay.max(0.0);
+        self
+    }
+
+    pub fn with_loop(mut self, loop_mode: TweenLoop) -> Self {
+        self.loop_mode = loop_mode;
+        self
+    }
+
+    pub fn with_yoyo(mut self, yoyo: bool) -> Self {
+        self.yoyo = yoyo;
+        self
+    }
+
+    /// Called with the new value every time the tween advances
+    pub fn on_update<F: FnMut(&T) + 'static>(mut self, callback: F) -> Self {
+        self.on_update = Some(Box::new(callback));
+        self
+    }
+
+    pub fn on_complete<F: FnMut() + 'static>(mut self, callback: F) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Seconds played so far, including the delay
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    fn play_count(&self) -> Option<u32> {
+        match self.loop_mode {
+            TweenLoop::Once => Some(1),
+            TweenLoop::Count(count) => Some(count.max(1)),
+            TweenLoop::Infinite => None,
+        }
+    }
+
+    /// Linear progress of the current play, taking the yoyo direction into account
+    fn progress(&self) -> f32 {
+        let play_time = self.elapsed - self.delay;
+        if play_time <= 0.0 {
+            return 0.0;
+        }
+        if self.duration <= 0.0 {
+            return match self.play_count() {
+                Some(count) if self.yoyo && count % 2 == 0 => 0.0,
+                _ => 1.0,
+            };
+        }
+        let mut play_index = (play_time / self.duration).floor();
+        let mut play_progress = (play_time - play_index * self.duration) / self.duration;
+        if let Some(count) = self.play_count() {
+            if play_index >= count as f64 {
+                play_index = (count - 1) as f64;
+                play_progress = 1.0;
+            }
+        }
+        let reversed = self.yoyo && (play_index as u64) % 2 == 1;
+        if reversed {
+            (1.0 - play_progress) as f32
+        } else {
+            play_progress as f32
+        }
+    }
+}
+
+impl<T: Tweenable> TweenPlayer for Tween<T> {
+    fn advance(&mut self, delta: f64) -> f64 {
+        if self.finished {
+            return delta;
+        }
+        self.elapsed += delta.max(0.0);
+
+        self.value = T::tween_lerp(&self.from, &self.to, self.easing.apply(self.progress()));
+        if let Some(on_update) = self.on_update.as_mut() {
+            on_update(&self.value);
+        }
+
+        match self.total_duration() {
+            Some(total_duration) if self.elapsed >= total_duration => {
+                self.finished = true;
+                if let Some(on_complete) = self.on_complete.as_mut() {
+                    on_complete();
+                }
+                self.elapsed - total_duration
+            }
+            _ => 0.0,
+        }
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+        self.value = self.from.clone();
+    }
+
+    fn total_duration(&self) -> Option<f64> {
+        self.play_count()
+            .map(|count| self.delay + self.duration * count as f64)
+    }
+}
@@ -0,0 +1,182 @@
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use glam::*;
+    use rust_webgl2::RGBA;
+
+    use super::super::*;
+
+    const EASINGS: [Easing; 22] = [
+        Easing::Linear,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::ExpoIn,
+        Easing::ExpoOut,
+        Easing::ExpoInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+    ];
+
+    #[test]
+    fn every_easing_starts_at_zero_and_ends_at_one() {
+        for easing in EASINGS {
+            assert!(easing.apply(0.0).abs() < 1e-4, "{:?} does not start at 0", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-4, "{:?} does not end at 1", easing);
+        }
+    }
+
+    #[test]
+    fn in_out_easings_are_symmetric() {
+        for easing in [Easing::QuadInOut, Easing::CubicInOut, Easing::SineInOut, Easing::BounceInOut] {
+            assert!((easing.apply(0.5) - 0.5).abs() < 1e-4, "{:?} is not centered", easing);
+            let a = easing.apply(0.25);
+            let b = easing.apply(0.75);
+            assert!((a + b - 1.0).abs() < 1e-4, "{:?} is not symmetric", easing);
+        }
+    }
+
+    #[test]
+    fn back_easing_overshoots() {
+        assert!(Easing::BackIn.apply(0.2) < 0.0);
+        assert!(Easing::BackOut.apply(0.8) > 1.0);
+        assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+    }
+
+    #[test]
+    fn tween_interpolates_and_completes() {
+        let completed = Rc::new(Cell::new(false));
+        let completed_ref = Rc::clone(&completed);
+        let mut tween = Tween::new(0.0_f32, 10.0, 2.0).on_complete(move || completed_ref.set(true));
+
+        assert_eq!(tween.advance(1.0), 0.0);
+        assert_eq!(*tween.value(), 5.0);
+        assert!(!completed.get());
+
+        let left_over = tween.advance(1.5);
+        assert_eq!(*tween.value(), 10.0);
+        assert!(tween.is_finished());
+        assert!(completed.get());
+        assert_eq!(left_over, 0.5);
+    }
+
+    #[test]
+    fn tween_waits_for_delay() {
+        let mut tween = Tween::new(Vec2::ZERO, Vec2::ONE, 1.0).with_delay(0.5);
+        tween.advance(0.4);
+        assert_eq!(*tween.value(), Vec2::ZERO);
+        tween.advance(0.6);
+        assert_eq!(*tween.value(), Vec2::splat(0.5));
+        assert_eq!(tween.total_duration(), Some(1.5));
+    }
+
+    #[test]
+    fn yoyo_tween_returns_to_start() {
+        let mut tween = Tween::new(0.0_f32, 1.0, 1.0)
+            .with_loop(TweenLoop::Count(2))
+            .with_yoyo(true);
+        tween.advance(0.5);
+        assert_eq!(*tween.value(), 0.5);
+        tween.advance(0.75);
+        assert_eq!(*tween.value(), 0.75);
+        tween.advance(1.0);
+        assert!(tween.is_finished());
+        assert_eq!(*tween.value(), 0.0);
+    }
+
+    #[test]
+    fn infinite_tween_never_finishes() {
+        let mut tween = Tween::new(Vec3::ZERO, Vec3::X, 1.0).with_loop(TweenLoop::Infinite);
+        tween.advance(10.25);
+        assert!(!tween.is_finished());
+        assert!((tween.value().x - 0.25).abs() < 1e-5);
+        assert_eq!(tween.total_duration(), None);
+    }
+
+    #[test]
+    fn quat_and_color_tweens() {
+        let target = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        let mut rotation = Tween::new(Quat::IDENTITY, target, 1.0);
+        rotation.advance(0.5);
+        let expected = Quat::from_rotation_y(std::f32::consts::FRAC_PI_4);
+        assert!(rotation.value().abs_diff_eq(expected, 1e-5));
+
+        let mut color = Tween::new(RGBA::BLACK, RGBA::WHITE, 1.0);
+        color.advance(0.5);
+        assert_eq!(color.value().r, 0.5);
+        assert_eq!(color.value().a, 1.0);
+    }
+
+    #[test]
+    fn update_callback_receives_the_values() {
+        let last_value = Rc::new(Cell::new(0.0_f32));
+        let last_value_ref = Rc::clone(&last_value);
+        let mut tween = Tween::new(0.0_f32, 4.0, 1.0).on_update(move |v| last_value_ref.set(*v));
+        tween.advance(0.25);
+        assert_eq!(last_value.get(), 1.0);
+    }
+
+    #[test]
+    fn sequence_carries_left_over_time() {
+        let value_a = Rc::new(Cell::new(0.0_f32));
+        let value_b = Rc::new(Cell::new(0.0_f32));
+        let (a_ref, b_ref) = (Rc::clone(&value_a), Rc::clone(&value_b));
+        let mut sequence = TweenGroup::sequence()
+            .with(Tween::new(0.0_f32, 1.0, 1.0).on_update(move |v| a_ref.set(*v)))
+            .with(Tween::new(0.0_f32, 1.0, 1.0).on_update(move |v| b_ref.set(*v)));
+
+        assert_eq!(sequence.total_duration(), Some(2.0));
+        sequence.advance(1.5);
+        assert_eq!(value_a.get(), 1.0);
+        assert_eq!(value_b.get(), 0.5);
+        assert!(!sequence.is_finished());
+        assert_eq!(sequence.advance(1.0), 0.5);
+        assert!(sequence.is_finished());
+    }
+
+    #[test]
+    fn parallel_group_finishes_with_longest_tween() {
+        let completed = Rc::new(Cell::new(0));
+        let completed_ref = Rc::clone(&completed);
+        let mut parallel = TweenGroup::parallel()
+            .with(Tween::new(0.0_f32, 1.0, 1.0))
+            .with(Tween::new(0.0_f32, 1.0, 3.0))
+            .on_complete(move || completed_ref.set(completed_ref.get() + 1));
+
+        assert_eq!(parallel.total_duration(), Some(3.0));
+        parallel.advance(2.0);
+        assert!(!parallel.is_finished());
+        assert_eq!(parallel.advance(1.5), 0.5);
+        assert!(parallel.is_finished());
+        assert_eq!(completed.get(), 1);
+    }
+
+    #[test]
+    fn looping_group_restarts_players() {
+        let value = Rc::new(Cell::new(0.0_f32));
+        let value_ref = Rc::clone(&value);
+        let mut group = TweenGroup::sequence()
+            .with(Tween::new(0.0_f32, 1.0, 1.0).on_update(move |v| value_ref.set(*v)))
+            .with_loop(TweenLoop::Count(3));
+
+        group.advance(2.25);
+        assert_eq!(value.get(), 0.25);
+        assert!(!group.is_finished());
+        group.advance(1.0);
+        assert!(group.is_finished());
+    }
+}
@@ -0,0 +1,183 @@
+use glam::*;
+
+use super::XrFrameSource;
+use crate::collision::Ray;
+use crate::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Handedness {
+    Left,
+    Right,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TargetRayMode {
+    /// Head gaze, e.g. cardboard style viewers
+    Gaze,
+    /// Controllers and hands
+    TrackedPointer,
+    /// Taps on a phone screen
+    Screen,
+}
+
+/// Buttons of the `xr-standard` gamepad mapping
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XrButton {
+    Trigger,
+    Squeeze,
+    Touchpad,
+    Thumbstick,
+    /// A or X
+    Primary,
+    /// B or Y
+    Secondary,
+}
+
+impl XrButton {
+    pub fn index(&self) -> usize {
+        match self {
+            XrButton::Trigger => 0,
+            XrButton::Squeeze => 1,
+            XrButton::Touchpad => 2,
+            XrButton::Thumbstick => 3,
+            XrButton::Primary => 4,
+            XrButton::Secondary => 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct XrButtonState {
+    pub pressed: bool,
+    pub touched: bool,
+    /// Between 0 and 1, analog triggers report partial presses
+    pub value: f32,
+}
+
+/// State of a controller, hand or gaze input during one frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct XrInputState {
+    pub handedness: Handedness,
+    pub target_ray_mode: TargetRayMode,
+    /// Pointing pose, looks down -Z like a camera
+    pub target_ray: Option<Transform>,
+    /// Pose to place a held object, `None` for inputs that can't hold anything
+    pub grip: Option<Transform>,
+    /// Indexed following the `xr-standard` mapping, see `XrButton`
+    pub buttons: Vec<XrButtonState>,
+    pub axes: Vec<f32>,
+}
+
+impl XrInputState {
+    pub fn new(handedness: Handedness, target_ray_mode: TargetRayMode) -> Self {
+        Self {
+            handedness,
+            target_ray_mode,
+            target_ray: None,
+            grip: None,
+            buttons: Vec::new(),
+            axes: Vec::new(),
+        }
+    }
+
+    pub fn button(&self, button: XrButton) -> XrButtonState {
+        self.buttons
+            .get(button.index())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn axes_pair(&self, index: usize) -> Vec2 {
+        match self.axes.get(index..index + 2) {
+            Some(axes) => vec2(axes[0], axes[1]),
+            None => Vec2::ZERO,
+        }
+    }
+
+    pub fn touchpad(&self) -> Vec2 {
+        self.axes_pair(0)
+    }
+
+    /// Positive y is pulling the stick towards the user, as reported by the gamepad
+    pub fn thumbstick(&self) -> Vec2 {
+        self.axes_pair(2)
+    }
+
+    /// World ray of the target ray pose, `origin` places the reference space in the world
+    pub fn pointer_ray(&self, origin: &Transform) -> Option<Ray> {
+        let target_ray = origin.mul_transform(&self.target_ray?);
+        Some(Ray {
+            position: target_ray.translation,
+            direction: (target_ray.rotation * Vec3::NEG_Z).normalize(),
+            distance: None,
+        })
+    }
+}
+
+/// Input sources of the current and previous frames, used to detect presses and releases
+#[derive(Debug, Clone, Default)]
+pub struct XrInput {
+    sources: Vec<XrInputState>,
+    previous_sources: Vec<XrInputState>,
+}
+
+impl XrInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update<S: XrFrameSource>(&mut self, frame: &S) {
+        self.previous_sources = std::mem::replace(&mut self.sources, frame.input_sources());
+    }
+
+    pub fn sources(&self) -> &[XrInputState] {
+        &self.sources
+    }
+
+    /// First tracked source held in the hand, gaze and screen inputs report no handedness
+    pub fn source(&self, handedness: Handedness) -> Option<&XrInputState> {
+        self.sources
+            .iter()
+            .find(|source| source.handedness == handedness)
+    }
+
+    fn previous_source(&self, handedness: Handedness) -> Option<&XrInputState> {
+        self.previous_sources
+            .iter()
+            .find(|source| source.handedness == handedness)
+    }
+
+    pub fn grip(&self, handedness: Handedness) -> Option<Transform> {
+        self.source(handedness)?.grip
+    }
+
+    pub fn is_pressed(&self, handedness: Handedness, button: XrButton) -> bool {
+        self.source(handedness)
+            .is_some_and(|source| source.button(button).pressed)
+    }
+
+    fn was_pressed(&self, handedness: Handedness, button: XrButton) -> bool {
+        self.previous_source(handedness)
+            .is_some_and(|source| source.button(button).pressed)
+    }
+
+    pub fn just_pressed(&self, handedness: Handedness, button: XrButton) -> bool {
+        self.is_pressed(handedness, button) && !self.was_pressed(handedness, button)
+    }
+
+    /// Also true when the source is disconnected while the button is held
+    pub fn just_released(&self, handedness: Handedness, button: XrButton) -> bool {
+        !self.is_pressed(handedness, button) && self.was_pressed(handedness, button)
+    }
+
+    pub fn button_value(&self, handedness: Handedness, button: XrButton) -> f32 {
+        self.source(handedness)
+            .map_or(0.0, |source| source.button(button).value)
+    }
+
+    pub fn thumbstick(&self, handedness: Handedness) -> Vec2 {
+        self.source(handedness)
+            .map_or(Vec2::ZERO, |source| source.thumbstick())
+    }
+}
@@ -0,0 +1,272 @@
+use glam::*;
+
+use super::XrViewSample;
+use crate::collision::{ray_to_plane_intersection, Plane, Ray};
+use crate::time::Time;
+use crate::transform::Transform;
+
+// The locomotion moves the origin of the reference space in the world, a generalization of the
+// `camera_offset` of `XrCamera` that can also turn. Views are placed with
+// `views_to_view_data(&views, &origin)`.
+
+/// Head position in the reference space, the middle point of the views
+pub fn head_position(views: &[XrViewSample]) -> Option<Vec3> {
+    if views.is_empty() {
+        return None;
+    }
+    let sum: Vec3 = views.iter().map(|view| view.transform.translation).sum();
+    Some(sum / views.len() as f32)
+}
+
+/// Rotates the origin around the vertical axis through the head, the user sees the world turn
+/// without their head moving
+pub fn turn_about_head(origin: &Transform, head: Vec3, angle: f32) -> Transform {
+    let pivot = origin.transform_point(head);
+    let rotation = Quat::from_rotation_y(angle);
+    Transform {
+        translation: pivot + rotation * (origin.translation - pivot),
+        rotation: (rotation * origin.rotation).normalize(),
+        scale: origin.scale,
+    }
+}
+
+/// Moves the origin so the head is above `target` and the floor of the reference space is at
+/// its height
+pub fn teleport_origin(origin: &Transform, head: Vec3, target: Vec3) -> Transform {
+    let head_offset = origin.transform_vector(vec3(head.x, 0.0, head.z));
+    Transform {
+        translation: vec3(target.x - head_offset.x, target.y, target.z - head_offset.z),
+        ..*origin
+    }
+}
+
+/// Ray cast against a horizontal floor at `height`, usable as the ground of `TeleportArc`
+pub fn floor_raycast(height: f32) -> impl Fn(&Ray) -> Option<f32> {
+    move |ray| {
+        let plane = Plane {
+            center: vec3(0.0, height, 0.0),
+            normal: Vec3::Y,
+        };
+        ray_to_plane_intersection(ray, &plane)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TeleportArcResult {
+    /// Points of the curve up to the hit, for drawing the arc
+    pub points: Vec<Vec3>,
+    pub hit: Option<Vec3>,
+}
+
+/// Parabola thrown from the controller, the first ground hit is the teleport target
+#[derive(Debug, Clone)]
+pub struct TeleportArc {
+    /// Launch speed in units per second, controls how far the arc reaches
+    pub speed: f32,
+    pub gravity: f32,
+    /// Seconds of flight covered by each segment
+    pub time_step: f32,
+    pub max_segments: usize,
+}
+
+impl TeleportArc {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            gravity: 9.81,
+            time_step: 0.05,
+            max_segments: 60,
+        }
+    }
+
+    pub fn with_segments(mut self, time_step: f32, max_segments: usize) -> Self {
+        self.time_step = time_step;
+        self.max_segments = max_segments;
+        self
+    }
+
+    pub fn point_at(&self, start: Vec3, direction: Vec3, time: f32) -> Vec3 {
+        start + direction * self.speed * time - Vec3::Y * 0.5 * self.gravity * time * time
+    }
+
+    /// Samples the arc from a world pointer ray, every segment is tested with `raycast`, which
+    /// returns the distance of the hit along the ray like `ray_to_plane_intersection`
+    pub fn compute<F: Fn(&Ray) -> Option<f32>>(
+        &self,
+        pointer: &Ray,
+        raycast: F,
+    ) -> TeleportArcResult {
+        let direction = pointer.direction.normalize_or_zero();
+        let mut points = vec![pointer.position];
+        for segment in 0..self.max_segments {
+            let start = points[points.len() - 1];
+            let end = self.point_at(
+                pointer.position,
+                direction,
+                (segment + 1) as f32 * self.time_step,
+            );
+            let length = start.distance(end);
+            if length <= f32::EPSILON {
+                break;
+            }
+            let ray = Ray {
+                position: start,
+                direction: (end - start) / length,
+                distance: Some(length),
+            };
+            if let Some(distance) = raycast(&ray).filter(|distance| *distance <= length) {
+                let hit = ray.get_position(distance);
+                points.push(hit);
+                return TeleportArcResult {
+                    points,
+                    hit: Some(hit),
+                };
+            }
+            points.push(end);
+        }
+        TeleportArcResult { points, hit: None }
+    }
+}
+
+/// Turns by a fixed angle when the thumbstick is pushed to a side, the stick has to come back
+/// to the center before the next turn
+#[derive(Debug, Clone)]
+pub struct SnapTurn {
+    pub angle: f32,
+    /// Stick deflection that triggers a turn
+    pub threshold: f32,
+    /// Stick deflection under which a new turn can be triggered
+    pub release_threshold: f32,
+    armed: bool,
+}
+
+impl SnapTurn {
+    pub fn new(angle: f32) -> Self {
+        Self {
+            angle,
+            threshold: 0.7,
+            release_threshold: 0.3,
+            armed: true,
+        }
+    }
+
+    /// Yaw to apply this frame from the horizontal stick axis, pushing right turns right
+    pub fn advance(&mut self, stick_x: f32) -> Option<f32> {
+        if stick_x.abs() < self.release_threshold {
+            self.armed = true;
+        }
+        if self.armed && stick_x.abs() >= self.threshold {
+            self.armed = false;
+            return Some(-self.angle * stick_x.signum());
+        }
+        None
+    }
+}
+
+/// Continuous turn proportional to the stick deflection
+#[derive(Debug, Clone)]
+pub struct SmoothTurn {
+    /// Radians per second at full deflection
+    pub speed: f32,
+    pub dead_zone: f32,
+}
+
+impl SmoothTurn {
+    pub fn new(speed: f32) -> Self {
+        Self {
+            speed,
+            dead_zone: 0.15,
+        }
+    }
+
+    pub fn advance(&self, stick_x: f32, delta: f32) -> f32 {
+        if stick_x.abs() <= self.dead_zone {
+            return 0.0;
+        }
+        // Rescaled so the turn starts from zero at the edge of the dead zone
+        let amount = (stick_x.abs() - self.dead_zone) / (1.0 - self.dead_zone);
+        -amount.min(1.0) * stick_x.signum() * self.speed * delta
+    }
+
+    pub fn update(&self, stick_x: f32, time: &Time) -> f32 {
+        self.advance(stick_x, time.delta_time_seconds().0 as f32)
+    }
+}
+
+/// Values for a screen space vignette shader, radii in the units of the view, 1 is the edge
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VignetteParameters {
+    /// Between 0 and 1, 0 disables the effect
+    pub intensity: f32,
+    /// Fully visible inside this radius
+    pub inner_radius: f32,
+    /// Fully dark outside this radius
+    pub outer_radius: f32,
+}
+
+/// Narrows the view while the user moves or turns artificially, reducing motion sickness
+#[derive(Debug, Clone)]
+pub struct ComfortVignette {
+    pub max_intensity: f32,
+    /// Speeds that reach the full intensity
+    pub max_linear_speed: f32,
+    pub max_angular_speed: f32,
+    /// Inner radius at full intensity, the outer radius keeps the same distance to it
+    pub closed_radius: f32,
+    pub feather: f32,
+    /// How fast the intensity follows the movement, per second
+    pub sharpness: f32,
+    intensity: f32,
+}
+
+impl ComfortVignette {
+    pub fn new() -> Self {
+        Self {
+            max_intensity: 1.0,
+            max_linear_speed: 3.0,
+            max_angular_speed: std::f32::consts::FRAC_PI_2,
+            closed_radius: 0.45,
+            feather: 0.25,
+            sharpness: 8.0,
+            intensity: 0.0,
+        }
+    }
+
+    /// Artificial movement of the origin this frame, head movement should not be included
+    pub fn advance(&mut self, linear_speed: f32, angular_speed: f32, delta: f32) {
+        let target = (linear_speed.abs() / self.max_linear_speed)
+            .max(angular_speed.abs() / self.max_angular_speed)
+            .clamp(0.0, 1.0)
+            * self.max_intensity;
+        let t = 1.0 - (-self.sharpness * delta).exp();
+        self.intensity += (target - self.intensity) * t;
+    }
+
+    pub fn update(&mut self, linear_speed: f32, angular_speed: f32, time: &Time) {
+        self.advance(
+            linear_speed,
+            angular_speed,
+            time.delta_time_seconds().0 as f32,
+        );
+    }
+
+    /// A snap turn or teleport darkens the view at once, it fades out with the next updates
+    pub fn flash(&mut self) {
+        self.intensity = self.max_intensity;
+    }
+
+    pub fn parameters(&self) -> VignetteParameters {
+        let inner_radius = 1.0 + (self.closed_radius - 1.0) * self.intensity;
+        VignetteParameters {
+            intensity: self.intensity,
+            inner_radius,
+            outer_radius: inner_radius + self.feather,
+        }
+    }
+}
+
+impl Default for ComfortVignette {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,22 @@
+mod views;
+pub use views::*;
+
+mod input;
+pub use input::*;
+
+mod locomotion;
+pub use locomotion::*;
+
+mod web;
+pub use web::*;
+
+mod test;
+
+/// Views and inputs of one XR frame.\
+/// Implemented by `WebXrFrame` for a running session, tests and replays can provide their own
+/// frames.
+pub trait XrFrameSource {
+    /// Empty when the viewer pose is not tracked
+    fn views(&self) -> Vec<XrViewSample>;
+    fn input_sources(&self) -> Vec<XrInputState>;
+}
@@ -0,0 +1,249 @@
+#[cfg(test)]
+mod tests {
+    use glam::*;
+
+    use super::super::*;
+    use crate::collision::Ray;
+    use crate::transform::Transform;
+
+    struct FakeFrame {
+        views: Vec<XrViewSample>,
+        inputs: Vec<XrInputState>,
+    }
+
+    impl XrFrameSource for FakeFrame {
+        fn views(&self) -> Vec<XrViewSample> {
+            self.views.clone()
+        }
+
+        fn input_sources(&self) -> Vec<XrInputState> {
+            self.inputs.clone()
+        }
+    }
+
+    fn eye_view(eye: Eye, x_offset: f32, viewport_x: u32) -> XrViewSample {
+        XrViewSample {
+            eye,
+            projection_matrix: Mat4::perspective_rh_gl(1.6, 1.0, 0.1, 100.0),
+            transform: Transform::from_translation(vec3(x_offset, 1.6, 0.0)),
+            viewport_position: uvec2(viewport_x, 0),
+            viewport_size: uvec2(1000, 1000),
+        }
+    }
+
+    fn stereo_frame() -> FakeFrame {
+        FakeFrame {
+            views: vec![
+                eye_view(Eye::Left, -0.032, 0),
+                eye_view(Eye::Right, 0.032, 1000),
+            ],
+            inputs: Vec::new(),
+        }
+    }
+
+    fn controller(handedness: Handedness, trigger: bool) -> XrInputState {
+        let mut state = XrInputState::new(handedness, TargetRayMode::TrackedPointer);
+        state.target_ray = Some(Transform::from_translation(vec3(0.2, 1.0, -0.3)));
+        state.grip = Some(Transform::from_translation(vec3(0.2, 0.9, -0.3)));
+        state.buttons = vec![
+            XrButtonState {
+                pressed: trigger,
+                touched: trigger,
+                value: if trigger { 1.0 } else { 0.0 },
+            },
+            XrButtonState::default(),
+        ];
+        state.axes = vec![0.0, 0.0, 0.5, -1.0];
+        state
+    }
+
+    #[test]
+    fn stereo_views_produce_per_eye_view_data() {
+        let frame = stereo_frame();
+        let origin = Transform::from_translation(vec3(10.0, 0.0, 0.0));
+        let views = views_to_view_data(&frame.views(), &origin);
+        assert_eq!(views.len(), 2);
+        assert_eq!(views[0].viewport.position, uvec2(0, 0));
+        assert_eq!(views[1].viewport.position, uvec2(1000, 0));
+
+        let left = views[0].camera_matrices.transform_matrix.w_axis.truncate();
+        let right = views[1].camera_matrices.transform_matrix.w_axis.truncate();
+        assert!(left.abs_diff_eq(vec3(9.968, 1.6, 0.0), 1e-5));
+        assert!((right - left).abs_diff_eq(vec3(0.064, 0.0, 0.0), 1e-5));
+        assert_eq!(views[0].camera_matrices.viewport_size, vec2(1000.0, 1000.0));
+
+        // A point straight ahead of the head lands on either side of the eye centers
+        let point = vec3(10.0, 1.6, -2.0);
+        let left_ndc = views[0]
+            .camera_matrices
+            .projection_view_matrix
+            .project_point3(point);
+        let right_ndc = views[1]
+            .camera_matrices
+            .projection_view_matrix
+            .project_point3(point);
+        assert!(left_ndc.x > 0.0 && right_ndc.x < 0.0);
+    }
+
+    #[test]
+    fn converts_matrices_and_rigid_transforms_safely() {
+        let matrix = Mat4::from_translation(vec3(1.0, 2.0, 3.0));
+        assert_eq!(mat4_from_slice(&matrix.to_cols_array()), Some(matrix));
+        assert_eq!(mat4_from_slice(&[0.0; 12]), None);
+        let mut values = matrix.to_cols_array();
+        values[3] = f32::NAN;
+        assert_eq!(mat4_from_slice(&values), None);
+
+        let transform = rigid_transform([2.0, 4.0, 6.0, 2.0], [0.0, 0.0, 0.0, 2.0]).unwrap();
+        assert_eq!(transform.translation, vec3(1.0, 2.0, 3.0));
+        assert_eq!(transform.rotation, Quat::IDENTITY);
+        assert!(rigid_transform([0.0, 0.0, 0.0, 1.0], [0.0; 4]).is_none());
+        assert!(rigid_transform([0.0, 0.0, 0.0, 0.0], [0.0, 0.0, 0.0, 1.0]).is_none());
+        assert!(rigid_transform([f64::INFINITY, 0.0, 0.0, 1.0], [0.0, 0.0, 0.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn input_tracks_presses_between_frames() {
+        let mut input = XrInput::new();
+        let mut frame = FakeFrame {
+            views: Vec::new(),
+            inputs: vec![
+                controller(Handedness::Left, false),
+                controller(Handedness::Right, false),
+            ],
+        };
+        input.update(&frame);
+        assert!(!input.is_pressed(Handedness::Right, XrButton::Trigger));
+
+        frame.inputs[1] = controller(Handedness::Right, true);
+        input.update(&frame);
+        assert!(input.just_pressed(Handedness::Right, XrButton::Trigger));
+        assert!(!input.just_pressed(Handedness::Left, XrButton::Trigger));
+        assert_eq!(
+            input.button_value(Handedness::Right, XrButton::Trigger),
+            1.0
+        );
+        // Buttons missing from the gamepad read as released
+        assert!(!input.is_pressed(Handedness::Right, XrButton::Secondary));
+
+        input.update(&frame);
+        assert!(input.is_pressed(Handedness::Right, XrButton::Trigger));
+        assert!(!input.just_pressed(Handedness::Right, XrButton::Trigger));
+
+        // Disconnecting a controller releases its buttons
+        frame.inputs.pop();
+        input.update(&frame);
+        assert!(input.just_released(Handedness::Right, XrButton::Trigger));
+        assert!(input.source(Handedness::Right).is_none());
+        assert_eq!(input.thumbstick(Handedness::Left), vec2(0.5, -1.0));
+    }
+
+    #[test]
+    fn pointer_ray_follows_target_ray_pose() {
+        let mut state = controller(Handedness::Right, false);
+        assert_eq!(
+            state.grip,
+            Some(Transform::from_translation(vec3(0.2, 0.9, -0.3)))
+        );
+        state.target_ray = Some(Transform {
+            translation: vec3(0.0, 1.0, 0.0),
+            rotation: Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            scale: Vec3::ONE,
+        });
+        let origin = Transform::from_translation(vec3(5.0, 0.0, 0.0));
+        let ray = state.pointer_ray(&origin).unwrap();
+        assert!(ray.position.abs_diff_eq(vec3(5.0, 1.0, 0.0), 1e-6));
+        assert!(ray.direction.abs_diff_eq(Vec3::NEG_X, 1e-6));
+
+        state.target_ray = None;
+        assert!(state.pointer_ray(&origin).is_none());
+    }
+
+    #[test]
+    fn teleport_arc_lands_on_the_floor() {
+        let arc = TeleportArc::new(6.0);
+        let pointer = Ray {
+            position: vec3(0.0, 1.0, 0.0),
+            direction: vec3(0.0, 1.0, -1.0).normalize(),
+            distance: None,
+        };
+        let result = arc.compute(&pointer, floor_raycast(0.0));
+        let hit = result.hit.unwrap();
+        assert_eq!(result.points.last(), Some(&hit));
+        assert!(hit.y.abs() < 1e-4);
+
+        // Analytic landing point of the parabola
+        let velocity = pointer.direction * arc.speed;
+        let time =
+            (velocity.y + (velocity.y * velocity.y + 2.0 * arc.gravity).sqrt()) / arc.gravity;
+        assert!((hit.z - velocity.z * time).abs() < 0.05);
+
+        // Too short to reach a floor far below
+        let short = arc.clone().with_segments(0.05, 4);
+        let result = short.compute(&pointer, floor_raycast(-100.0));
+        assert!(result.hit.is_none());
+        assert_eq!(result.points.len(), 5);
+    }
+
+    #[test]
+    fn turning_keeps_the_head_in_place() {
+        let origin = Transform {
+            translation: vec3(3.0, 0.0, -2.0),
+            rotation: Quat::from_rotation_y(0.4),
+            scale: Vec3::ONE,
+        };
+        let head = head_position(&stereo_frame().views()).unwrap();
+        assert!(head.abs_diff_eq(vec3(0.0, 1.6, 0.0), 1e-6));
+        let head = vec3(0.3, 1.6, 0.5);
+        let turned = turn_about_head(&origin, head, std::f32::consts::FRAC_PI_4);
+        assert!(turned
+            .transform_point(head)
+            .abs_diff_eq(origin.transform_point(head), 1e-5));
+        let forward = turned.transform_vector(Vec3::NEG_Z);
+        let expected = Quat::from_rotation_y(0.4 + std::f32::consts::FRAC_PI_4) * Vec3::NEG_Z;
+        assert!(forward.abs_diff_eq(expected, 1e-5));
+
+        let target = vec3(10.0, 0.5, 4.0);
+        let moved = teleport_origin(&origin, head, target);
+        let head_world = moved.transform_point(head);
+        assert!(head_world.abs_diff_eq(vec3(10.0, 2.1, 4.0), 1e-5));
+        assert_eq!(moved.rotation, origin.rotation);
+    }
+
+    #[test]
+    fn snap_turn_needs_the_stick_released() {
+        let mut snap = SnapTurn::new(std::f32::consts::FRAC_PI_4);
+        assert_eq!(snap.advance(0.5), None);
+        assert_eq!(snap.advance(0.9), Some(-std::f32::consts::FRAC_PI_4));
+        assert_eq!(snap.advance(1.0), None);
+        assert_eq!(snap.advance(0.5), None);
+        assert_eq!(snap.advance(0.1), None);
+        assert_eq!(snap.advance(-0.8), Some(std::f32::consts::FRAC_PI_4));
+
+        let smooth = SmoothTurn::new(2.0);
+        assert_eq!(smooth.advance(0.1, 0.5), 0.0);
+        assert!((smooth.advance(1.0, 0.5) + 1.0).abs() < 1e-6);
+        assert!(smooth.advance(-0.5, 0.5) > 0.0);
+    }
+
+    #[test]
+    fn vignette_follows_artificial_movement() {
+        let mut vignette = ComfortVignette::new();
+        assert_eq!(vignette.parameters().intensity, 0.0);
+        assert_eq!(vignette.parameters().inner_radius, 1.0);
+        for _ in 0..60 {
+            vignette.advance(3.0, 0.0, 1.0 / 60.0);
+        }
+        let moving = vignette.parameters();
+        assert!(moving.intensity > 0.99);
+        assert!((moving.inner_radius - vignette.closed_radius).abs() < 0.01);
+        assert!((moving.outer_radius - moving.inner_radius - vignette.feather).abs() < 1e-6);
+
+        for _ in 0..60 {
+            vignette.advance(0.0, 0.0, 1.0 / 60.0);
+        }
+        assert!(vignette.parameters().intensity < 0.01);
+        vignette.flash();
+        assert_eq!(vignette.parameters().intensity, 1.0);
+    }
+}
@@ -0,0 +1,88 @@
+use glam::*;
+use rust_webgl2::Viewport;
+
+use crate::camera::{CameraMatrices, ViewData};
+use crate::transform::Transform;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Eye {
+    Left,
+    Right,
+    /// Single view devices like phones in immersive AR
+    None,
+}
+
+/// One view of the viewer pose, a stereo headset reports one per eye
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct XrViewSample {
+    pub eye: Eye,
+    pub projection_matrix: Mat4,
+    /// Pose of the eye in the reference space
+    pub transform: Transform,
+    /// Region of the XR framebuffer the view renders to, with the `Viewport` convention
+    pub viewport_position: UVec2,
+    pub viewport_size: UVec2,
+}
+
+/// Column major matrix from a WebXR array, `None` when it doesn't have 16 finite values
+pub fn mat4_from_slice(values: &[f32]) -> Option<Mat4> {
+    if values.len() != 16 || values.iter().any(|value| !value.is_finite()) {
+        return None;
+    }
+    Some(Mat4::from_cols_slice(values))
+}
+
+/// Converts the position and orientation of a rigid transform.\
+/// The position is divided by its w component and the orientation is normalized, `None` when
+/// a value is not finite or the orientation has no length.
+pub fn rigid_transform(position: [f64; 4], orientation: [f64; 4]) -> Option<Transform> {
+    if position
+        .iter()
+        .chain(orientation.iter())
+        .any(|value| !value.is_finite())
+    {
+        return None;
+    }
+    let w = position[3] as f32;
+    if w.abs() <= f32::EPSILON {
+        return None;
+    }
+    let translation = vec3(position[0] as f32, position[1] as f32, position[2] as f32) / w;
+    let rotation = Vec4::new(
+        orientation[0] as f32,
+        orientation[1] as f32,
+        orientation[2] as f32,
+        orientation[3] as f32,
+    )
+    .try_normalize()?;
+    Some(Transform {
+        translation,
+        rotation: Quat::from_vec4(rotation),
+        scale: Vec3::ONE,
+    })
+}
+
+impl XrViewSample {
+    /// Camera matrices of the view with the reference space placed at `origin`
+    pub fn camera_matrices(&self, origin: &Transform) -> CameraMatrices {
+        let transform_matrix = origin.to_mat4() * self.transform.to_mat4();
+        let mut camera_matrices = CameraMatrices::new(transform_matrix, self.projection_matrix);
+        camera_matrices.viewport_size = self.viewport_size.as_vec2();
+        camera_matrices
+    }
+
+    pub fn view_data(&self, origin: &Transform) -> ViewData {
+        ViewData {
+            camera_matrices: self.camera_matrices(origin),
+            viewport: Viewport {
+                position: self.viewport_position,
+                size: self.viewport_size,
+            },
+        }
+    }
+}
+
+/// View data of every view in the order reported by the session, left eye first on headsets
+pub fn views_to_view_data(views: &[XrViewSample], origin: &Transform) -> Vec<ViewData> {
+    views.iter().map(|view| view.view_data(origin)).collect()
+}
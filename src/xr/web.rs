@@ -0,0 +1,128 @@
+use glam::*;
+use wasm_bindgen::JsCast;
+use web_sys::{
+    DomPointReadOnly, Gamepad, GamepadButton, XrEye, XrFrame, XrHandedness, XrInputSource,
+    XrReferenceSpace, XrRigidTransform, XrSpace, XrTargetRayMode, XrView, XrWebGlLayer,
+};
+
+use super::*;
+use crate::transform::Transform;
+
+fn point_values(point: &DomPointReadOnly) -> [f64; 4] {
+    [point.x(), point.y(), point.z(), point.w()]
+}
+
+pub fn from_xr_rigid_transform(transform: &XrRigidTransform) -> Option<Transform> {
+    rigid_transform(
+        point_values(&transform.position()),
+        point_values(&transform.orientation()),
+    )
+}
+
+/// Frame of a running WebXR session, read inside the `requestAnimationFrame` callback
+pub struct WebXrFrame<'a> {
+    pub frame: &'a XrFrame,
+    pub reference_space: &'a XrReferenceSpace,
+    pub layer: &'a XrWebGlLayer,
+}
+
+impl<'a> WebXrFrame<'a> {
+    pub fn new(
+        frame: &'a XrFrame,
+        reference_space: &'a XrReferenceSpace,
+        layer: &'a XrWebGlLayer,
+    ) -> Self {
+        Self {
+            frame,
+            reference_space,
+            layer,
+        }
+    }
+
+    fn pose(&self, space: &XrSpace) -> Option<Transform> {
+        let pose = self.frame.get_pose(space, self.reference_space)?;
+        from_xr_rigid_transform(&pose.transform())
+    }
+
+    fn view_sample(&self, view: &XrView) -> Option<XrViewSample> {
+        let eye = match view.eye() {
+            XrEye::Left => Eye::Left,
+            XrEye::Right => Eye::Right,
+            _ => Eye::None,
+        };
+        let viewport = self.layer.get_viewport(view)?;
+        Some(XrViewSample {
+            eye,
+            projection_matrix: mat4_from_slice(&view.projection_matrix())?,
+            transform: from_xr_rigid_transform(&view.transform())?,
+            viewport_position: uvec2(viewport.x().max(0) as u32, viewport.y().max(0) as u32),
+            viewport_size: uvec2(
+                viewport.width().max(0) as u32,
+                viewport.height().max(0) as u32,
+            ),
+        })
+    }
+
+    fn input_state(&self, source: &XrInputSource) -> XrInputState {
+        let handedness = match source.handedness() {
+            XrHandedness::Left => Handedness::Left,
+            XrHandedness::Right => Handedness::Right,
+            _ => Handedness::None,
+        };
+        let target_ray_mode = match source.target_ray_mode() {
+            XrTargetRayMode::Gaze => TargetRayMode::Gaze,
+            XrTargetRayMode::Screen => TargetRayMode::Screen,
+            _ => TargetRayMode::TrackedPointer,
+        };
+        let mut state = XrInputState::new(handedness, target_ray_mode);
+        state.target_ray = self.pose(&source.target_ray_space());
+        state.grip = source.grip_space().and_then(|grip| self.pose(&grip));
+        if let Some(gamepad) = source.gamepad() {
+            read_gamepad(&gamepad, &mut state);
+        }
+        state
+    }
+}
+
+fn read_gamepad(gamepad: &Gamepad, state: &mut XrInputState) {
+    state.buttons = gamepad
+        .buttons()
+        .iter()
+        .map(|button| match button.dyn_into::<GamepadButton>() {
+            Ok(button) => XrButtonState {
+                pressed: button.pressed(),
+                touched: button.touched(),
+                value: button.value() as f32,
+            },
+            Err(_) => XrButtonState::default(),
+        })
+        .collect();
+    state.axes = gamepad
+        .axes()
+        .iter()
+        .map(|axis| axis.as_f64().unwrap_or(0.0) as f32)
+        .collect();
+}
+
+impl<'a> XrFrameSource for WebXrFrame<'a> {
+    fn views(&self) -> Vec<XrViewSample> {
+        let viewer_pose = match self.frame.get_viewer_pose(self.reference_space) {
+            Some(viewer_pose) => viewer_pose,
+            None => return Vec::new(),
+        };
+        viewer_pose
+            .views()
+            .iter()
+            .filter_map(|view| view.dyn_into::<XrView>().ok())
+            .filter_map(|view| self.view_sample(&view))
+            .collect()
+    }
+
+    fn input_sources(&self) -> Vec<XrInputState> {
+        let sources = self.frame.session().input_sources();
+        (0..sources.length())
+            .filter_map(|index| sources.get(index))
+            .map(|source| self.input_state(&source))
+            .collect()
+    }
+}